@@ -0,0 +1,270 @@
+//! Minimal DEFLATE (RFC 1951) decoder.
+//!
+//! Deflate streams can not be seeked. Reading a range of an entry therefore
+//! always decompresses everything in front of it.
+
+use std::io;
+
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> io::Result<u32> {
+        while self.count < count {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| invalid_data("unexpected end of deflate stream"))?;
+
+            self.buffer |= (byte as u32) << self.count;
+            self.position += 1;
+            self.count += 8;
+        }
+
+        let value = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    fn bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or_else(|| invalid_data("unexpected end of deflate stream"))?;
+
+        self.position += count;
+        Ok(bytes)
+    }
+}
+
+/// Canonical huffman code, decoded bit by bit.
+struct Huffman {
+    count: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut count = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            count[length as usize] += 1;
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for length in 1..MAX_BITS {
+            offsets[length + 1] = offsets[length] + count[length];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Self { count, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> io::Result<usize> {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+        for length in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+
+            let count = self.count[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize] as usize);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(invalid_data("invalid huffman code"))
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[0..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..288].fill(8);
+
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let length_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_count = reader.bits(4)? as usize + 4;
+    if length_count > 286 || distance_count > 30 {
+        return Err(invalid_data("invalid dynamic block header"));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[0..code_count] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = vec![0u8; length_count + distance_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let symbol = code_lengths.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *index
+                    .checked_sub(1)
+                    .and_then(|previous| lengths.get(previous))
+                    .ok_or_else(|| invalid_data("repeat without previous length"))?;
+
+                (previous, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+
+        if index + repeat > lengths.len() {
+            return Err(invalid_data("too many code lengths"));
+        }
+
+        lengths[index..index + repeat].fill(value);
+        index += repeat;
+    }
+
+    Ok((
+        Huffman::new(&lengths[0..length_count]),
+        Huffman::new(&lengths[length_count..]),
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    limit: usize,
+    lengths: &Huffman,
+    distances: &Huffman,
+) -> io::Result<()> {
+    while output.len() < limit {
+        let symbol = lengths.decode(reader)?;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => break,
+            _ => {
+                let symbol = symbol - 257;
+                if symbol >= LENGTH_BASE.len() {
+                    return Err(invalid_data("invalid length symbol"));
+                }
+                let length = LENGTH_BASE[symbol] as usize
+                    + reader.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+
+                let symbol = distances.decode(reader)?;
+                if symbol >= DISTANCE_BASE.len() {
+                    return Err(invalid_data("invalid distance symbol"));
+                }
+                let distance = DISTANCE_BASE[symbol] as usize
+                    + reader.bits(DISTANCE_EXTRA[symbol] as u32)? as usize;
+                if distance > output.len() {
+                    return Err(invalid_data("distance too far back"));
+                }
+
+                /* The referenced range may overlap with the bytes being copied. */
+                let start = output.len() - distance;
+                for offset in 0..length {
+                    output.push(output[start + offset]);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Decompress `data` until at least `limit` bytes have been produced
+/// or the end of the stream has been reached.
+pub fn inflate(data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::with_capacity(limit.min(16 * 1024 * 1024));
+
+    while output.len() < limit {
+        let last_block = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_to_byte();
+
+                let header = reader.bytes(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                let inverted_length = u16::from_le_bytes([header[2], header[3]]);
+                if length != !inverted_length {
+                    return Err(invalid_data("invalid stored block length"));
+                }
+
+                output.extend_from_slice(reader.bytes(length as usize)?);
+            }
+            1 => {
+                let (lengths, distances) = fixed_codes();
+                inflate_block(&mut reader, &mut output, limit, &lengths, &distances)?;
+            }
+            2 => {
+                let (lengths, distances) = dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut output, limit, &lengths, &distances)?;
+            }
+            _ => return Err(invalid_data("invalid block type")),
+        }
+
+        if last_block {
+            break;
+        }
+    }
+
+    Ok(output)
+}
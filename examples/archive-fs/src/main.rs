@@ -0,0 +1,215 @@
+use std::{
+    collections::{
+        hash_map::Entry,
+        HashMap,
+    },
+    fs::File,
+    io::{
+        self,
+        Cursor,
+        Read,
+        Seek,
+        SeekFrom,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use anyhow::Context;
+use clap::Parser;
+use windows_projfs::{
+    DirectoryEntry,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+    ProjectionMode,
+};
+use zip::{
+    CompressionMethod,
+    ZipEntry,
+};
+
+mod inflate;
+mod zip;
+
+/// Case insensitive lookup key of an archive path.
+fn entry_key<'a>(components: impl IntoIterator<Item = &'a str>) -> String {
+    components
+        .into_iter()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn path_key(path: &Path) -> String {
+    entry_key(
+        path.components()
+            .filter_map(|component| component.as_os_str().to_str()),
+    )
+}
+
+/// Projects the contents of a zip archive.
+/// Files are decompressed on demand when ProjFS requests their contents.
+struct ArchiveProjectedSource {
+    archive_path: PathBuf,
+    directories: HashMap<String, Vec<DirectoryEntry>>,
+    files: HashMap<String, ZipEntry>,
+}
+
+impl ArchiveProjectedSource {
+    fn open(archive_path: PathBuf) -> io::Result<Self> {
+        let entries = zip::read_entries(&mut File::open(&archive_path)?)?;
+
+        let mut directories = HashMap::new();
+        let mut files = HashMap::new();
+        directories.insert(String::new(), Vec::new());
+
+        for entry in entries {
+            let components = entry
+                .name
+                .split('/')
+                .filter(|component| !component.is_empty())
+                .collect::<Vec<_>>();
+
+            if components.contains(&"..") {
+                log::warn!(
+                    "Skipping entry {} pointing outside of the archive",
+                    entry.name
+                );
+                continue;
+            }
+
+            /* Archives do not need to contain entries for every directory. */
+            for (index, name) in components.iter().enumerate() {
+                let parent_key = entry_key(components[0..index].iter().copied());
+                let key = entry_key(components[0..=index].iter().copied());
+                let is_file = index + 1 == components.len() && !entry.is_directory();
+
+                if is_file {
+                    if let Some(parent) = directories.get_mut(&parent_key) {
+                        parent.push(DirectoryEntry::file(*name, entry.uncompressed_size));
+                    }
+                    files.insert(key, entry.clone());
+                } else if let Entry::Vacant(directory) = directories.entry(key) {
+                    directory.insert(Vec::new());
+                    if let Some(parent) = directories.get_mut(&parent_key) {
+                        parent.push(DirectoryEntry::directory(*name));
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            archive_path,
+            directories,
+            files,
+        })
+    }
+}
+
+impl ProjectedFileSystemSource for ArchiveProjectedSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        self.directories
+            .get(&path_key(path))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        let entry = self
+            .files
+            .get(&path_key(path))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "entry does not exists"))?;
+
+        let read_end = byte_offset + length;
+        if read_end as u64 > entry.uncompressed_size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "invalid read operation",
+            ));
+        }
+
+        let mut archive = File::open(&self.archive_path)?;
+        let data_offset = entry.data_offset(&mut archive)?;
+        match entry.method {
+            CompressionMethod::Stored => {
+                archive.seek(SeekFrom::Start(data_offset + byte_offset as u64))?;
+                Ok(Box::new(archive.take(length as u64)))
+            }
+            CompressionMethod::Deflated => {
+                /*
+                 * Deflate streams can not be seeked.
+                 * Everything in front of the requested range must be decompressed as well.
+                 */
+                let mut compressed = Vec::with_capacity(entry.compressed_size as usize);
+                archive.seek(SeekFrom::Start(data_offset))?;
+                archive
+                    .take(entry.compressed_size)
+                    .read_to_end(&mut compressed)?;
+
+                log::debug!(
+                    "Decompressing {} bytes of {} for reading {} bytes",
+                    read_end,
+                    entry.name,
+                    length
+                );
+                let mut content = inflate::inflate(&compressed, read_end)?;
+                if content.len() < read_end {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "compressed entry is too short",
+                    ));
+                }
+
+                content.truncate(read_end);
+                content.drain(0..byte_offset);
+                Ok(Box::new(Cursor::new(content)))
+            }
+        }
+    }
+}
+
+#[derive(clap::Parser)]
+struct Args {
+    #[clap(short, long)]
+    projection_root: PathBuf,
+
+    #[clap(short, long)]
+    archive: PathBuf,
+}
+
+fn pause() {
+    log::info!("Press any key to continue...");
+    let mut stdin = io::stdin();
+    let _ = stdin.read(&mut [0u8]).unwrap();
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    env_logger::init();
+
+    let source = ArchiveProjectedSource::open(args.archive.clone())
+        .with_context(|| format!("failed to open {}", args.archive.display()))?;
+
+    log::info!(
+        "Projecting {} at {}",
+        args.archive.display(),
+        args.projection_root.display()
+    );
+    {
+        let _pfs = ProjectedFileSystem::builder()
+            .remove_root_on_drop(true)
+            .mode(ProjectionMode::ReadOnly)
+            .build(&args.projection_root, source)?;
+        pause();
+    }
+
+    log::info!("Stopped projection.");
+    Ok(())
+}
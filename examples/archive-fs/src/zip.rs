@@ -0,0 +1,185 @@
+//! Reader for the central directory of zip archives.
+//!
+//! Only stored and deflated entries are supported. Zip64 archives,
+//! encrypted entries and multi-disk archives are rejected.
+
+use std::{
+    fs::File,
+    io::{
+        self,
+        Read,
+        Seek,
+        SeekFrom,
+    },
+};
+
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const CENTRAL_DIRECTORY_HEADER_SIZE: usize = 46;
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const LOCAL_HEADER_SIZE: usize = 30;
+
+const FLAG_ENCRYPTED: u16 = 0x0001;
+const FLAG_UTF8: u16 = 0x0800;
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buffer[offset], buffer[offset + 1]])
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        buffer[offset],
+        buffer[offset + 1],
+        buffer[offset + 2],
+        buffer[offset + 3],
+    ])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionMethod {
+    Stored,
+    Deflated,
+}
+
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    /// Name of the entry within the archive using `/` as separator.
+    /// Directory entries end with a `/`.
+    pub name: String,
+    pub method: CompressionMethod,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    local_header_offset: u64,
+}
+
+impl ZipEntry {
+    pub fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    /// Offset of the entry data within the archive.
+    pub fn data_offset(&self, archive: &mut File) -> io::Result<u64> {
+        let mut header = [0u8; LOCAL_HEADER_SIZE];
+        archive.seek(SeekFrom::Start(self.local_header_offset))?;
+        archive.read_exact(&mut header)?;
+        if read_u32(&header, 0) != LOCAL_HEADER_SIGNATURE {
+            return Err(invalid_data(format!(
+                "invalid local header for {}",
+                self.name
+            )));
+        }
+
+        let name_length = read_u16(&header, 26) as u64;
+        let extra_length = read_u16(&header, 28) as u64;
+        Ok(self.local_header_offset + LOCAL_HEADER_SIZE as u64 + name_length + extra_length)
+    }
+}
+
+fn find_end_of_central_directory(archive: &mut File) -> io::Result<Vec<u8>> {
+    let archive_size = archive.seek(SeekFrom::End(0))?;
+
+    /* The record is followed by a comment of up to 64 KiB. */
+    let search_size = archive_size.min((END_OF_CENTRAL_DIRECTORY_SIZE + u16::MAX as usize) as u64);
+    let mut buffer = vec![0u8; search_size as usize];
+    archive.seek(SeekFrom::Start(archive_size - search_size))?;
+    archive.read_exact(&mut buffer)?;
+
+    if buffer.len() < END_OF_CENTRAL_DIRECTORY_SIZE {
+        return Err(invalid_data("archive is too small"));
+    }
+
+    let position = (0..=buffer.len() - END_OF_CENTRAL_DIRECTORY_SIZE)
+        .rev()
+        .find(|&position| read_u32(&buffer, position) == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+        .ok_or_else(|| invalid_data("missing end of central directory record"))?;
+
+    Ok(buffer[position..position + END_OF_CENTRAL_DIRECTORY_SIZE].to_vec())
+}
+
+/// Read all entries listed in the central directory of `archive`.
+pub fn read_entries(archive: &mut File) -> io::Result<Vec<ZipEntry>> {
+    let record = find_end_of_central_directory(archive)?;
+    if read_u16(&record, 4) != 0 || read_u16(&record, 6) != 0 {
+        return Err(invalid_data("multi-disk archives are not supported"));
+    }
+
+    let entry_count = read_u16(&record, 10) as usize;
+    let directory_size = read_u32(&record, 12);
+    let directory_offset = read_u32(&record, 16);
+    if directory_size == u32::MAX || directory_offset == u32::MAX {
+        return Err(invalid_data("zip64 archives are not supported"));
+    }
+
+    let mut directory = vec![0u8; directory_size as usize];
+    archive.seek(SeekFrom::Start(directory_offset as u64))?;
+    archive.read_exact(&mut directory)?;
+
+    let mut entries = Vec::with_capacity(entry_count);
+    let mut offset = 0;
+    for _ in 0..entry_count {
+        if offset + CENTRAL_DIRECTORY_HEADER_SIZE > directory.len()
+            || read_u32(&directory, offset) != CENTRAL_DIRECTORY_SIGNATURE
+        {
+            return Err(invalid_data("invalid central directory header"));
+        }
+
+        let header = &directory[offset..];
+        let flags = read_u16(header, 8);
+        let method = read_u16(header, 10);
+        let compressed_size = read_u32(header, 20);
+        let uncompressed_size = read_u32(header, 24);
+        let name_length = read_u16(header, 28) as usize;
+        let extra_length = read_u16(header, 30) as usize;
+        let comment_length = read_u16(header, 32) as usize;
+        let local_header_offset = read_u32(header, 42);
+
+        let name = header
+            .get(CENTRAL_DIRECTORY_HEADER_SIZE..CENTRAL_DIRECTORY_HEADER_SIZE + name_length)
+            .ok_or_else(|| invalid_data("truncated central directory"))?;
+        let name = if flags & FLAG_UTF8 > 0 {
+            String::from_utf8_lossy(name).into_owned()
+        } else {
+            /* CP437 names are only mapped correctly for ASCII characters. */
+            name.iter().map(|&byte| byte as char).collect()
+        };
+
+        offset += CENTRAL_DIRECTORY_HEADER_SIZE + name_length + extra_length + comment_length;
+
+        if flags & FLAG_ENCRYPTED > 0 {
+            log::warn!("Skipping encrypted entry {}", name);
+            continue;
+        }
+
+        if [compressed_size, uncompressed_size, local_header_offset].contains(&u32::MAX) {
+            return Err(invalid_data("zip64 archives are not supported"));
+        }
+
+        let method = match method {
+            0 => CompressionMethod::Stored,
+            8 => CompressionMethod::Deflated,
+            method => {
+                log::warn!(
+                    "Skipping entry {} with unsupported compression method {}",
+                    name,
+                    method
+                );
+                continue;
+            }
+        };
+
+        entries.push(ZipEntry {
+            name: name.replace('\\', "/"),
+            method,
+            compressed_size: compressed_size as u64,
+            uncompressed_size: uncompressed_size as u64,
+            local_header_offset: local_header_offset as u64,
+        });
+    }
+
+    Ok(entries)
+}
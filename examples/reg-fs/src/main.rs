@@ -1,5 +1,4 @@
 use std::{
-    fs,
     io::{
         self,
         Cursor,
@@ -11,8 +10,6 @@ use std::{
 use clap::Parser;
 use windows_projfs::{
     DirectoryEntry,
-    DirectoryInfo,
-    FileInfo,
     ProjectedFileSystem,
     ProjectedFileSystemSource,
 };
@@ -32,25 +29,15 @@ impl ProjectedFileSystemSource for RegistryProjectedSource {
             Err(_) => return vec![],
         };
 
-        let directories = key.enum_keys().filter_map(|key| key.ok()).map(|name| {
-            DirectoryEntry::Directory(DirectoryInfo {
-                directory_name: name,
-
-                ..Default::default()
-            })
-        });
+        let directories = key
+            .enum_keys()
+            .filter_map(|key| key.ok())
+            .map(DirectoryEntry::directory);
 
         let files = key
             .enum_values()
             .filter_map(|value| value.ok())
-            .map(|(name, key)| {
-                DirectoryEntry::File(FileInfo {
-                    file_name: name,
-                    file_size: key.bytes.len() as u64,
-
-                    ..Default::default()
-                })
-            });
+            .map(|(name, key)| DirectoryEntry::file(name, key.bytes.len() as u64));
 
         directories.chain(files).collect()
     }
@@ -106,15 +93,6 @@ fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     env_logger::init();
 
-    if args.projection_root.exists() {
-        log::error!("Target path does already exists.");
-        log::error!("The root path should not be existent.");
-        return Ok(());
-    } else {
-        log::debug!("Creating {}", args.projection_root.display());
-        fs::create_dir_all(&args.projection_root)?;
-    }
-
     log::info!(
         "Starting projected registry at {}",
         args.projection_root.display()
@@ -122,13 +100,12 @@ fn main() -> anyhow::Result<()> {
     {
         let root_key = RegKey::predef(HKEY_LOCAL_MACHINE);
 
-        let _pfs =
-            ProjectedFileSystem::new(&args.projection_root, RegistryProjectedSource { root_key })?;
+        let _pfs = ProjectedFileSystem::builder()
+            .remove_root_on_drop(true)
+            .build(&args.projection_root, RegistryProjectedSource { root_key })?;
         pause();
     }
 
-    log::info!("Stopped projection. Cleaning up root.");
-    fs::remove_dir_all(&args.projection_root)?;
-    log::info!("Root path cleaned.");
+    log::info!("Stopped projection.");
     Ok(())
 }
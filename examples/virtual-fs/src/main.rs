@@ -1,5 +1,4 @@
 use std::{
-    fs,
     io::{
         self,
         Cursor,
@@ -12,57 +11,38 @@ use std::{
 use clap::Parser;
 use windows_projfs::{
     DirectoryEntry,
-    DirectoryInfo,
-    FileInfo,
+    FnSource,
     Notification,
     ProjectedFileSystem,
-    ProjectedFileSystemSource,
 };
 
-struct VirtualProjectedSource;
-impl ProjectedFileSystemSource for VirtualProjectedSource {
-    fn list_directory(&self, path: &std::path::Path) -> Vec<windows_projfs::DirectoryEntry> {
-        if path.display().to_string().is_empty() {
-            vec![
-                DirectoryEntry::Directory(DirectoryInfo {
-                    directory_name: "test-dir".to_string(),
-
-                    ..Default::default()
-                }),
-                DirectoryEntry::File(FileInfo {
-                    file_name: "test.txt".to_string(),
-                    file_size: 12,
-
-                    ..Default::default()
-                }),
-            ]
-        } else {
-            vec![]
-        }
-    }
-
-    fn stream_file_content(
-        &self,
-        _path: &std::path::Path,
-        _byte_offset: usize,
-        _length: usize,
-    ) -> std::io::Result<Box<dyn Read>> {
-        let buffer = "Hello World\n".to_owned().into_bytes();
-
-        Ok(Box::new(Cursor::new(buffer)))
-    }
-
-    fn handle_notification(&self, notification: &Notification) -> ControlFlow<()> {
-        log::debug!("Notification: {:?}", notification);
-        if notification.is_cancelable()
-            && !matches!(notification, Notification::FilePreConvertToFull(_))
-        {
-            /* Try to cancel all possible actions to make the file system read only. */
-            ControlFlow::Break(())
-        } else {
-            ControlFlow::Continue(())
-        }
-    }
+fn virtual_source() -> FnSource {
+    FnSource::new()
+        .on_list(|path| {
+            if path.as_os_str().is_empty() {
+                vec![
+                    DirectoryEntry::directory("test-dir"),
+                    DirectoryEntry::file("test.txt", 12),
+                ]
+            } else {
+                vec![]
+            }
+        })
+        .on_stream(|_path, _byte_offset, _length| {
+            let buffer = "Hello World\n".to_owned().into_bytes();
+            Ok(Box::new(Cursor::new(buffer)))
+        })
+        .on_notification(|notification| {
+            log::debug!("Notification: {:?}", notification);
+            if notification.is_cancelable()
+                && !matches!(notification, Notification::FilePreConvertToFull(_))
+            {
+                /* Try to cancel all possible actions to make the file system read only. */
+                ControlFlow::Break(().into())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
 }
 
 #[derive(clap::Parser)]
@@ -81,22 +61,13 @@ fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     env_logger::init();
 
-    if args.root.exists() {
-        log::error!("Target path does already exists.");
-        log::error!("The root path should not be existent.");
-        return Ok(());
-    } else {
-        log::debug!("Creating {}", args.root.display());
-        fs::create_dir_all(&args.root)?;
-    }
-
     log::info!("Starting projected file system ({})", args.root.display());
     {
-        let _pfs = ProjectedFileSystem::new(&args.root, VirtualProjectedSource {})?;
+        let _pfs = ProjectedFileSystem::builder()
+            .remove_root_on_drop(true)
+            .build(&args.root, virtual_source())?;
         pause();
     }
-    log::info!("Stopped projected file system. Cleaning up root.");
-    fs::remove_dir_all(&args.root)?;
-    log::info!("Root path cleaned.");
+    log::info!("Stopped projected file system.");
     Ok(())
 }
@@ -0,0 +1,120 @@
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::{
+    io::{
+        self,
+        Read,
+    },
+    path::Path,
+};
+
+use common::MockLibrary;
+use criterion::{
+    criterion_group,
+    criterion_main,
+    Criterion,
+    Throughput,
+};
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+const DIRECTORY_ENTRIES: usize = 100_000;
+const FILE_SIZE: u32 = 256 * 1024 * 1024;
+
+/// Entries per enumeration callback (ProjFS uses a buffer of a few kilobytes).
+const ENUMERATION_BUFFER_ENTRIES: usize = 128;
+
+struct SyntheticProjectionSource {
+    entries: Vec<DirectoryEntry>,
+}
+
+impl SyntheticProjectionSource {
+    fn new() -> Self {
+        /* reversed to force the projection to sort the entries */
+        let entries = (0..DIRECTORY_ENTRIES)
+            .rev()
+            .map(|index| DirectoryEntry::file(format!("file_{:06}.bin", index), index as u64))
+            .chain([DirectoryEntry::file("large.bin", FILE_SIZE as u64)])
+            .collect();
+
+        Self { entries }
+    }
+}
+
+impl ProjectedFileSystemSource for SyntheticProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            self.entries.clone()
+        } else {
+            vec![]
+        }
+    }
+
+    fn get_directory_entry(&self, path: &Path) -> Option<DirectoryEntry> {
+        (path == Path::new("large.bin"))
+            .then(|| DirectoryEntry::file("large.bin", FILE_SIZE as u64))
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(io::repeat(0x55).take(length as u64)))
+    }
+}
+
+fn bench_enumeration(c: &mut Criterion) {
+    let target_dir = TempDir::new("bench_enumeration").unwrap();
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        SyntheticProjectionSource::new(),
+        library.clone(),
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("enumeration");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(DIRECTORY_ENTRIES as u64 + 1));
+    group.bench_function("100k_entries", |b| {
+        b.iter(|| {
+            let entries = library
+                .enumerate("", None, ENUMERATION_BUFFER_ENTRIES)
+                .unwrap();
+            assert_eq!(entries.len(), DIRECTORY_ENTRIES + 1);
+        })
+    });
+    group.finish();
+}
+
+fn bench_file_data(c: &mut Criterion) {
+    let target_dir = TempDir::new("bench_file_data").unwrap();
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        SyntheticProjectionSource::new(),
+        library.clone(),
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("file_data");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(FILE_SIZE as u64));
+    group.bench_function("256mib_read", |b| {
+        b.iter(|| {
+            let data = library.read_file("large.bin", 0, FILE_SIZE).unwrap();
+            assert_eq!(data.len(), FILE_SIZE as usize);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_enumeration, bench_file_data);
+criterion_main!(benches);
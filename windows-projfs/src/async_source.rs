@@ -0,0 +1,213 @@
+use std::{
+    future::{
+        self,
+        Future,
+    },
+    io,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        mpsc,
+        Arc,
+    },
+    task::{
+        Context,
+        Poll,
+        Wake,
+        Waker,
+    },
+    thread::{
+        self,
+        Thread,
+    },
+};
+
+use parking_lot::{
+    Condvar,
+    Mutex,
+};
+use windows::core::HRESULT;
+
+use crate::{
+    utils::{
+        catch_panic,
+        io_result_to_hresult,
+    },
+    CallbackKind,
+    RequestContext,
+};
+
+/// Future returned by an `AsyncSource`.
+pub type SourceFuture<T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send>>;
+
+/// Source which provides file contents asynchronously.
+///
+/// Instead of blocking a ProjFS worker thread until the content is available
+/// the read is marked as pending and completed via `PrjCompleteCommand` once
+/// the returned future resolves.
+///
+/// Note:
+/// Reads which resolve after the projection started stopping are dropped without being completed.  
+/// The crate does not depend on a specific runtime. Reads are driven by the spawner set via
+/// `ProjectedFileSystemBuilder::async_spawner` or else by a bounded pool of threads
+/// (see `ProjectedFileSystemBuilder::async_read_threads`).  
+/// Without a spawner every future is polled by a minimal executor which parks the pool thread
+/// until the future wakes it. This supports futures which are woken by other threads
+/// (e.g. channels or `futures` primitives) but not futures relying on the reactor of a runtime
+/// (e.g. tokio IO or timers). Such futures require a spawner of their runtime.
+pub trait AsyncSource: Send + Sync {
+    /// Read `length` bytes of the file at `path` starting at `byte_offset`.
+    /// The returned buffer must contain exactly `length` bytes.
+    fn read_file_content(
+        &self,
+        path: PathBuf,
+        byte_offset: usize,
+        length: usize,
+    ) -> SourceFuture<Vec<u8>>;
+}
+
+/// Task completing an asynchronous read of an `AsyncSource`.
+pub type AsyncReadTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Spawner set by `ProjectedFileSystemBuilder::async_spawner`.
+pub type AsyncSpawner = dyn Fn(AsyncReadTask) + Send + Sync;
+
+/// Drives the reads of an `AsyncSource`.
+pub(crate) enum AsyncExecutor {
+    Spawner(Box<AsyncSpawner>),
+    Pool(ReadPool),
+}
+
+impl AsyncExecutor {
+    pub fn spawn(&self, task: AsyncReadTask) -> io::Result<()> {
+        match self {
+            Self::Spawner(spawner) => {
+                spawner(task);
+                Ok(())
+            }
+            Self::Pool(pool) => pool
+                .sender
+                .send(task)
+                .map_err(|_| io::Error::other("the read pool has been stopped")),
+        }
+    }
+}
+
+/// Gate for completing asynchronous reads.  
+/// Reads which resolve once the projection is stopping are dropped
+/// as the virtualization context must not be used after `PrjStopVirtualizing`.
+#[derive(Default)]
+pub(crate) struct AsyncCompletions {
+    state: Mutex<AsyncCompletionsState>,
+    idle: Condvar,
+}
+
+#[derive(Default)]
+struct AsyncCompletionsState {
+    stopping: bool,
+    /// Number of reads which are currently being completed
+    active: usize,
+}
+
+impl AsyncCompletions {
+    /// Begin completing a read.  
+    /// Returns `None` if the projection is stopping and the read must be dropped.
+    pub fn begin(self: &Arc<Self>) -> Option<AsyncCompletionGuard> {
+        let mut state = self.state.lock();
+        if state.stopping {
+            return None;
+        }
+
+        state.active += 1;
+        Some(AsyncCompletionGuard(self.clone()))
+    }
+
+    /// Reject all further completions and wait for the running completions to finish.
+    pub fn stop(&self) {
+        let mut state = self.state.lock();
+        state.stopping = true;
+        while state.active > 0 {
+            self.idle.wait(&mut state);
+        }
+    }
+}
+
+/// Completion of a read which has been started by `AsyncCompletions::begin`.
+pub(crate) struct AsyncCompletionGuard(Arc<AsyncCompletions>);
+
+impl Drop for AsyncCompletionGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock();
+        state.active -= 1;
+        if state.active == 0 {
+            self.0.idle.notify_all();
+        }
+    }
+}
+
+/// Fixed number of threads driving read tasks one after another.  
+/// The threads exit once the pool has been dropped and all queued tasks are done.
+pub(crate) struct ReadPool {
+    sender: mpsc::Sender<AsyncReadTask>,
+}
+
+impl ReadPool {
+    pub fn new(threads: usize) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<AsyncReadTask>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..threads.max(1) {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("projfs-read-{}", index))
+                .spawn(move || loop {
+                    let task = receiver.lock().recv();
+                    match task {
+                        Ok(task) => block_on(task),
+                        Err(_) => break,
+                    }
+                })?;
+        }
+
+        Ok(Self { sender })
+    }
+}
+
+/// Poll `future` within `request_context` and convert a panic into `E_UNEXPECTED`.  
+/// The context is entered for every poll as the future may be polled by different threads.
+pub(crate) fn within_request<T: 'static>(
+    request_context: RequestContext,
+    mut future: SourceFuture<T>,
+) -> impl Future<Output = Result<T, HRESULT>> + Send {
+    future::poll_fn(move |context| {
+        let _request_context = request_context.clone().enter();
+        match catch_panic(CallbackKind::GetFileData, || {
+            Ok(future.as_mut().poll(context))
+        }) {
+            Ok(Poll::Ready(result)) => Poll::Ready(result.map_err(io_result_to_hresult)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(code) => Poll::Ready(Err(code)),
+        }
+    })
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Drive `future` to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
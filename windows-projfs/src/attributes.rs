@@ -0,0 +1,72 @@
+use std::ops::{
+    BitAnd,
+    BitOr,
+    BitOrAssign,
+};
+
+/// Typed representation of the Windows file attributes
+/// as used by `FileInfo::file_attributes` and `DirectoryInfo::directory_attributes`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileAttributes(pub u32);
+
+impl FileAttributes {
+    pub const READONLY: Self = Self(0x0000_0001);
+    pub const HIDDEN: Self = Self(0x0000_0002);
+    pub const SYSTEM: Self = Self(0x0000_0004);
+    pub const DIRECTORY: Self = Self(0x0000_0010);
+    pub const ARCHIVE: Self = Self(0x0000_0020);
+    pub const NORMAL: Self = Self(0x0000_0080);
+    pub const TEMPORARY: Self = Self(0x0000_0100);
+    pub const SPARSE_FILE: Self = Self(0x0000_0200);
+    pub const REPARSE_POINT: Self = Self(0x0000_0400);
+    pub const COMPRESSED: Self = Self(0x0000_0800);
+    pub const OFFLINE: Self = Self(0x0000_1000);
+    pub const NOT_CONTENT_INDEXED: Self = Self(0x0000_2000);
+    pub const ENCRYPTED: Self = Self(0x0000_4000);
+    pub const RECALL_ON_OPEN: Self = Self(0x0004_0000);
+    pub const RECALL_ON_DATA_ACCESS: Self = Self(0x0040_0000);
+
+    /// Returns the raw attribute bits.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all attributes of `other` are set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl From<u32> for FileAttributes {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FileAttributes> for u32 {
+    fn from(value: FileAttributes) -> Self {
+        value.0
+    }
+}
+
+impl BitOr for FileAttributes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for FileAttributes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for FileAttributes {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
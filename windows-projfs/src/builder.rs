@@ -0,0 +1,344 @@
+use std::{
+    cmp::Ordering,
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    library::ProjectedFSLibrary,
+    AsyncReadTask,
+    AsyncSource,
+    AsyncSpawner,
+    CallbackError,
+    CallbackErrorSink,
+    DirectoryEntry,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+    Result,
+};
+
+/// Access mode of the projection.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectionMode {
+    /// Deletions, renames, hardlinks and modifications of projected files are rejected
+    /// before the source gets notified.
+    ///
+    /// Note:
+    /// ProjFS does not allow rejecting the creation of new files.
+    ReadOnly,
+
+    /// All file operations are allowed unless the source rejects them
+    /// within `ProjectedFileSystemSource::handle_notification`.
+    #[default]
+    ReadWrite,
+}
+
+/// Comparison used by `SortStrategy::Custom`.
+pub type EntryComparator = dyn Fn(&DirectoryEntry, &DirectoryEntry) -> Ordering + Send + Sync;
+
+/// Filter used by `ProjectedFileSystemBuilder::notification_path_filter`.
+pub type NotificationPathFilter = dyn Fn(&Path) -> bool + Send + Sync;
+
+/// Order in which the entries of `ProjectedFileSystemSource::list_directory`
+/// are reported to ProjFS.
+#[derive(Default)]
+pub enum SortStrategy {
+    /// Sort the entries using `PrjFileNameCompare`, the collation order expected by ProjFS.
+    #[default]
+    ProjFsDefault,
+
+    /// Sort the entries using a custom comparison.
+    Custom(Box<EntryComparator>),
+
+    /// The entries are already sorted by the source and will be reported as returned.  
+    /// Search expressions are still applied.
+    ///
+    /// Note:
+    /// ProjFS merges the projected entries with entries existing on disk and expects
+    /// both to be in the `PrjFileNameCompare` order. Any other order may cause
+    /// duplicated or missing entries when listing partially hydrated directories.
+    PreSorted,
+}
+
+/// Builder for configuring and starting a `ProjectedFileSystem`.
+pub struct ProjectedFileSystemBuilder {
+    pub(crate) create_root: bool,
+    pub(crate) remove_root_on_drop: bool,
+    pub(crate) provider_id: [u8; 16],
+    pub(crate) instance_id: Option<u128>,
+    pub(crate) enumeration_chunk_size: usize,
+    pub(crate) sort_strategy: SortStrategy,
+    pub(crate) case_sensitive: bool,
+    pub(crate) async_source: Option<Arc<dyn AsyncSource>>,
+    pub(crate) async_spawner: Option<Box<AsyncSpawner>>,
+    pub(crate) async_read_threads: usize,
+    pub(crate) mode: ProjectionMode,
+    pub(crate) read_only_attributes: bool,
+    pub(crate) use_negative_path_cache: bool,
+    pub(crate) report_missing_directories: bool,
+    pub(crate) pool_threads: u32,
+    pub(crate) concurrent_threads: u32,
+    pub(crate) library: Option<Arc<dyn ProjectedFSLibrary>>,
+    pub(crate) error_sink: Option<Box<CallbackErrorSink>>,
+    pub(crate) notification_path_filter: Option<Box<NotificationPathFilter>>,
+}
+
+impl Default for ProjectedFileSystemBuilder {
+    fn default() -> Self {
+        Self {
+            create_root: true,
+            remove_root_on_drop: false,
+            provider_id: [0; 16],
+            instance_id: None,
+            enumeration_chunk_size: 1024,
+            sort_strategy: SortStrategy::ProjFsDefault,
+            case_sensitive: false,
+            async_source: None,
+            async_spawner: None,
+            async_read_threads: 4,
+            mode: ProjectionMode::ReadWrite,
+            read_only_attributes: false,
+            use_negative_path_cache: false,
+            report_missing_directories: false,
+            pool_threads: 0,
+            concurrent_threads: 0,
+            library: None,
+            error_sink: None,
+            notification_path_filter: None,
+        }
+    }
+}
+
+impl ProjectedFileSystemBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the projection root directory if it does not exists.  
+    /// Default: `true`
+    ///
+    /// Note:
+    /// An already existing root directory must be empty.
+    pub fn create_root(mut self, create_root: bool) -> Self {
+        self.create_root = create_root;
+        self
+    }
+
+    /// Remove the projection root including all materialized files
+    /// after the projection has been stopped.  
+    /// Default: `false`
+    pub fn remove_root_on_drop(mut self, remove_root_on_drop: bool) -> Self {
+        self.remove_root_on_drop = remove_root_on_drop;
+        self
+    }
+
+    /// Identifier of the provider which will be stored within every placeholder.  
+    /// This allows multiple providers to coexist and to detect which provider owns a placeholder.  
+    /// Default: all zero
+    pub fn provider_id(mut self, provider_id: [u8; 16]) -> Self {
+        self.provider_id = provider_id;
+        self
+    }
+
+    /// Virtualization instance id the projection root gets marked with.  
+    /// Default: a new random id for every start
+    ///
+    /// Note:
+    /// Placeholders on disk are bound to the instance id of their projection root.
+    /// A stable id allows starting the projection again on a root which has been projected
+    /// before (e.g. after a restart) while keeping its materialized placeholders.
+    /// If the root already is a virtualization root, it does not need to be empty and is not
+    /// marked again. Any other root must be empty. Use `remove_root_on_drop(false)` to keep the root.  
+    /// Never run two projections with the same id at the same time. The source must provide
+    /// the same entries as before as ProjFS keeps serving placeholders written by the previous
+    /// run (see `ProjectedFileSystem::refresh` for updating them).
+    pub fn instance_id(mut self, instance_id: u128) -> Self {
+        self.instance_id = Some(instance_id);
+        self
+    }
+
+    /// Number of entries requested at once from sources implementing
+    /// `ProjectedFileSystemSource::list_directory_chunk`.  
+    /// Default: `1024`
+    pub fn enumeration_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.enumeration_chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Order of the entries returned by `ProjectedFileSystemSource::list_directory`.  
+    /// Default: `SortStrategy::ProjFsDefault`
+    ///
+    /// Note:
+    /// Entries of `list_directory_chunk` and directory producers are never sorted.
+    pub fn sort_strategy(mut self, sort_strategy: SortStrategy) -> Self {
+        self.sort_strategy = sort_strategy;
+        self
+    }
+
+    /// Mark the projection root as case-sensitive directory, allowing entries which only
+    /// differ in case (e.g. `File.txt` and `file.txt`) to coexist.  
+    /// `SortStrategy::ProjFsDefault` sorts the entries ordinally (see `ordinal_name_compare`)
+    /// and search expressions of enumerations are matched case-sensitive.  
+    /// Default: `false`
+    ///
+    /// Note:
+    /// Requires NTFS and a Windows version supporting per-directory case sensitivity.
+    /// Directories created within the root inherit the flag, existing directories
+    /// (e.g. the backing directory) keep theirs.
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Access mode of the projection.  
+    /// Default: `ProjectionMode::ReadWrite`
+    pub fn mode(mut self, mode: ProjectionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Add `FILE_ATTRIBUTE_READONLY` to all projected files.  
+    /// Only applies to `ProjectionMode::ReadOnly`.  
+    /// Default: `false`
+    pub fn read_only_attributes(mut self, read_only_attributes: bool) -> Self {
+        self.read_only_attributes = read_only_attributes;
+        self
+    }
+
+    /// Let ProjFS remember paths for which the source returned no entry
+    /// and fail further requests for these paths without invoking the source.  
+    /// Default: `false`
+    ///
+    /// Note:
+    /// Entries which are added to the source afterwards stay invisible until
+    /// `ProjectedFileSystem::clear_negative_path_cache` has been called.
+    pub fn use_negative_path_cache(mut self, use_negative_path_cache: bool) -> Self {
+        self.use_negative_path_cache = use_negative_path_cache;
+        self
+    }
+
+    /// Fail enumerating directories which are no directory entry of the source
+    /// (see `ProjectedFileSystemSource::get_directory_entry`) with `ERROR_PATH_NOT_FOUND`
+    /// instead of reporting them as empty.  
+    /// Default: `false`
+    ///
+    /// Note:
+    /// ProjFS only enumerates directories existing on disk. This mainly concerns
+    /// placeholders of directories which have been removed from the source.  
+    /// Every enumeration looks up its directory once. The projection root always exists.
+    pub fn report_missing_directories(mut self, report_missing_directories: bool) -> Self {
+        self.report_missing_directories = report_missing_directories;
+        self
+    }
+
+    /// Number of threads ProjFS creates to serve callbacks.  
+    /// Default: `0` (twice the number of concurrent threads)
+    ///
+    /// Note:
+    /// Must not be less than the number of concurrent threads.
+    pub fn pool_threads(mut self, pool_threads: u32) -> Self {
+        self.pool_threads = pool_threads;
+        self
+    }
+
+    /// Maximum number of callbacks ProjFS runs concurrently.  
+    /// Default: `0` (the number of logical processors)
+    pub fn concurrent_threads(mut self, concurrent_threads: u32) -> Self {
+        self.concurrent_threads = concurrent_threads;
+        self
+    }
+
+    /// Use a custom implementation of the ProjFS API instead of the system library.  
+    /// Default: the system library
+    ///
+    /// Note:
+    /// This is mainly useful for testing. Use `load_library` for the system library.
+    pub fn library(mut self, library: Arc<dyn ProjectedFSLibrary>) -> Self {
+        self.library = Some(library);
+        self
+    }
+
+    /// Serve file contents from `source` asynchronously.  
+    /// Reads of the default data stream will be completed via `PrjCompleteCommand`
+    /// instead of blocking the ProjFS worker thread.  
+    /// Default: `None`
+    ///
+    /// Note:
+    /// Directory listings, placeholder information and named streams
+    /// are still served by the synchronous source.
+    pub fn async_source(mut self, source: impl AsyncSource + 'static) -> Self {
+        self.async_source = Some(Arc::new(source));
+        self
+    }
+
+    /// Drive the reads of the `async_source` by spawning them via `spawner`
+    /// (e.g. onto a tokio runtime using `Handle::spawn`).  
+    /// Default: reads are driven by a pool of `async_read_threads` threads
+    ///
+    /// Note:
+    /// Futures relying on the reactor of a runtime (e.g. tokio IO or timers)
+    /// must be spawned onto that runtime (see `AsyncSource`).  
+    /// Once the read resolved the task writes the data and completes the command,
+    /// which blocks briefly.
+    pub fn async_spawner(
+        mut self,
+        spawner: impl Fn(AsyncReadTask) + Send + Sync + 'static,
+    ) -> Self {
+        self.async_spawner = Some(Box::new(spawner));
+        self
+    }
+
+    /// Number of threads driving the reads of the `async_source` if no `async_spawner` is set.  
+    /// Further reads are queued until a thread becomes available.  
+    /// Default: `4`
+    pub fn async_read_threads(mut self, threads: usize) -> Self {
+        self.async_read_threads = threads.max(1);
+        self
+    }
+
+    /// Invoke `sink` whenever a ProjFS callback fails (e.g. because the source
+    /// failed to provide the file contents or ProjFS rejected the written data).  
+    /// Default: failures are only logged
+    ///
+    /// Note:
+    /// Expected results are not reported: requests for paths not provided by the source
+    /// (including `SourceError`s),
+    /// reads completed asynchronously and notifications cancelled by the source or
+    /// the `ProjectionMode`.  
+    /// The sink is invoked on the ProjFS worker thread and should return quickly.
+    pub fn on_callback_error(
+        mut self,
+        sink: impl Fn(CallbackError) + Send + Sync + 'static,
+    ) -> Self {
+        self.error_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Only dispatch notifications for paths accepted by `filter`
+    /// (e.g. `|path| path.starts_with("src")`).  
+    /// Renames are dispatched if either the source or the destination is accepted.  
+    /// Default: all notifications are dispatched
+    ///
+    /// Note:
+    /// Rejected notifications are neither passed to the source nor to any notification channel
+    /// and are filtered without locking the projection. Cancelable notifications are still
+    /// rejected in `ProjectionMode::ReadOnly`.  
+    /// The paths are relative to the projection root.
+    pub fn notification_path_filter(
+        mut self,
+        filter: impl Fn(&Path) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.notification_path_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Start the projected file system at `root`.  
+    /// See `ProjectedFileSystem::new` for how `root` is resolved.
+    pub fn build(
+        self,
+        root: &Path,
+        source: impl ProjectedFileSystemSource + 'static,
+    ) -> Result<ProjectedFileSystem> {
+        ProjectedFileSystem::start(root, Box::new(source), self)
+    }
+}
@@ -10,7 +10,7 @@ use windows::{
         HRESULT,
     },
     Win32::{
-        Foundation::STATUS_SUCCESS,
+        Foundation::S_OK,
         Storage::ProjectedFileSystem::{
             PRJ_CALLBACK_DATA,
             PRJ_CALLBACK_DATA_FLAGS,
@@ -19,6 +19,29 @@ use windows::{
     },
 };
 
+use crate::{
+    utils::{
+        catch_panic,
+        normalize_request_path,
+    },
+    CallbackKind,
+    ProcessImage,
+    RawCallbackInfo,
+    RequestContext,
+};
+
+/// Result of a successfully handled callback.
+///
+/// Note:
+/// ProjFS expects HRESULTs. `STATUS_SUCCESS.to_hresult()` sets the NT facility bit
+/// and therefore is a success code but not `S_OK`.
+pub const CALLBACK_SUCCESS: HRESULT = S_OK;
+
+/// Result of `GetDirectoryEnumerationCallback` for an enumeration which has ended
+/// or is not known (any more).  
+/// ProjFS detects the end of an enumeration by a successful callback without any entries.
+pub const ENUMERATION_ENDED: HRESULT = S_OK;
+
 #[allow(unused)]
 pub struct CallbackData<'a, C> {
     pub flags: PRJ_CALLBACK_DATA_FLAGS,
@@ -34,7 +57,7 @@ pub struct CallbackData<'a, C> {
     pub file_path: Option<PathBuf>,
 
     pub triggering_process_id: u32,
-    pub triggering_process_image_file_name: Option<String>,
+    pub triggering_process_image: Option<ProcessImage>,
 
     /// The projection context should be a valid reference as long
     /// as this callback data exists.
@@ -43,12 +66,39 @@ pub struct CallbackData<'a, C> {
 }
 
 impl<'a, C> CallbackData<'a, C> {
-    pub fn execute<F>(self, executor: F) -> HRESULT
+    pub fn request_context(&self) -> RequestContext {
+        RequestContext {
+            command_id: self.command_id,
+            triggering_process_id: self.triggering_process_id,
+            triggering_process_image: self.triggering_process_image.clone(),
+        }
+    }
+
+    pub fn raw_info(&self, kind: CallbackKind) -> RawCallbackInfo {
+        RawCallbackInfo {
+            kind,
+            flags: self.flags.0 as u32,
+            command_id: self.command_id,
+            file_id: self.file_id.to_u128(),
+            data_stream_id: self.data_stream_id.to_u128(),
+            triggering_process_id: self.triggering_process_id,
+        }
+    }
+
+    /// The normalized path of the request (see `normalize_request_path`).
+    pub fn request_path(&self) -> Result<PathBuf, HRESULT> {
+        normalize_request_path(self.file_path.as_deref())
+    }
+
+    /// Execute the callback handler.  
+    /// A panic of the handler fails the callback with `E_UNEXPECTED` (see `catch_panic`).
+    pub fn execute<F>(self, kind: CallbackKind, executor: F) -> HRESULT
     where
         F: FnOnce(&Self) -> Result<(), HRESULT>,
     {
-        match executor(&self) {
-            Ok(_) => STATUS_SUCCESS.to_hresult(),
+        let _request_context = self.request_context().enter();
+        match catch_panic(kind, || executor(&self)) {
+            Ok(_) => CALLBACK_SUCCESS,
             Err(code) => code,
         }
     }
@@ -65,12 +115,12 @@ impl<'a, C> From<*const PRJ_CALLBACK_DATA> for CallbackData<'a, C> {
             })))
         };
 
-        let triggering_process_image_file_name = if value.TriggeringProcessImageFileName.is_null() {
+        let triggering_process_image = if value.TriggeringProcessImageFileName.is_null() {
             None
         } else {
-            Some(String::from_utf16_lossy(unsafe {
+            Some(ProcessImage::new(OsString::from_wide(unsafe {
                 value.TriggeringProcessImageFileName.as_wide()
-            }))
+            })))
         };
 
         let context = unsafe { &mut *(value.InstanceContext as *mut C) };
@@ -85,7 +135,7 @@ impl<'a, C> From<*const PRJ_CALLBACK_DATA> for CallbackData<'a, C> {
             data_stream_id: value.DataStreamId,
 
             triggering_process_id: value.TriggeringProcessId,
-            triggering_process_image_file_name,
+            triggering_process_image,
 
             context,
         }
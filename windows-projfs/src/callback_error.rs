@@ -0,0 +1,35 @@
+use std::{
+    fmt,
+    path::PathBuf,
+};
+
+use windows::core::HRESULT;
+
+use crate::CallbackKind;
+
+/// A ProjFS callback which failed and reported `result` to ProjFS.
+///
+/// ProjFS only forwards these results to the process which triggered the callback.
+/// Register a sink with `ProjectedFileSystemBuilder::on_callback_error` to observe them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackError {
+    pub kind: CallbackKind,
+
+    /// Path of the request relative to the projection root as reported by ProjFS.
+    pub path: Option<PathBuf>,
+
+    pub result: HRESULT,
+}
+
+impl fmt::Display for CallbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} failed", self.kind)?;
+        if let Some(path) = &self.path {
+            write!(f, " for {}", path.display())?;
+        }
+        write!(f, ": {}", self.result.message())
+    }
+}
+
+/// Sink registered via `ProjectedFileSystemBuilder::on_callback_error`.
+pub type CallbackErrorSink = dyn Fn(CallbackError) + Send + Sync;
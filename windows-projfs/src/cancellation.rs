@@ -0,0 +1,32 @@
+use std::sync::{
+    atomic::{
+        AtomicBool,
+        Ordering,
+    },
+    Arc,
+};
+
+/// Token signaling that ProjFS canceled the operation it has been passed to.
+///
+/// ProjFS cancels an operation (e.g. the user aborted a file copy) by invoking the
+/// `PRJ_CANCEL_COMMAND_CB` callback with the command id of the operation.
+/// Long running operations should check `is_canceled` regularly and stop early.
+#[derive(Default, Debug, Clone)]
+pub struct CancellationToken {
+    canceled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the operation has been canceled.
+    pub fn is_canceled(&self) -> bool {
+        self.canceled.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.canceled.store(true, Ordering::Relaxed);
+    }
+}
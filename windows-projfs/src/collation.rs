@@ -0,0 +1,135 @@
+use std::cmp::Ordering;
+
+use windows::core::PCWSTR;
+
+use crate::{
+    library::ProjectedFSLibrary,
+    utils::encode_wide_null,
+    DirectoryEntry,
+};
+
+/// Compare two file names in the collation order expected by ProjFS (`PrjFileNameCompare`).
+pub fn projfs_name_compare(library: &dyn ProjectedFSLibrary, a: &str, b: &str) -> Ordering {
+    let a = encode_wide_null(a);
+    let b = encode_wide_null(b);
+    let result = unsafe { library.prj_file_name_compare(PCWSTR(a.as_ptr()), PCWSTR(b.as_ptr())) };
+    result.cmp(&0)
+}
+
+/// Insert `entry` into `entries` which are sorted by `projfs_name_compare`
+/// (e.g. the listing of a source using `SortStrategy::PreSorted`).  
+/// An entry with the same name is replaced as names are unique within a directory.  
+/// Returns the index of the inserted entry.
+///
+/// Note:
+/// The position is determined by a binary search. If `entries` are not sorted
+/// the position is unspecified.
+pub fn projfs_sorted_insert(
+    library: &dyn ProjectedFSLibrary,
+    entries: &mut Vec<DirectoryEntry>,
+    entry: DirectoryEntry,
+) -> usize {
+    match entries.binary_search_by(|probe| projfs_name_compare(library, probe.name(), entry.name()))
+    {
+        Ok(index) => {
+            entries[index] = entry;
+            index
+        }
+        Err(index) => {
+            entries.insert(index, entry);
+            index
+        }
+    }
+}
+
+/// Compare two file names ordinally by their UTF-16 code units
+/// (the collation order of case-sensitive projections).
+pub fn ordinal_name_compare(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+/// Upcase `name` per UTF-16 code unit like the upcase table ProjFS and NTFS use
+/// to compare names case-insensitive (`PrjFileNameCompare`).  
+/// Code units without a single character uppercase mapping (e.g. `ß`) are kept as they are.
+pub(crate) fn upcase_name(name: &str) -> Vec<u16> {
+    name.encode_utf16()
+        .map(|unit| {
+            char::from_u32(unit as u32)
+                .and_then(|value| {
+                    let mut upper = value.to_uppercase();
+                    match (upper.next(), upper.next()) {
+                        (Some(upper), None) => u16::try_from(upper as u32).ok(),
+                        _ => None,
+                    }
+                })
+                .unwrap_or(unit)
+        })
+        .collect()
+}
+
+const WILDCARD_STAR: u16 = b'*' as u16;
+const WILDCARD_QM: u16 = b'?' as u16;
+const WILDCARD_DOS_STAR: u16 = b'<' as u16;
+const WILDCARD_DOS_QM: u16 = b'>' as u16;
+const WILDCARD_DOS_DOT: u16 = b'"' as u16;
+const DOT: u16 = b'.' as u16;
+
+fn trim_null(value: &[u16]) -> &[u16] {
+    let length = value.iter().position(|&c| c == 0).unwrap_or(value.len());
+    &value[..length]
+}
+
+/// Match the (null terminated) `name` against a ProjFS search expression
+/// like `PrjFileNameMatch` does, but without ignoring the case.  
+/// Supports the wildcards `*` and `?` as well as the DOS wildcards `<`, `>` and `"`.
+pub(crate) fn name_match_case_sensitive(name: &[u16], pattern: &[u16]) -> bool {
+    let name = trim_null(name);
+    let pattern = trim_null(pattern);
+    let last_dot = name.iter().rposition(|&c| c == DOT);
+
+    /* matches[n] is set if the pattern processed so far matches the first n characters */
+    let mut matches = vec![false; name.len() + 1];
+    matches[0] = true;
+
+    for &wildcard in pattern {
+        let mut next = vec![false; name.len() + 1];
+        for offset in (0..=name.len()).filter(|&offset| matches[offset]) {
+            let current = name.get(offset).copied();
+            match wildcard {
+                WILDCARD_STAR => next[offset..].fill(true),
+                WILDCARD_DOS_STAR => {
+                    /* matches everything up to the final dot */
+                    next[offset] = true;
+                    let mut end = offset;
+                    while end < name.len() && Some(end) != last_dot {
+                        end += 1;
+                        next[end] = true;
+                    }
+                }
+                WILDCARD_QM => {
+                    if current.is_some() {
+                        next[offset + 1] = true;
+                    }
+                }
+                WILDCARD_DOS_QM => match current {
+                    None | Some(DOT) => next[offset] = true,
+                    Some(_) => next[offset + 1] = true,
+                },
+                WILDCARD_DOS_DOT => match current {
+                    None => next[offset] = true,
+                    Some(DOT) => next[offset + 1] = true,
+                    Some(_) => {}
+                },
+                _ => {
+                    if current == Some(wildcard) {
+                        next[offset + 1] = true;
+                    }
+                }
+            }
+        }
+
+        matches = next;
+    }
+
+    matches[name.len()]
+}
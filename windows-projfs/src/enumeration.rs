@@ -0,0 +1,28 @@
+use std::fmt;
+
+use windows::core::GUID;
+
+/// Identifier of an active directory enumeration.
+///
+/// ProjFS identifies every enumeration by a GUID which stays
+/// unique as long as the enumeration has not been ended.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EnumerationId(u128);
+
+impl EnumerationId {
+    pub(crate) fn from_guid(guid: &GUID) -> Self {
+        Self(guid.to_u128())
+    }
+}
+
+impl fmt::Debug for EnumerationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EnumerationId({:032X})", self.0)
+    }
+}
+
+impl fmt::Display for EnumerationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032X}", self.0)
+    }
+}
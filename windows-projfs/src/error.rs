@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -9,26 +11,77 @@ pub enum Error {
     #[error("{0}")]
     GenericWindows(#[from] windows::core::Error),
 
+    /// An IO error occurred while preparing the projection
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    /// The path is not relative to the projection root
+    /// (e.g. it is absolute or contains `..`)
+    #[error("path {0} is not relative to the projection root")]
+    InvalidRelativePath(PathBuf),
+
+    /// The projection root path is not a valid directory path
+    /// (e.g. it is an existing file, contains a reserved name like `NUL` or invalid characters like `:`)
+    #[error("projection root path {0} is invalid")]
+    InvalidRootPath(PathBuf),
+
+    /// The projection root does not exist and should not be created
+    #[error("projection root {0} does not exist")]
+    RootNotFound(PathBuf),
+
+    /// The projection root directory already contains entries
+    #[error("projection root {0} is not empty")]
+    RootNotEmpty(PathBuf),
+
+    /// The configured pool thread count is less than the concurrent thread count
+    #[error("pool thread count {0} is less than the concurrent thread count {1}")]
+    InvalidThreadCounts(u32, u32),
+
     /// Failed to create the projection root directory
     #[error("failed to mark projection root: {0}")]
     MarkProjectionRoot(windows::core::Error),
 
+    /// Failed to mark the projection root as case-sensitive directory
+    #[error("failed to enable case sensitivity of {0}: {1}")]
+    EnableCaseSensitivity(PathBuf, std::io::Error),
+
     /// Failed to start the projection
     #[error("failed to start projection: {0}")]
     StartProjection(windows::core::Error),
 
+    /// The projection did not stop within the given timeout.  
+    /// The projection keeps stopping in the background.
+    #[error("timed out while waiting for the projection to stop")]
+    StopTimeout,
+
     /// The Windows feature "Projected File System" is not enabled.
     /// This feature has to be enabled before using this library.
     ///
     /// Note:
-    /// This error can only occurr with feature "dynamic-import" else
-    /// you would receive a DLL loading error when starting your application.
+    /// Without feature "dynamic-import" you would usually receive a DLL loading error
+    /// when starting your application instead (see `is_projfs_available`).  
+    /// See `projfs_enable_instructions` and `try_enable_projfs` for enabling the feature.
     #[error("The Windows feature \"Projected File System\" is not enabled")]
     WindowsFeatureNotEnabled,
 
+    /// The Windows feature "Projected File System" is already enabled
+    #[cfg(feature = "admin")]
+    #[error("The Windows feature \"Projected File System\" is already enabled")]
+    WindowsFeatureAlreadyEnabled,
+
+    /// Enabling Windows features requires the process to be elevated
+    #[cfg(feature = "admin")]
+    #[error("enabling Windows features requires an elevated process")]
+    NotElevated,
+
+    /// The DISM API failed to enable the Windows feature
+    #[cfg(feature = "admin")]
+    #[error("failed to enable the Windows feature: {0}")]
+    EnableFeature(windows::core::Error),
+
     /// Failed to resolve certain Windows project fs API imports
     /// which are required for this library to work.
-    #[cfg(feature = "dynamic-import")]
+    #[cfg(any(feature = "dynamic-import", feature = "admin"))]
     #[error("failed to resolve imports: {0}")]
     LibraryError(#[from] libloading::Error),
 }
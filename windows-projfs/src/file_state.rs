@@ -0,0 +1,58 @@
+use std::ops::{
+    BitAnd,
+    BitOr,
+};
+
+/// State of an item within the projection root as reported by `PrjGetOnDiskFileState`.  
+/// Multiple states can be combined (e.g. `PLACEHOLDER | DIRTY_PLACEHOLDER`).
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OnDiskFileState(pub u32);
+
+impl OnDiskFileState {
+    /// The item is a placeholder without any content on disk
+    pub const PLACEHOLDER: Self = Self(0x0000_0001);
+
+    /// The item is a placeholder whose content has been read from the source
+    pub const HYDRATED_PLACEHOLDER: Self = Self(0x0000_0002);
+
+    /// The metadata of the placeholder has been modified
+    pub const DIRTY_PLACEHOLDER: Self = Self(0x0000_0004);
+
+    /// The item has been created or modified locally and is no longer backed by the source
+    pub const FULL: Self = Self(0x0000_0008);
+
+    /// The item has been deleted locally and hides the entry of the source
+    pub const TOMBSTONE: Self = Self(0x0000_0010);
+
+    /// Returns the raw state bits.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all states of `other` are set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl From<u32> for OnDiskFileState {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl BitOr for OnDiskFileState {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for OnDiskFileState {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    borrow::Cow,
+    io::{
+        self,
+        Read,
+    },
+    ops::{
+        ControlFlow,
+        Range,
+    },
+    path::Path,
+};
+
+use crate::{
+    CancellationToken,
+    DirectoryEntry,
+    FileChunks,
+    Notification,
+    NotificationVeto,
+    ProjectedFileSystemSource,
+    RawCallbackInfo,
+};
+
+/// Source exposing only the entries of `source` accepted by `predicate`
+/// (e.g. for hiding dot files or allowing only certain file extensions).
+///
+/// Rejected entries are neither listed nor found by `get_directory_entry`
+/// and reading their contents fails with `io::ErrorKind::NotFound`.
+///
+/// Note:
+/// Only the entry itself is checked. Entries within a rejected directory
+/// are unreachable as ProjFS never requests paths below an entry which does not exist.
+pub struct Filter<S, F> {
+    pub source: S,
+    pub predicate: F,
+}
+
+impl<S, F> Filter<S, F>
+where
+    S: ProjectedFileSystemSource,
+    F: Fn(&DirectoryEntry) -> bool,
+{
+    pub fn new(source: S, predicate: F) -> Self {
+        Self { source, predicate }
+    }
+
+    fn ensure_visible(&self, path: &Path) -> io::Result<()> {
+        if self.get_directory_entry(path).is_some() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} has been filtered", path.display()),
+            ))
+        }
+    }
+}
+
+impl<S, F> ProjectedFileSystemSource for Filter<S, F>
+where
+    S: ProjectedFileSystemSource,
+    F: Fn(&DirectoryEntry) -> bool,
+{
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        let mut entries = self.source.list_directory(path);
+        entries.retain(|entry| (self.predicate)(entry));
+        entries
+    }
+
+    fn get_directory_entry(&self, path: &Path) -> Option<DirectoryEntry> {
+        self.source
+            .get_directory_entry(path)
+            .filter(|entry| (self.predicate)(entry))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.source.exists(path) && self.get_directory_entry(path).is_some()
+    }
+
+    fn content_id(&self, path: &Path) -> Option<[u8; 16]> {
+        self.get_directory_entry(path)?;
+        self.source.content_id(path)
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        self.ensure_visible(path)?;
+        self.source.stream_file_content(path, byte_offset, length)
+    }
+
+    fn stream_file_content_cancelable(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+        cancellation: &CancellationToken,
+    ) -> io::Result<Box<dyn Read>> {
+        self.ensure_visible(path)?;
+        self.source
+            .stream_file_content_cancelable(path, byte_offset, length, cancellation)
+    }
+
+    fn stream_file_content_mut(
+        &mut self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+        cancellation: &CancellationToken,
+    ) -> io::Result<Box<dyn Read>> {
+        self.ensure_visible(path)?;
+        self.source
+            .stream_file_content_mut(path, byte_offset, length, cancellation)
+    }
+
+    fn file_content_slice(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> Option<io::Result<Cow<'_, [u8]>>> {
+        if let Err(error) = self.ensure_visible(path) {
+            return Some(Err(error));
+        }
+        self.source.file_content_slice(path, byte_offset, length)
+    }
+
+    fn stream_file_chunks(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> Option<io::Result<FileChunks>> {
+        if let Err(error) = self.ensure_visible(path) {
+            return Some(Err(error));
+        }
+        self.source.stream_file_chunks(path, byte_offset, length)
+    }
+
+    fn zero_ranges(&self, path: &Path) -> Vec<Range<u64>> {
+        self.source.zero_ranges(path)
+    }
+
+    fn stream_named_content(
+        &self,
+        path: &Path,
+        stream_name: &str,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        self.ensure_visible(path)?;
+        self.source
+            .stream_named_content(path, stream_name, byte_offset, length)
+    }
+
+    fn handle_notification(&self, notification: &Notification) -> ControlFlow<NotificationVeto> {
+        self.source.handle_notification(notification)
+    }
+
+    fn handle_notification_mut(
+        &mut self,
+        notification: &Notification,
+    ) -> ControlFlow<NotificationVeto> {
+        self.source.handle_notification_mut(notification)
+    }
+
+    fn on_stop(&self) {
+        self.source.on_stop();
+    }
+
+    fn on_raw_callback(&self, info: &RawCallbackInfo) {
+        self.source.on_raw_callback(info);
+    }
+}
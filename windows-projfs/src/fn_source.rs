@@ -0,0 +1,98 @@
+use std::{
+    io::{
+        self,
+        Read,
+    },
+    ops::ControlFlow,
+    path::Path,
+};
+
+use crate::{
+    DirectoryEntry,
+    Notification,
+    NotificationVeto,
+    ProjectedFileSystemSource,
+};
+
+type ListFn = dyn Fn(&Path) -> Vec<DirectoryEntry>;
+type StreamFn = dyn Fn(&Path, usize, usize) -> io::Result<Box<dyn Read>>;
+type NotificationFn = dyn Fn(&Notification) -> ControlFlow<NotificationVeto>;
+
+/// A `ProjectedFileSystemSource` delegating to closures.  
+/// Useful for prototypes and small ad-hoc projections.
+///
+/// Note:
+/// Without `on_list` all directories are empty and without `on_stream`
+/// reading any file fails with `io::ErrorKind::NotFound`.
+pub struct FnSource {
+    list: Box<ListFn>,
+    stream: Box<StreamFn>,
+    notification: Option<Box<NotificationFn>>,
+}
+
+impl Default for FnSource {
+    fn default() -> Self {
+        Self {
+            list: Box::new(|_| Vec::new()),
+            stream: Box::new(|_, _, _| {
+                Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "file contents are not supported",
+                ))
+            }),
+            notification: None,
+        }
+    }
+}
+
+impl FnSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See `ProjectedFileSystemSource::list_directory`.
+    pub fn on_list(mut self, list: impl Fn(&Path) -> Vec<DirectoryEntry> + 'static) -> Self {
+        self.list = Box::new(list);
+        self
+    }
+
+    /// See `ProjectedFileSystemSource::stream_file_content`.
+    pub fn on_stream(
+        mut self,
+        stream: impl Fn(&Path, usize, usize) -> io::Result<Box<dyn Read>> + 'static,
+    ) -> Self {
+        self.stream = Box::new(stream);
+        self
+    }
+
+    /// See `ProjectedFileSystemSource::handle_notification`.
+    pub fn on_notification(
+        mut self,
+        notification: impl Fn(&Notification) -> ControlFlow<NotificationVeto> + 'static,
+    ) -> Self {
+        self.notification = Some(Box::new(notification));
+        self
+    }
+}
+
+impl ProjectedFileSystemSource for FnSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        (self.list)(path)
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        (self.stream)(path, byte_offset, length)
+    }
+
+    fn handle_notification(&self, notification: &Notification) -> ControlFlow<NotificationVeto> {
+        match &self.notification {
+            Some(handler) => handler(notification),
+            None => ControlFlow::Continue(()),
+        }
+    }
+}
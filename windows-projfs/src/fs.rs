@@ -1,43 +1,107 @@
 use std::{
     self,
+    borrow::Cow,
     cell::RefCell,
     collections::{
         btree_map::Entry,
         BTreeMap,
+        VecDeque,
+    },
+    ffi::{
+        c_void,
+        OsStr,
+        OsString,
+    },
+    fs,
+    io::{
+        self,
+        Read,
+    },
+    mem,
+    os::windows::{
+        fs::{
+            MetadataExt,
+            OpenOptionsExt,
+        },
+        io::AsRawHandle,
     },
-    ffi::c_void,
     path::{
+        Component,
         Path,
         PathBuf,
     },
-    rc::Rc,
-    sync::Arc,
+    ptr,
+    sync::{
+        atomic::{
+            self,
+            AtomicU64,
+        },
+        mpsc::{
+            self,
+            Receiver,
+            Sender,
+        },
+        Arc,
+    },
+    thread,
+    time::Duration,
 };
 
-use parking_lot::Mutex;
+use parking_lot::{
+    Mutex,
+    MutexGuard,
+};
 use windows::{
     core::{
         GUID,
+        HRESULT,
         PCWSTR,
     },
-    Win32::Storage::ProjectedFileSystem::{
-        PRJ_CALLBACKS,
-        PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
-        PRJ_NOTIFICATION_MAPPING,
-        PRJ_NOTIFY_FILE_HANDLE_CLOSED_FILE_DELETED,
-        PRJ_NOTIFY_FILE_HANDLE_CLOSED_FILE_MODIFIED,
-        PRJ_NOTIFY_FILE_HANDLE_CLOSED_NO_MODIFICATION,
-        PRJ_NOTIFY_FILE_OPENED,
-        PRJ_NOTIFY_FILE_OVERWRITTEN,
-        PRJ_NOTIFY_FILE_PRE_CONVERT_TO_FULL,
-        PRJ_NOTIFY_FILE_RENAMED,
-        PRJ_NOTIFY_HARDLINK_CREATED,
-        PRJ_NOTIFY_NEW_FILE_CREATED,
-        PRJ_NOTIFY_PRE_DELETE,
-        PRJ_NOTIFY_PRE_RENAME,
-        PRJ_NOTIFY_PRE_SET_HARDLINK,
-        PRJ_NOTIFY_TYPES,
-        PRJ_STARTVIRTUALIZING_OPTIONS,
+    Win32::{
+        Foundation::{
+            ERROR_ALREADY_EXISTS,
+            ERROR_FILE_NOT_FOUND,
+            ERROR_OPERATION_ABORTED,
+            ERROR_PATH_NOT_FOUND,
+            HANDLE,
+        },
+        Storage::{
+            FileSystem::{
+                FileCaseSensitiveInfo,
+                SetFileInformationByHandle,
+                FILE_ATTRIBUTE_REPARSE_POINT,
+                FILE_FLAG_BACKUP_SEMANTICS,
+                FILE_FLAG_OPEN_REPARSE_POINT,
+            },
+            ProjectedFileSystem::{
+                PRJ_CALLBACKS,
+                PRJ_FLAG_NONE,
+                PRJ_FLAG_USE_NEGATIVE_PATH_CACHE,
+                PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+                PRJ_NOTIFICATION_MAPPING,
+                PRJ_NOTIFY_FILE_HANDLE_CLOSED_FILE_DELETED,
+                PRJ_NOTIFY_FILE_HANDLE_CLOSED_FILE_MODIFIED,
+                PRJ_NOTIFY_FILE_HANDLE_CLOSED_NO_MODIFICATION,
+                PRJ_NOTIFY_FILE_OPENED,
+                PRJ_NOTIFY_FILE_OVERWRITTEN,
+                PRJ_NOTIFY_FILE_PRE_CONVERT_TO_FULL,
+                PRJ_NOTIFY_FILE_RENAMED,
+                PRJ_NOTIFY_HARDLINK_CREATED,
+                PRJ_NOTIFY_NEW_FILE_CREATED,
+                PRJ_NOTIFY_PRE_DELETE,
+                PRJ_NOTIFY_PRE_RENAME,
+                PRJ_NOTIFY_PRE_SET_HARDLINK,
+                PRJ_NOTIFY_TYPES,
+                PRJ_PLACEHOLDER_VERSION_INFO,
+                PRJ_STARTVIRTUALIZING_OPTIONS,
+                PRJ_UPDATE_NONE,
+            },
+        },
+        System::{
+            Ioctl::FSCTL_DELETE_REPARSE_POINT,
+            SystemServices::IO_REPARSE_TAG_PROJFS,
+            IO::DeviceIoControl,
+        },
     },
 };
 
@@ -46,150 +110,1101 @@ use crate::{
         load_library,
         ProjectedFSLibrary,
     },
+    ordinal_name_compare,
+    utils::{
+        encode_path_null,
+        encode_wide_null,
+        normalize_request_path,
+    },
+    AsyncCompletions,
+    AsyncExecutor,
+    AsyncSource,
+    CallbackError,
+    CallbackErrorSink,
+    CancellationToken,
     DirectoryEntry,
+    DirectoryProducer,
+    EnumerationId,
     Error,
+    FileAttributes,
+    FileSize,
+    Notification,
+    NotificationKind,
+    NotificationPathFilter,
+    OnDiskFileState,
+    ProjectedFileSystemBuilder,
     ProjectedFileSystemSource,
+    ProjectionMode,
+    ReadPool,
     Result,
+    SortStrategy,
+    DIRECT_WRITE_ALIGNMENT,
 };
 
 #[derive(Default)]
 struct FileNameU16Cache {
-    cache: BTreeMap<String, Vec<u16>>,
+    cache: BTreeMap<OsString, Vec<u16>>,
 }
 
 impl FileNameU16Cache {
-    pub fn get_or_cache(&mut self, name: String) -> &[u16] {
-        match self.cache.entry(name) {
+    /// Get the zero terminated UTF-16 representation of `name`.  
+    /// The name is encoded losslessly, including unpaired surrogates.
+    pub fn get_or_cache(&mut self, name: impl Into<OsString>) -> &[u16] {
+        match self.cache.entry(name.into()) {
             Entry::Occupied(entry) => entry.into_mut().as_slice(),
             Entry::Vacant(entry) => {
-                let mut name = entry.key().encode_utf16().collect::<Vec<_>>();
-                name.push(0);
-
+                let name = encode_wide_null(entry.key());
                 entry.insert(name).as_slice()
             }
         }
     }
+
+    /// Get the cached representation of `name` and cache it.  
+    /// Names which are cached by `seed` will be copied instead of being encoded again.
+    pub fn get_or_seed(&mut self, name: &OsStr, seed: &FileNameU16Cache) -> &[u16] {
+        if !self.cache.contains_key(name) {
+            let encoded = match seed.get(name) {
+                Some(encoded) => encoded.to_vec(),
+                None => encode_wide_null(name),
+            };
+            self.cache.insert(name.to_os_string(), encoded);
+        }
+
+        &self.cache[name]
+    }
+
+    /// Get the cached representation of `name` without caching it.
+    pub fn get(&self, name: impl AsRef<OsStr>) -> Option<&[u16]> {
+        self.cache.get(name.as_ref()).map(Vec::as_slice)
+    }
+
+    /// Get the cached representation of `name` or encode it without caching it.
+    pub fn get_or_encode(&self, name: impl AsRef<OsStr>) -> Cow<'_, [u16]> {
+        match self.get(&name) {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(encode_wide_null(name)),
+        }
+    }
 }
 
-struct DirectoryIteration {
-    id: u128,
+/// Error code reported when a source aborts an enumeration.
+fn enumeration_abort_code(error: &io::Error) -> HRESULT {
+    match error.raw_os_error() {
+        Some(code) => HRESULT::from_win32(code as u32),
+        None => ERROR_OPERATION_ABORTED.to_hresult(),
+    }
+}
+
+/// Upper bound of the bytes kept by `BufferedContents`.  
+/// Exceeding it releases the oldest buffered contents. Their files are fully hydrated,
+/// so the content will be streamed again from the start if it gets read.
+const BUFFERED_CONTENTS_LIMIT: usize = 64 * 1024 * 1024;
+
+/// File contents which have been buffered to determine
+/// the size of files with `FileSize::Unknown`.
+#[derive(Default)]
+struct BufferedContents {
+    contents: BTreeMap<PathBuf, Vec<u8>>,
+    /// Buffered paths, oldest first
+    order: VecDeque<PathBuf>,
+    /// Total bytes buffered
+    size: usize,
+}
+
+impl BufferedContents {
+    /// Replace an unknown file size of `entry` by buffering the whole file content.
+    pub fn resolve_size(
+        &mut self,
+        source: &dyn ProjectedFileSystemSource,
+        path: &Path,
+        entry: &mut DirectoryEntry,
+    ) -> io::Result<()> {
+        let DirectoryEntry::File(file) = entry else {
+            return Ok(());
+        };
+
+        if file.file_size != FileSize::Unknown {
+            return Ok(());
+        }
+
+        let content = match self.contents.entry(path.to_path_buf()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut content = Vec::new();
+                source
+                    .stream_file_content(path, 0, usize::MAX)?
+                    .read_to_end(&mut content)?;
+
+                log::trace!(
+                    "Buffered {} bytes for unknown sized file {}",
+                    content.len(),
+                    path.display()
+                );
+                self.size += content.len();
+                self.order.push_back(path.to_path_buf());
+                entry.insert(content)
+            }
+        };
+
+        file.file_size = FileSize::Known(content.len() as u64);
+        self.evict(path);
+        Ok(())
+    }
+
+    /// Release the oldest contents (except the one of `keep`) until the limit is met.
+    fn evict(&mut self, keep: &Path) {
+        while self.size > BUFFERED_CONTENTS_LIMIT {
+            let Some(index) = self.order.iter().position(|path| path != keep) else {
+                break;
+            };
+
+            let Some(path) = self.order.remove(index) else {
+                break;
+            };
+
+            log::debug!(
+                "Releasing buffered content of {} as the buffer limit has been exceeded",
+                path.display()
+            );
+            self.release(&path);
+        }
+    }
 
+    pub fn get(&self, path: &Path) -> Option<&[u8]> {
+        self.contents.get(path).map(Vec::as_slice)
+    }
+
+    pub fn release(&mut self, path: &Path) {
+        if let Some(content) = self.contents.remove(path) {
+            self.size -= content.len();
+            self.order.retain(|buffered| buffered != path);
+        }
+    }
+}
+
+/// Producer which requires the source to produce further entries.
+trait SourceDirectoryProducer {
+    fn next_entry(
+        &mut self,
+        source: &dyn ProjectedFileSystemSource,
+    ) -> Option<io::Result<DirectoryEntry>>;
+
+    fn reset(&mut self);
+}
+
+impl SourceDirectoryProducer for Box<dyn DirectoryProducer> {
+    fn next_entry(
+        &mut self,
+        _source: &dyn ProjectedFileSystemSource,
+    ) -> Option<io::Result<DirectoryEntry>> {
+        (**self).next_entry()
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+}
+
+/// Entries of `ProjectedFileSystemSource::list_directory` sorted according to the `SortStrategy`.
+struct SortedEntries {
     entries: Vec<DirectoryEntry>,
     current_entry: usize,
+}
+
+impl SortedEntries {
+    pub fn new(
+        library: &dyn ProjectedFSLibrary,
+        sort_strategy: &SortStrategy,
+        case_sensitive: bool,
+        prewarmed_names: &FileNameU16Cache,
+        name_cache: &mut FileNameU16Cache,
+        mut entries: Vec<DirectoryEntry>,
+    ) -> Self {
+        match sort_strategy {
+            SortStrategy::ProjFsDefault if case_sensitive => {
+                entries.sort_unstable_by(|a, b| ordinal_name_compare(a.name(), b.name()));
+            }
+            SortStrategy::ProjFsDefault => {
+                let name_cache = RefCell::new(name_cache);
+                entries.sort_unstable_by(|a, b| {
+                    let mut name_cache = name_cache.borrow_mut();
+                    let name_a = name_cache
+                        .get_or_seed(a.name().as_ref(), prewarmed_names)
+                        .as_ptr();
+                    let name_b = name_cache
+                        .get_or_seed(b.name().as_ref(), prewarmed_names)
+                        .as_ptr();
+
+                    let result =
+                        unsafe { library.prj_file_name_compare(PCWSTR(name_a), PCWSTR(name_b)) };
+                    result.cmp(&0)
+                });
+            }
+            SortStrategy::Custom(compare) => entries.sort_by(|a, b| compare(a, b)),
+            SortStrategy::PreSorted => {}
+        }
+
+        Self {
+            entries,
+            current_entry: 0,
+        }
+    }
+}
+
+impl DirectoryProducer for SortedEntries {
+    fn next_entry(&mut self) -> Option<io::Result<DirectoryEntry>> {
+        let entry = self.entries.get(self.current_entry)?.clone();
+        self.current_entry += 1;
+        Some(Ok(entry))
+    }
+
+    fn reset(&mut self) {
+        self.current_entry = 0;
+    }
+}
+
+/// Entries of `ProjectedFileSystemSource::list_directory_chunk` requested chunk by chunk.
+struct ChunkedEntries {
+    target: PathBuf,
+    chunk_size: usize,
+
+    entries: VecDeque<DirectoryEntry>,
+    /// Offset of the next chunk within the whole directory listing
+    next_offset: usize,
+    last_chunk: bool,
+}
+
+impl ChunkedEntries {
+    pub fn new(target: PathBuf, chunk_size: usize, entries: Vec<DirectoryEntry>) -> Self {
+        Self {
+            target,
+            chunk_size,
+
+            last_chunk: entries.len() < chunk_size,
+            next_offset: entries.len(),
+            entries: entries.into(),
+        }
+    }
+}
+
+impl SourceDirectoryProducer for ChunkedEntries {
+    fn next_entry(
+        &mut self,
+        source: &dyn ProjectedFileSystemSource,
+    ) -> Option<io::Result<DirectoryEntry>> {
+        if self.entries.is_empty() && !self.last_chunk {
+            let chunk = source
+                .list_directory_chunk(&self.target, self.next_offset, self.chunk_size)
+                .unwrap_or_else(|| Ok(Vec::new()));
+
+            match chunk {
+                Ok(entries) => {
+                    self.last_chunk = entries.len() < self.chunk_size;
+                    self.next_offset += entries.len();
+                    self.entries = entries.into();
+                }
+                Err(error) => return Some(Err(error)),
+            }
+        }
+
+        self.entries.pop_front().map(Ok)
+    }
 
-    name_cache: Rc<RefCell<FileNameU16Cache>>,
+    fn reset(&mut self) {
+        self.entries.clear();
+        self.next_offset = 0;
+        self.last_chunk = false;
+    }
+}
+
+struct DirectoryIteration {
+    id: EnumerationId,
+    target: PathBuf,
+
+    producer: Box<dyn SourceDirectoryProducer>,
+    /// Entry which has been produced but not yet consumed
+    current_entry: Option<DirectoryEntry>,
+    /// Number of entries written to ProjFS since the last (re)start
+    returned_entries: u64,
+
+    /// Set if the source aborted the enumeration
+    aborted: Option<HRESULT>,
+    /// Value of the enumeration generation when the enumeration has been started
+    generation: u64,
+
+    /// Encoded names of the produced entries.  
+    /// Kept for the whole enumeration as ProjFS resumes (and restarts) enumerations
+    /// with the entries which did not fit into the previous buffer.
+    name_cache: FileNameU16Cache,
     search_expression: Option<Vec<u16>>,
 }
 
 impl DirectoryIteration {
     pub fn from_unsorted(
         library: &dyn ProjectedFSLibrary,
-        id: u128,
-        mut entries: Vec<DirectoryEntry>,
+        sort_strategy: &SortStrategy,
+        case_sensitive: bool,
+        prewarmed_names: &FileNameU16Cache,
+        id: EnumerationId,
+        target: PathBuf,
+        entries: Vec<DirectoryEntry>,
     ) -> Self {
-        let name_cache: Rc<RefCell<FileNameU16Cache>> = Default::default();
-        entries.sort_unstable_by({
-            let name_cache = name_cache.clone();
-            move |a, b| {
-                let mut name_cache = name_cache.borrow_mut();
-                let name_a = name_cache.get_or_cache(a.name().to_string()).as_ptr();
-                let name_b = name_cache.get_or_cache(b.name().to_string()).as_ptr();
-
-                let result =
-                    unsafe { library.prj_file_name_compare(PCWSTR(name_a), PCWSTR(name_b)) };
-                result.cmp(&0)
-            }
-        });
+        let mut name_cache = FileNameU16Cache::default();
+        let producer: Box<dyn DirectoryProducer> = Box::new(SortedEntries::new(
+            library,
+            sort_strategy,
+            case_sensitive,
+            prewarmed_names,
+            &mut name_cache,
+            entries,
+        ));
+
+        let mut iteration = Self::from_producer(id, target, Box::new(producer));
+        iteration.name_cache = name_cache;
+        iteration
+    }
+
+    /// Create an iteration for a source which provides the
+    /// (already sorted) directory entries in chunks.
+    pub fn from_chunked(
+        id: EnumerationId,
+        target: PathBuf,
+        chunk_size: usize,
+        entries: Vec<DirectoryEntry>,
+    ) -> Self {
+        let producer = ChunkedEntries::new(target.clone(), chunk_size, entries);
+        Self::from_producer(id, target, Box::new(producer))
+    }
 
+    fn from_producer(
+        id: EnumerationId,
+        target: PathBuf,
+        producer: Box<dyn SourceDirectoryProducer>,
+    ) -> Self {
         Self {
             id,
+            target,
 
-            entries,
-            current_entry: 0,
+            producer,
+            current_entry: None,
+            returned_entries: 0,
 
-            name_cache,
+            aborted: None,
+            generation: 0,
+
+            name_cache: Default::default(),
             search_expression: None,
         }
     }
 
-    pub fn peek_entry(&mut self) -> Option<&DirectoryEntry> {
-        let index = self.current_entry;
-        if index < self.entries.len() {
-            Some(&self.entries[index])
-        } else {
-            None
+    /// Get the next entry of the enumeration alongside its encoded name.
+    pub fn peek_entry(
+        &mut self,
+        source: &dyn ProjectedFileSystemSource,
+        generation: &AtomicU64,
+        prewarmed_names: &FileNameU16Cache,
+    ) -> std::result::Result<Option<(&mut DirectoryEntry, &[u16])>, HRESULT> {
+        if let Some(code) = self.aborted {
+            return Err(code);
+        }
+
+        if generation.load(atomic::Ordering::Relaxed) != self.generation {
+            log::debug!(
+                "Enumeration {} of {} has been canceled",
+                self.id,
+                self.target.display()
+            );
+
+            self.aborted = Some(ERROR_OPERATION_ABORTED.to_hresult());
+            return Err(ERROR_OPERATION_ABORTED.to_hresult());
+        }
+
+        while self.current_entry.is_none() {
+            self.current_entry = match self.producer.next_entry(source) {
+                Some(Ok(entry)) if !is_valid_file_name(entry.name()) => {
+                    /* ProjFS would reject the entry and abort the enumeration */
+                    log::warn!(
+                        "Skipping entry {:?} of {} as it is not a valid file name",
+                        entry.name(),
+                        self.target.display()
+                    );
+                    continue;
+                }
+                Some(Ok(entry)) => Some(entry),
+                Some(Err(error)) => {
+                    let code = enumeration_abort_code(&error);
+                    log::debug!(
+                        "Source aborted enumeration {} of {}: {}",
+                        self.id,
+                        self.target.display(),
+                        error
+                    );
+
+                    self.aborted = Some(code);
+                    return Err(code);
+                }
+                None => break,
+            };
         }
+
+        Ok(self.current_entry.as_mut().map(|entry| {
+            let name = self
+                .name_cache
+                .get_or_seed(entry.name().as_ref(), prewarmed_names);
+            (entry, name)
+        }))
     }
 
     pub fn consume_entry(&mut self) {
-        self.current_entry += 1;
+        self.current_entry = None;
     }
 
     pub fn reset_enumeration(&mut self) {
         self.search_expression = None;
-        self.current_entry = 0;
+        self.current_entry = None;
+        self.returned_entries = 0;
+        self.producer.reset();
     }
 }
 
-pub type RawProjectionContext = Mutex<ProjectionContext>;
+/// Context passed to all ProjFS callbacks.
+pub struct RawProjectionContext {
+    context: Mutex<ProjectionContext>,
+
+    /// Commands which are currently being processed.  
+    /// These are accessible without locking the context as
+    /// cancellations arrive while the context is locked by the command.
+    commands: Arc<Mutex<BTreeMap<i32, CancellationToken>>>,
+
+    /// Completes asynchronous reads without locking the context.
+    async_completions: Arc<AsyncCompletions>,
+
+    /// Invoked for failed callbacks without locking the context.
+    error_sink: Option<Box<CallbackErrorSink>>,
+
+    /// Consulted for notifications without locking the context.
+    notification_path_filter: Option<Box<NotificationPathFilter>>,
+
+    /// Incremented to cancel all active enumerations.  
+    /// Enumerations check this without locking the context while producing entries
+    /// as the context is locked while they are being served.
+    enumeration_generation: Arc<AtomicU64>,
+}
+
+impl RawProjectionContext {
+    pub fn lock(&self) -> MutexGuard<'_, ProjectionContext> {
+        self.context.lock()
+    }
+
+    /// Register a command so it can be canceled by ProjFS.
+    pub fn begin_command(&self, command_id: i32) -> CommandGuard {
+        let cancellation = CancellationToken::new();
+        self.commands
+            .lock()
+            .insert(command_id, cancellation.clone());
+
+        CommandGuard {
+            commands: self.commands.clone(),
+            command_id,
+            cancellation,
+        }
+    }
+
+    /// Returns `true` if `notification` should be dispatched to the source.
+    pub fn notification_matches(&self, notification: &Notification) -> bool {
+        let Some(filter) = &self.notification_path_filter else {
+            return true;
+        };
+
+        match notification {
+            Notification::FileCreated(file)
+            | Notification::FileOpened(file)
+            | Notification::FileClosed(file, _)
+            | Notification::FileOverwritten(file)
+            | Notification::PreSetHardlink(file)
+            | Notification::HardlinkCreated(file)
+            | Notification::PreFileDelete(file)
+            | Notification::FilePreConvertToFull(file) => filter(&file.path),
+
+            Notification::PreFileRename(info) | Notification::FileRenamed(info) => {
+                [info.source.as_deref(), info.destination.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .any(filter)
+            }
+        }
+    }
+
+    pub fn report_error(&self, error: CallbackError) {
+        log::debug!("{}", error);
+        if let Some(sink) = &self.error_sink {
+            sink(error);
+        }
+    }
+
+    pub fn cancel_command(&self, command_id: i32) {
+        if let Some(cancellation) = self.commands.lock().get(&command_id) {
+            log::debug!("Canceling command {}", command_id);
+            cancellation.cancel();
+        }
+    }
+
+    /// Cancel all running commands and drop asynchronous reads which did not complete yet.  
+    /// Waits for reads which are currently being completed.
+    pub fn cancel_all_commands(&self) {
+        for cancellation in self.commands.lock().values() {
+            cancellation.cancel();
+        }
+
+        self.async_completions.stop();
+    }
+}
+
+/// Registration of a running command.  
+/// The guard is not bound to the context so it can be moved
+/// onto the thread completing an asynchronous command.
+pub struct CommandGuard {
+    commands: Arc<Mutex<BTreeMap<i32, CancellationToken>>>,
+    command_id: i32,
+    cancellation: CancellationToken,
+}
+
+impl CommandGuard {
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+}
+
+impl Drop for CommandGuard {
+    fn drop(&mut self) {
+        self.commands.lock().remove(&self.command_id);
+    }
+}
 pub struct ProjectionContext {
     library: Arc<dyn ProjectedFSLibrary>,
     source: Box<dyn ProjectedFileSystemSource>,
-    directory_enumerations: BTreeMap<u128, DirectoryIteration>,
+    async_source: Option<(Arc<dyn AsyncSource>, AsyncExecutor)>,
+    mode: ProjectionMode,
+    read_only_attributes: bool,
+    directory_enumerations: BTreeMap<EnumerationId, DirectoryIteration>,
+    buffered_contents: BufferedContents,
+    provider_id: [u8; 16],
+    notification_senders: Vec<NotificationSender>,
+    enumeration_chunk_size: usize,
+    sort_strategy: SortStrategy,
+    /// Sort and match names ordinally (see `ProjectedFileSystemBuilder::case_sensitive`)
+    case_sensitive: bool,
+    /// Names encoded ahead of time (see `ProjectedFileSystem::prewarm_names`)
+    prewarmed_names: FileNameU16Cache,
+    enumeration_stats: EnumerationStats,
+    report_missing_directories: bool,
+    /// See `RawProjectionContext::enumeration_generation`
+    enumeration_generation: Arc<AtomicU64>,
+
+    /// Alignment of writes required by ProjFS (see `ProjectedFileSystem::write_alignment`)
+    write_alignment: usize,
+
+    /// Size of all files with `Hydration::Full` which have not been read yet
+    full_hydration: BTreeMap<PathBuf, u64>,
+
+    /// File placeholders written by the projection.  
+    /// Reads are served according to the placeholder without asking the source for the entry again.
+    placeholder_files: BTreeMap<PathBuf, PlaceholderFile>,
+}
+
+/// Properties of a file placeholder relevant for serving its content.
+#[derive(Debug, Clone, Copy)]
+struct PlaceholderFile {
+    /// `None` if the size of the file is unknown
+    size: Option<u64>,
+    sparse: bool,
+}
+
+impl PlaceholderFile {
+    fn new(entry: &DirectoryEntry) -> Self {
+        Self {
+            size: entry.size(),
+            sparse: entry.attributes().contains(FileAttributes::SPARSE_FILE),
+        }
+    }
 }
 
 impl ProjectionContext {
-    pub fn register_enumeration(&mut self, target: PathBuf, id: u128) {
-        let old_enumeration = self.directory_enumerations.insert(
-            id,
-            DirectoryIteration::from_unsorted(
-                &*self.library,
+    pub fn register_enumeration(
+        &mut self,
+        target: PathBuf,
+        id: EnumerationId,
+    ) -> std::result::Result<(), HRESULT> {
+        if self.directory_enumerations.contains_key(&id) {
+            /*
+             * ProjFS never reuses the id of an active enumeration.
+             * Keep the existing enumeration intact and report the collision.
+             */
+            log::error!("Enumeration id {} is already in use", id);
+            return Err(ERROR_ALREADY_EXISTS.to_hresult());
+        }
+
+        if self.report_missing_directories
+            && !target.as_os_str().is_empty()
+            && !self
+                .source
+                .get_directory_entry(&target)
+                .is_some_and(|entry| entry.is_dir())
+        {
+            log::debug!(
+                "Rejecting enumeration {} of missing directory {}",
                 id,
-                self.source.list_directory(&target),
-            ),
-        );
+                target.display()
+            );
+            return Err(ERROR_PATH_NOT_FOUND.to_hresult());
+        }
+
+        if let Some(producer) = self.source.directory_producer(&target) {
+            let producer = producer.map_err(|error| {
+                log::debug!(
+                    "Source aborted enumeration {} of {}: {}",
+                    id,
+                    target.display(),
+                    error
+                );
+                enumeration_abort_code(&error)
+            })?;
 
-        if let Some(enumeration) = old_enumeration {
-            log::warn!("Duplicate enumeration id {:X}", enumeration.id);
+            self.insert_enumeration(DirectoryIteration::from_producer(
+                id,
+                target,
+                Box::new(producer),
+            ));
+            return Ok(());
         }
+
+        let chunk_size = self.enumeration_chunk_size;
+        let enumeration = match self.source.list_directory_chunk(&target, 0, chunk_size) {
+            Some(Ok(entries)) => DirectoryIteration::from_chunked(id, target, chunk_size, entries),
+            Some(Err(error)) => {
+                log::debug!(
+                    "Source aborted enumeration {} of {}: {}",
+                    id,
+                    target.display(),
+                    error
+                );
+                return Err(enumeration_abort_code(&error));
+            }
+            None => {
+                let entries = self.source.list_directory(&target);
+                DirectoryIteration::from_unsorted(
+                    &*self.library,
+                    &self.sort_strategy,
+                    self.case_sensitive,
+                    &self.prewarmed_names,
+                    id,
+                    target,
+                    entries,
+                )
+            }
+        };
+
+        self.insert_enumeration(enumeration);
+        Ok(())
+    }
+
+    fn insert_enumeration(&mut self, mut enumeration: DirectoryIteration) {
+        enumeration.generation = self.enumeration_generation.load(atomic::Ordering::Relaxed);
+
+        let stats = &mut self.enumeration_stats;
+        stats.enumerations += 1;
+        stats.max_depth = stats.max_depth.max(enumeration.target.components().count());
+
+        self.directory_enumerations
+            .insert(enumeration.id, enumeration);
+
+        let stats = &mut self.enumeration_stats;
+        stats.active_enumerations = self.directory_enumerations.len();
+        stats.max_concurrent_enumerations = stats
+            .max_concurrent_enumerations
+            .max(stats.active_enumerations);
+    }
+
+    pub fn finish_enumeration(&mut self, id: EnumerationId) -> bool {
+        let Some(enumeration) = self.directory_enumerations.remove(&id) else {
+            return false;
+        };
+
+        let stats = &mut self.enumeration_stats;
+        stats.active_enumerations = self.directory_enumerations.len();
+
+        let entries = stats
+            .entries_per_directory
+            .entry(enumeration.target)
+            .or_default();
+        *entries = (*entries).max(enumeration.returned_entries);
+        true
     }
 
-    pub fn finish_enumeration(&mut self, id: u128) -> bool {
-        self.directory_enumerations.remove(&id).is_some()
+    pub fn forward_notification(&mut self, notification: &Notification) {
+        /* drop all channels which have been closed by the receiver */
+        self.notification_senders
+            .retain(|sender| sender.send(notification.clone()));
     }
 }
 
+/// Sending half of a channel created by `notification_channel` or `notification_stream`.
+enum NotificationSender {
+    Channel(Sender<Notification>),
+
+    #[cfg(feature = "async")]
+    Stream(futures_channel::mpsc::UnboundedSender<Notification>),
+}
+
+impl NotificationSender {
+    /// Returns `false` if the receiver has been dropped.
+    fn send(&self, notification: Notification) -> bool {
+        match self {
+            Self::Channel(sender) => sender.send(notification).is_ok(),
+
+            #[cfg(feature = "async")]
+            Self::Stream(sender) => sender.unbounded_send(notification).is_ok(),
+        }
+    }
+}
+
+/// Statistics of the directory enumerations of a projection.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct EnumerationStats {
+    /// Number of enumerations which have been started
+    pub enumerations: u64,
+
+    /// Number of enumerations which are currently running
+    pub active_enumerations: usize,
+
+    /// Highest number of enumerations which have been running at the same time
+    pub max_concurrent_enumerations: usize,
+
+    /// Number of path components of the deepest enumerated directory
+    /// (the projection root has a depth of zero)
+    pub max_depth: usize,
+
+    /// Largest number of entries returned by a single finished enumeration of each directory.  
+    /// Entries excluded by a search expression are not counted.
+    pub entries_per_directory: BTreeMap<PathBuf, u64>,
+}
+
+/// Statistics of a `ProjectedFileSystem::hydrate` call.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct HydrateStats {
+    /// Number of files which have been fully read
+    pub files: u64,
+
+    /// Number of directories which have been enumerated
+    pub directories: u64,
+
+    /// Total number of bytes read
+    pub bytes: u64,
+}
+
 pub struct ProjectedFileSystem {
     library: Arc<dyn ProjectedFSLibrary>,
     instance_id: GUID,
+    root: PathBuf,
+    remove_root_on_drop: bool,
 
     raw_context: *mut RawProjectionContext,
     virtualization_context: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
 }
 
+fn create_version_info(
+    provider_id: &[u8; 16],
+    content_id: Option<[u8; 16]>,
+) -> PRJ_PLACEHOLDER_VERSION_INFO {
+    let mut version_info = PRJ_PLACEHOLDER_VERSION_INFO::default();
+    version_info.ProviderID[..provider_id.len()].copy_from_slice(provider_id);
+    if let Some(content_id) = content_id {
+        version_info.ContentID[..content_id.len()].copy_from_slice(&content_id);
+    }
+
+    version_info
+}
+
+/// Names which can not be used as file or directory names on Windows.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Check if `name` can be used as a single file or directory name.
+fn is_valid_file_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    !name.is_empty()
+        && !name.ends_with(['.', ' '])
+        && !name.contains(|char: char| char.is_control() || "<>:\"/\\|?*".contains(char))
+        && !RESERVED_NAMES
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Validate the projection root path before passing it to ProjFS.
+fn validate_root(root: &Path) -> Result<()> {
+    let valid = root.components().all(|component| match component {
+        Component::Normal(name) => name.to_str().is_none_or(is_valid_file_name),
+        _ => true,
+    });
+
+    if valid && !root.as_os_str().is_empty() {
+        Ok(())
+    } else {
+        Err(Error::InvalidRootPath(root.to_path_buf()))
+    }
+}
+
+/// Ensure the projection root is an (empty) directory.
+fn prepare_root(root: &Path, create_root: bool, require_empty: bool) -> Result<()> {
+    match fs::metadata(root) {
+        Ok(metadata) if !metadata.is_dir() => Err(Error::InvalidRootPath(root.to_path_buf())),
+        Ok(_) => {
+            if require_empty && fs::read_dir(root)?.next().is_some() {
+                Err(Error::RootNotEmpty(root.to_path_buf()))
+            } else {
+                Ok(())
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound && create_root => {
+            log::debug!("Creating projection root {}", root.display());
+            fs::create_dir_all(root)?;
+            Ok(())
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            Err(Error::RootNotFound(root.to_path_buf()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Check if `path` is a reparse point (e.g. a virtualization root of a previous projection).
+fn is_reparse_point(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT.0 != 0)
+}
+
+/// Mark the directory at `path` as case-sensitive.  
+/// Directories created within it afterwards inherit the flag.
+fn set_case_sensitive(path: &Path) -> io::Result<()> {
+    /* FILE_CASE_SENSITIVE_INFO */
+    #[repr(C)]
+    struct CaseSensitiveInfo {
+        flags: u32,
+    }
+    const FILE_CS_FLAG_CASE_SENSITIVE_DIR: u32 = 0x00000001;
+
+    let directory = fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
+        .open(path)?;
+
+    let info = CaseSensitiveInfo {
+        flags: FILE_CS_FLAG_CASE_SENSITIVE_DIR,
+    };
+    unsafe {
+        SetFileInformationByHandle(
+            HANDLE(directory.as_raw_handle() as isize),
+            FileCaseSensitiveInfo,
+            &info as *const _ as *const c_void,
+            std::mem::size_of::<CaseSensitiveInfo>() as u32,
+        )
+    }
+    .map_err(io::Error::from)
+}
+
+/// Remove the ProjFS reparse point from a root which has been marked
+/// as virtualization root but could not be projected.  
+/// Without this, subsequent attempts to start a projection at the root would fail.
+fn unmark_projection_root(root: &Path) {
+    let result = fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_OPEN_REPARSE_POINT.0 | FILE_FLAG_BACKUP_SEMANTICS.0)
+        .open(root)
+        .and_then(|root| {
+            /* REPARSE_DATA_BUFFER header with an empty reparse data buffer */
+            let mut header = [0u8; 8];
+            header[0..4].copy_from_slice(&IO_REPARSE_TAG_PROJFS.to_le_bytes());
+
+            unsafe {
+                DeviceIoControl(
+                    HANDLE(root.as_raw_handle() as isize),
+                    FSCTL_DELETE_REPARSE_POINT,
+                    Some(header.as_ptr() as *const c_void),
+                    header.len() as u32,
+                    None,
+                    0,
+                    None,
+                    None,
+                )
+            }
+            .map_err(io::Error::from)
+        });
+
+    match result {
+        Ok(()) => log::debug!("Unmarked projection root {}", root.display()),
+        Err(err) => log::warn!(
+            "Failed to unmark projection root {}: {}",
+            root.display(),
+            err
+        ),
+    }
+}
+
+/// ProjFS notification types reporting notifications of `kind`.
+fn notify_types(kind: NotificationKind) -> PRJ_NOTIFY_TYPES {
+    match kind {
+        NotificationKind::FileCreated => PRJ_NOTIFY_NEW_FILE_CREATED,
+        NotificationKind::FileOpened => PRJ_NOTIFY_FILE_OPENED,
+        NotificationKind::FileClosed => PRJ_NOTIFY_TYPES(
+            PRJ_NOTIFY_FILE_HANDLE_CLOSED_FILE_DELETED.0
+                | PRJ_NOTIFY_FILE_HANDLE_CLOSED_FILE_MODIFIED.0
+                | PRJ_NOTIFY_FILE_HANDLE_CLOSED_NO_MODIFICATION.0,
+        ),
+        NotificationKind::FileOverwritten => PRJ_NOTIFY_FILE_OVERWRITTEN,
+        NotificationKind::PreFileRename => PRJ_NOTIFY_PRE_RENAME,
+        NotificationKind::FileRenamed => PRJ_NOTIFY_FILE_RENAMED,
+        NotificationKind::PreSetHardlink => PRJ_NOTIFY_PRE_SET_HARDLINK,
+        NotificationKind::HardlinkCreated => PRJ_NOTIFY_HARDLINK_CREATED,
+        NotificationKind::PreFileDelete => PRJ_NOTIFY_PRE_DELETE,
+        NotificationKind::FilePreConvertToFull => PRJ_NOTIFY_FILE_PRE_CONVERT_TO_FULL,
+    }
+}
+
 static EMPTY_U16_STRING: &[u16] = &[0];
 impl ProjectedFileSystem {
+    /// Start a projected file system at `root` with the default options.  
+    /// Use `ProjectedFileSystem::builder` for further configuration.
+    ///
+    /// Note:
+    /// Relative roots are resolved against the current working directory.
+    /// Roots exceeding `MAX_PATH` are passed to ProjFS with the `\\?\` prefix,
+    /// therefore callers do not need to add the prefix themselves.
     pub fn new(root: &Path, source: impl ProjectedFileSystemSource + 'static) -> Result<Self> {
-        let instance_id = GUID::new()?;
-        let mut root_encoded = root.to_string_lossy().encode_utf16().collect::<Vec<_>>();
-        root_encoded.push(0);
+        Self::builder().build(root, source)
+    }
 
-        let library = load_library()?;
-        unsafe {
-            library.prj_mark_directory_as_placeholder(
-                PCWSTR(root_encoded.as_ptr()),
-                PCWSTR::null(),
-                None,
-                &instance_id,
-            )
+    /// Start a projected file system at `root` using a custom implementation of the ProjFS API.  
+    /// See `ProjectedFileSystemBuilder::library` for details.
+    pub fn new_with_library(
+        root: &Path,
+        source: impl ProjectedFileSystemSource + 'static,
+        library: Arc<dyn ProjectedFSLibrary>,
+    ) -> Result<Self> {
+        Self::builder().library(library).build(root, source)
+    }
+
+    pub fn builder() -> ProjectedFileSystemBuilder {
+        ProjectedFileSystemBuilder::new()
+    }
+
+    pub(crate) fn start(
+        root: &Path,
+        source: Box<dyn ProjectedFileSystemSource>,
+        options: ProjectedFileSystemBuilder,
+    ) -> Result<Self> {
+        validate_root(root)?;
+        /* ProjFS requires a full path and the root must stay valid if the working directory changes */
+        let root = &std::path::absolute(root)?;
+        if options.pool_threads != 0 && options.pool_threads < options.concurrent_threads {
+            return Err(Error::InvalidThreadCounts(
+                options.pool_threads,
+                options.concurrent_threads,
+            ));
         }
-        .map_err(Error::MarkProjectionRoot)?;
 
-        let context = Box::new(Mutex::new(ProjectionContext {
-            library: library.clone(),
-            source: Box::new(source),
-            directory_enumerations: Default::default(),
-        }));
+        let async_source = match options.async_source {
+            Some(async_source) => {
+                let executor = match options.async_spawner {
+                    Some(spawner) => AsyncExecutor::Spawner(spawner),
+                    None => AsyncExecutor::Pool(ReadPool::new(options.async_read_threads)?),
+                };
+                Some((async_source, executor))
+            }
+            None => None,
+        };
+
+        /* a root projected before with the same instance id keeps its placeholders */
+        let reuse_root = options.instance_id.is_some() && is_reparse_point(root);
+        prepare_root(root, options.create_root, !reuse_root)?;
+        if options.case_sensitive {
+            set_case_sensitive(root)
+                .map_err(|err| Error::EnableCaseSensitivity(root.to_path_buf(), err))?;
+        }
+
+        let instance_id = match options.instance_id {
+            Some(instance_id) => GUID::from_u128(instance_id),
+            None => GUID::new()?,
+        };
+        let root_encoded = encode_path_null(root);
+        let unmark_root = |root: &Path| {
+            if !reuse_root {
+                unmark_projection_root(root);
+            }
+        };
+
+        let library = match options.library {
+            Some(library) => library,
+            None => load_library()?,
+        };
+        if reuse_root {
+            log::debug!(
+                "Reusing virtualization root {} with instance {:X}",
+                root.display(),
+                instance_id.to_u128()
+            );
+        } else {
+            let version_info =
+                create_version_info(&options.provider_id, source.content_id(Path::new("")));
+
+            /*
+             * The target path only names a directory within an existing root which should be marked
+             * as placeholder. ProjFS has no mode where missing files fall through to a backing directory,
+             * therefore the root is always marked without a target.
+             */
+            unsafe {
+                library.prj_mark_directory_as_placeholder(
+                    PCWSTR(root_encoded.as_ptr()),
+                    PCWSTR::null(),
+                    Some(&version_info),
+                    &instance_id,
+                )
+            }
+            .map_err(Error::MarkProjectionRoot)?;
+        }
+
+        let enumeration_generation = Arc::new(AtomicU64::new(0));
+        let context = Box::new(RawProjectionContext {
+            context: Mutex::new(ProjectionContext {
+                library: library.clone(),
+                source,
+                async_source,
+                mode: options.mode,
+                read_only_attributes: options.mode == ProjectionMode::ReadOnly
+                    && options.read_only_attributes,
+                directory_enumerations: Default::default(),
+                buffered_contents: Default::default(),
+                provider_id: options.provider_id,
+                notification_senders: Default::default(),
+                enumeration_chunk_size: options.enumeration_chunk_size,
+                sort_strategy: options.sort_strategy,
+                case_sensitive: options.case_sensitive,
+                prewarmed_names: Default::default(),
+                enumeration_stats: Default::default(),
+                report_missing_directories: options.report_missing_directories,
+                enumeration_generation: enumeration_generation.clone(),
+                /* updated once the projection has been started */
+                write_alignment: DIRECT_WRITE_ALIGNMENT,
+                full_hydration: Default::default(),
+                placeholder_files: Default::default(),
+            }),
+            commands: Default::default(),
+            async_completions: Default::default(),
+            error_sink: options.error_sink,
+            notification_path_filter: options.notification_path_filter,
+            enumeration_generation,
+        });
 
         let callbacks = Box::new(PRJ_CALLBACKS {
             StartDirectoryEnumerationCallback: Some(native::start_directory_enumeration_callback),
@@ -200,36 +1215,33 @@ impl ProjectedFileSystem {
             GetFileDataCallback: Some(native::get_file_data_callback),
 
             NotificationCallback: Some(native::notification_callback),
+            CancelCommandCallback: Some(native::cancel_command_callback),
             ..Default::default()
         });
 
         let raw_context = Box::into_raw(context);
         let virtualization_context = {
-            #[allow(clippy::identity_op)]
-            let notification_mask = 0
-                | PRJ_NOTIFY_FILE_HANDLE_CLOSED_FILE_DELETED.0
-                | PRJ_NOTIFY_FILE_HANDLE_CLOSED_FILE_MODIFIED.0
-                | PRJ_NOTIFY_FILE_HANDLE_CLOSED_NO_MODIFICATION.0
-                | PRJ_NOTIFY_FILE_OPENED.0
-                | PRJ_NOTIFY_FILE_OVERWRITTEN.0
-                | PRJ_NOTIFY_FILE_PRE_CONVERT_TO_FULL.0
-                | PRJ_NOTIFY_FILE_RENAMED.0
-                | PRJ_NOTIFY_HARDLINK_CREATED.0
-                | PRJ_NOTIFY_NEW_FILE_CREATED.0
-                | PRJ_NOTIFY_PRE_DELETE.0
-                | PRJ_NOTIFY_PRE_RENAME.0
-                | PRJ_NOTIFY_PRE_SET_HARDLINK.0;
+            let notification_mask = NotificationKind::ALL
+                .into_iter()
+                .fold(0, |mask, kind| mask | notify_types(kind).0);
 
             let mut notification_mapping = PRJ_NOTIFICATION_MAPPING {
                 NotificationBitMask: PRJ_NOTIFY_TYPES(notification_mask),
                 NotificationRoot: PCWSTR(EMPTY_U16_STRING.as_ptr()),
             };
 
+            let flags = if options.use_negative_path_cache {
+                PRJ_FLAG_USE_NEGATIVE_PATH_CACHE
+            } else {
+                PRJ_FLAG_NONE
+            };
+
             let options = PRJ_STARTVIRTUALIZING_OPTIONS {
+                Flags: flags,
+                PoolThreadCount: options.pool_threads,
+                ConcurrentThreadCount: options.concurrent_threads,
                 NotificationMappings: &mut notification_mapping,
                 NotificationMappingsCount: 1,
-
-                ..Default::default()
             };
 
             let result = unsafe {
@@ -244,43 +1256,478 @@ impl ProjectedFileSystem {
                 Ok(virtualization_context) => virtualization_context,
                 Err(err) => {
                     unsafe { drop(Box::from_raw(raw_context)) }
+                    unmark_root(root);
                     return Err(Error::StartProjection(err));
                 }
             }
         };
 
-        log::debug!(
-            "Started projection {:X} at {}",
-            instance_id.to_u128(),
-            root.to_string_lossy()
-        );
-        Ok(Self {
-            library,
-            instance_id,
+        match unsafe { library.prj_get_virtualization_instance_info(virtualization_context) } {
+            Ok(instance_info) if instance_info.WriteAlignment > 0 => {
+                unsafe { &*raw_context }.lock().write_alignment =
+                    instance_info.WriteAlignment as usize;
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!(
+                "Failed to query the write alignment, assuming {} bytes: {}",
+                DIRECT_WRITE_ALIGNMENT,
+                err
+            ),
+        }
+
+        log::debug!(
+            "Started projection {:X} at {}",
+            instance_id.to_u128(),
+            root.display()
+        );
+        Ok(Self {
+            library,
+            instance_id,
+            root: root.to_path_buf(),
+            remove_root_on_drop: options.remove_root_on_drop,
+
+            raw_context,
+            virtualization_context,
+        })
+    }
+
+    fn context(&self) -> &RawProjectionContext {
+        /* the context is valid until the projection gets dropped */
+        unsafe { &*self.raw_context }
+    }
+
+    /// Alignment in bytes ProjFS requires for the offset and length of written file data
+    /// (the sector size of the underlying volume).  
+    /// Writes of file contents are split into chunks of a multiple of this alignment.
+    /// Slices returned by `ProjectedFileSystemSource::file_content_slice` are only written
+    /// without copying if they are aligned to it.
+    pub fn write_alignment(&self) -> usize {
+        self.context().lock().write_alignment
+    }
+
+    /// Check if the projection is still served by ProjFS.  
+    /// Returns `false` if ProjFS does no longer know the virtualization instance
+    /// (e.g. the ProjFS driver has been unloaded or the Windows feature has been disabled)
+    /// or if the projection root does no longer exist (e.g. it has been deleted
+    /// or its volume has been dismounted).
+    ///
+    /// Note:
+    /// This does not lock the projection and is cheap enough to be polled.  
+    /// A projection does not recover once it stopped running. Drop it before
+    /// starting a new projection at the same root.
+    pub fn is_running(&self) -> bool {
+        if let Err(err) = unsafe {
+            self.library
+                .prj_get_virtualization_instance_info(self.virtualization_context)
+        } {
+            log::debug!(
+                "Projection {:X} is no longer known to ProjFS: {}",
+                self.instance_id.to_u128(),
+                err
+            );
+            return false;
+        }
+
+        fs::metadata(&self.root).is_ok_and(|metadata| metadata.is_dir())
+    }
+
+    /// Statistics about the directory enumerations requested by ProjFS.
+    pub fn enumeration_stats(&self) -> EnumerationStats {
+        self.context().lock().enumeration_stats.clone()
+    }
+
+    /// Cancel all directory enumerations which are currently active
+    /// (e.g. because the backing data of the source is no longer available).  
+    /// Canceled enumerations fail with `ERROR_OPERATION_ABORTED` before producing their next entry.
+    /// Enumerations started afterwards are not affected.
+    ///
+    /// Note:  
+    /// This does not lock the projection and therefore does not wait for
+    /// enumerations which are currently blocked within the source.  
+    /// Use `enumeration_stats` to inspect the number of active enumerations.
+    pub fn cancel_enumerations(&self) {
+        self.context()
+            .enumeration_generation
+            .fetch_add(1, atomic::Ordering::Relaxed);
+    }
+
+    /// Encode `names` ahead of time instead of converting them to UTF-16 whenever ProjFS
+    /// requests them (e.g. for providers with a fixed namespace).  
+    /// Names are used for the entries of directory enumerations and for the paths of
+    /// placeholders, which are relative to the projection root (e.g. `dir\file.txt`).
+    ///
+    /// Note:
+    /// Prewarmed names are kept until the projection is dropped.
+    pub fn prewarm_names(&self, names: impl IntoIterator<Item = String>) {
+        let mut context = self.context().lock();
+        for name in names {
+            context.prewarmed_names.get_or_cache(name);
+        }
+    }
+
+    /// Receive all non cancelable notifications through a channel.  
+    /// The notifications will be send after `ProjectedFileSystemSource::handle_notification` has been called.
+    ///
+    /// Note:  
+    /// Cancelable pre-notifications (see `Notification::is_cancelable`) require a synchronous
+    /// decision and are therefore only delivered to `ProjectedFileSystemSource::handle_notification`.
+    pub fn notification_channel(&self) -> Receiver<Notification> {
+        let (sender, receiver) = mpsc::channel();
+        self.context()
+            .lock()
+            .notification_senders
+            .push(NotificationSender::Channel(sender));
+        receiver
+    }
+
+    /// Receive all non cancelable notifications through an asynchronous stream.  
+    /// The stream does not depend on a specific runtime and ends once the projection has been dropped.
+    ///
+    /// Note:  
+    /// The same restrictions as for `notification_channel` apply. Cancelable pre-notifications
+    /// must be handled by `ProjectedFileSystemSource::handle_notification`.
+    #[cfg(feature = "async")]
+    pub fn notification_stream(&self) -> impl futures_core::Stream<Item = Notification> {
+        let (sender, receiver) = futures_channel::mpsc::unbounded();
+        self.context()
+            .lock()
+            .notification_senders
+            .push(NotificationSender::Stream(sender));
+        receiver
+    }
+
+    /// Hydrate the projected file or directory at `path` (relative to the projection root)
+    /// so it's available on disk without the provider running.  
+    /// Directories will be enumerated and all contained files fully read.
+    /// If `recursive` is set, all sub directories will be hydrated as well.
+    ///
+    /// Note:  
+    /// This must not be called from within a `ProjectedFileSystemSource` callback
+    /// as it relies on the projection callbacks itself.
+    ///
+    /// Returns `Error::InvalidRelativePath` if `path` is absolute or contains `..`.
+    pub fn hydrate(&self, path: &Path, recursive: bool) -> Result<HydrateStats> {
+        /* a rooted path would replace the projection root when joined */
+        let is_rooted = path
+            .components()
+            .any(|component| matches!(component, Component::Prefix(_) | Component::RootDir));
+        let relative_path = Some(path)
+            .filter(|_| !is_rooted)
+            .and_then(|path| normalize_request_path(Some(path)).ok())
+            .ok_or_else(|| Error::InvalidRelativePath(path.to_path_buf()))?;
+
+        let mut stats = HydrateStats::default();
+        let mut pending = vec![self.root.join(relative_path)];
+        while let Some(target) = pending.pop() {
+            if !fs::metadata(&target)?.is_dir() {
+                stats.bytes += io::copy(&mut fs::File::open(&target)?, &mut io::sink())?;
+                stats.files += 1;
+                continue;
+            }
+
+            stats.directories += 1;
+            for entry in fs::read_dir(&target)? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    if recursive {
+                        pending.push(entry.path());
+                    }
+                } else {
+                    stats.bytes += io::copy(&mut fs::File::open(entry.path())?, &mut io::sink())?;
+                    stats.files += 1;
+                }
+            }
+        }
+
+        log::debug!(
+            "Hydrated {} files ({} bytes) in {} directories at {}",
+            stats.files,
+            stats.bytes,
+            stats.directories,
+            path.display()
+        );
+        Ok(stats)
+    }
+
+    /// Clear the negative path cache of ProjFS (see `use_negative_path_cache`)
+    /// so paths which have been added to the source become visible.  
+    /// Returns the number of entries which have been removed from the cache.
+    pub fn clear_negative_path_cache(&self) -> Result<u32> {
+        let mut total_entries = 0;
+        unsafe {
+            self.library.prj_clear_negative_path_cache(
+                self.virtualization_context,
+                Some(&mut total_entries),
+            )?
+        };
+        Ok(total_entries)
+    }
+
+    /// Query the on disk state of `path` (relative to the projection root).  
+    /// Use this to decide if an item can be updated or deleted without losing local modifications.
+    pub fn on_disk_state(&self, path: impl AsRef<Path>) -> Result<OnDiskFileState> {
+        let path = encode_path_null(&self.root.join(path));
+        let file_state = unsafe {
+            self.library
+                .prj_get_on_disk_file_state(PCWSTR(path.as_ptr()))?
+        };
+        Ok(OnDiskFileState(file_state.0 as u32))
+    }
+
+    /// Update the placeholder of `path` (relative to the projection root) with `entry`,
+    /// e.g. after the item has been modified within the source.  
+    /// Hydrated files are dehydrated and will be read again from the source.
+    ///
+    /// Note:  
+    /// Items with local modifications are not updated and ProjFS fails the update with
+    /// `ERROR_FILE_SYSTEM_VIRTUALIZATION_INVALID_OPERATION`.
+    pub fn update_file(&self, path: impl AsRef<Path>, mut entry: DirectoryEntry) -> Result<()> {
+        let path = path.as_ref();
+        let placeholder_info = {
+            let mut context = self.context().lock();
+            native::create_placeholder_info(&mut context, path, &mut entry)?
+        };
+
+        let name = encode_wide_null(path);
+        unsafe {
+            self.library.prj_update_file_if_needed(
+                self.virtualization_context,
+                PCWSTR(name.as_ptr()),
+                &placeholder_info,
+                mem::size_of_val(&placeholder_info) as u32,
+                PRJ_UPDATE_NONE,
+                None,
+            )?
+        };
+        Ok(())
+    }
+
+    /// Delete the placeholder of `path` (relative to the projection root),
+    /// e.g. after the item has been removed from the source.
+    ///
+    /// Note:  
+    /// Items with local modifications are not deleted and ProjFS fails the deletion with
+    /// `ERROR_FILE_SYSTEM_VIRTUALIZATION_INVALID_OPERATION`.
+    pub fn delete_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let name = encode_wide_null(path);
+        unsafe {
+            self.library.prj_delete_file(
+                self.virtualization_context,
+                PCWSTR(name.as_ptr()),
+                PRJ_UPDATE_NONE,
+                None,
+            )?
+        };
+
+        self.context().lock().placeholder_files.remove(path);
+        Ok(())
+    }
+
+    /// Make ProjFS reflect the current state of `path` (relative to the projection root)
+    /// within the source.  
+    /// The placeholder gets updated if the source still provides `path` and deleted otherwise.
+    ///
+    /// Note:  
+    /// Items which do not exist on disk are left untouched, as ProjFS queries
+    /// the source for them anyway. Paths cached by the negative path cache stay invisible
+    /// until `clear_negative_path_cache` has been called.
+    pub fn refresh(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let entry = self.context().lock().source.get_directory_entry(path);
+        let result = match entry {
+            Some(entry) => self.update_file(path, entry),
+            None => self.delete_file(path),
+        };
+
+        match result {
+            Err(Error::GenericWindows(error))
+                if error.code() == ERROR_FILE_NOT_FOUND.to_hresult()
+                    || error.code() == ERROR_PATH_NOT_FOUND.to_hresult() =>
+            {
+                /* nothing has been materialized yet */
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// List all paths (relative to the projection root) which have been materialized on disk
+    /// as placeholder or full file/directory. Tombstones are not included.
+    ///
+    /// Note:  
+    /// Placeholder directories are traversed by listing the source and querying the
+    /// on disk state of each entry, which does not trigger any projection callbacks.
+    /// Only full directories are read from disk as ProjFS no longer projects their contents.  
+    /// Therefore items created locally within a placeholder directory (which are not part
+    /// of the source) are not found.
+    pub fn materialized_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut result = Vec::new();
+        let mut pending = vec![PathBuf::new()];
+        while let Some(directory) = pending.pop() {
+            let entries = self.context().lock().source.list_directory(&directory);
+            for entry in entries {
+                let path = directory.join(entry.name());
+                let Some(state) = self.materialized_state(&path)? else {
+                    continue;
+                };
+
+                result.push(path.clone());
+                if !entry.is_dir() {
+                    continue;
+                }
+
+                if state.contains(OnDiskFileState::FULL) {
+                    self.collect_full_directory(&path, &mut result)?;
+                } else {
+                    pending.push(path);
+                }
+            }
+        }
+
+        result.sort();
+        Ok(result)
+    }
+
+    /// Returns the on disk state of `path` if it has been materialized.
+    fn materialized_state(&self, path: &Path) -> Result<Option<OnDiskFileState>> {
+        let state = match self.on_disk_state(path) {
+            Ok(state) => state,
+            Err(Error::GenericWindows(error))
+                if error.code() == ERROR_FILE_NOT_FOUND.to_hresult()
+                    || error.code() == ERROR_PATH_NOT_FOUND.to_hresult() =>
+            {
+                /* virtual items only exist within the source */
+                return Ok(None);
+            }
+            Err(error) => return Err(error),
+        };
+
+        if state.bits() == 0 || state.contains(OnDiskFileState::TOMBSTONE) {
+            Ok(None)
+        } else {
+            Ok(Some(state))
+        }
+    }
+
+    /// Collect all items within the full directory `path` (relative to the projection root).
+    fn collect_full_directory(&self, path: &Path, result: &mut Vec<PathBuf>) -> Result<()> {
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(directory) = pending.pop() {
+            for entry in fs::read_dir(self.root.join(&directory))? {
+                let entry = entry?;
+                let path = directory.join(entry.file_name());
+                if entry.file_type()?.is_dir() {
+                    pending.push(path.clone());
+                }
+                result.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The (absolute) root directory of the projection.  
+    /// All paths reported by ProjFS are relative to this directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Stop the projection and wait at most `timeout` for all running callbacks to finish.  
+    /// Returns `Error::StopTimeout` if the callbacks did not finish in time.
+    ///
+    /// Note:
+    /// ProjFS itself has no timeout for stopping a projection, therefore this is best-effort.  
+    /// The projection gets stopped on a helper thread which keeps waiting in the background
+    /// after the timeout elapsed. If the callbacks finished in time, the resources of the projection
+    /// (including the source) are released and the root is removed (see `remove_root_on_drop`)
+    /// on the calling thread.  
+    /// Otherwise the resources of the projection are leaked as the source might not be `Send`
+    /// and therefore can not be dropped by the helper thread. `on_stop` will not be called
+    /// and the root is kept in this case.
+    pub fn stop_with_timeout(mut self, timeout: Duration) -> Result<()> {
+        let instance_id = self.instance_id.to_u128();
+        self.context().cancel_all_commands();
+
+        /* only the library and the virtualization context handle are used by the helper thread */
+        let library = self.library.clone();
+        let virtualization_context = self.virtualization_context;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name(format!("projfs-stop-{:X}", instance_id))
+            .spawn(move || {
+                unsafe { library.prj_stop_virtualizing(virtualization_context) };
+                let _ = sender.send(());
+            })?;
+
+        match receiver.recv_timeout(timeout) {
+            Ok(()) => {
+                self.release_stopped();
+                Ok(())
+            }
+            Err(_) => {
+                log::warn!(
+                    "Projection {:X} did not stop within {:?}. Leaking the projection context.",
+                    instance_id,
+                    timeout
+                );
+
+                /* callbacks might still be running, the context must never be freed */
+                self.raw_context = ptr::null_mut();
+                Err(Error::StopTimeout)
+            }
+        }
+    }
+
+    /// Release the resources of the projection once `prj_stop_virtualizing` returned.
+    fn release_stopped(&mut self) {
+        /*
+         * PrjStopVirtualizing waits untill all callbacks have been processed.
+         * Therefore it's safe to assume that no one else will use the raw_context.
+         */
+        let context = unsafe { Box::from_raw(self.raw_context) };
+        self.raw_context = ptr::null_mut();
+
+        context.lock().source.on_stop();
+        drop(context);
+
+        log::debug!("Stopped projection for {:X}", self.instance_id.to_u128());
 
-            raw_context,
-            virtualization_context,
-        })
+        if self.remove_root_on_drop {
+            if let Err(err) = fs::remove_dir_all(&self.root) {
+                log::warn!(
+                    "Failed to remove projection root {}: {}",
+                    self.root.display(),
+                    err
+                );
+            }
+        }
     }
 }
 
 impl Drop for ProjectedFileSystem {
     fn drop(&mut self) {
+        if self.raw_context.is_null() {
+            /* the projection has already been stopped by `stop_with_timeout` */
+            return;
+        }
+
         log::trace!("Stopping projection for {:X}", self.instance_id.to_u128());
 
+        /*
+         * Asynchronous reads complete their commands outside of any callback.
+         * They must not use the virtualization context once the projection has been stopped.
+         */
+        self.context().cancel_all_commands();
+
         /* Shutdown projection and wait for all callbacks to finish. */
         unsafe {
             self.library
                 .prj_stop_virtualizing(self.virtualization_context)
         };
 
-        /*
-         * PrjStopVirtualizing waits untill all callbacks have been processed.
-         * Therefore it's safe to assume that no one else will use the raw_context.
-         */
-        unsafe { drop(Box::from_raw(self.raw_context)) };
-
-        log::debug!("Stopped projection for {:X}", self.instance_id.to_u128());
+        self.release_stopped();
     }
 }
 
@@ -290,10 +1737,21 @@ mod native {
             c_void,
             OsString,
         },
+        io::{
+            self,
+            Read,
+        },
         mem,
-        ops::ControlFlow,
+        ops::{
+            ControlFlow,
+            Range,
+        },
         os::windows::ffi::OsStringExt,
-        path::PathBuf,
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::Arc,
     };
 
     use windows::{
@@ -305,11 +1763,16 @@ mod native {
         Win32::{
             Foundation::{
                 BOOLEAN,
+                ERROR_ACCESS_DENIED,
+                ERROR_DIRECTORY_NOT_SUPPORTED,
                 ERROR_FILE_NOT_FOUND,
+                ERROR_HANDLE_EOF,
                 ERROR_INSUFFICIENT_BUFFER,
+                ERROR_IO_PENDING,
+                ERROR_OPERATION_ABORTED,
                 ERROR_OUTOFMEMORY,
+                ERROR_PATH_NOT_FOUND,
                 STATUS_CANNOT_DELETE,
-                STATUS_SUCCESS,
             },
             Storage::ProjectedFileSystem::{
                 PRJ_CALLBACK_DATA,
@@ -318,6 +1781,7 @@ mod native {
                 PRJ_DIR_ENTRY_BUFFER_HANDLE,
                 PRJ_EXTENDED_INFO,
                 PRJ_FILE_BASIC_INFO,
+                PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
                 PRJ_NOTIFICATION,
                 PRJ_NOTIFICATION_FILE_HANDLE_CLOSED_FILE_DELETED,
                 PRJ_NOTIFICATION_FILE_HANDLE_CLOSED_FILE_MODIFIED,
@@ -338,21 +1802,46 @@ mod native {
     };
 
     use super::{
-        FileNameU16Cache,
+        create_version_info,
+        CommandGuard,
+        PlaceholderFile,
+        ProjectionContext,
         RawProjectionContext,
     };
     use crate::{
         aligned_buffer::PrjAlignedBuffer,
-        utils::io_result_to_hresult,
+        collation::name_match_case_sensitive,
+        library::ProjectedFSLibrary,
+        utils::{
+            io_result_to_hresult,
+            normalize_relative_path,
+            split_stream_name,
+        },
+        within_request,
+        AsyncCompletions,
+        AsyncExecutor,
+        AsyncSource,
+        CallbackError,
+        CallbackKind,
+        CancellationToken,
         DirectoryEntry,
+        EnumerationId,
+        FileAttributes,
+        FileChunks,
         FileCloseAction,
         FileRenameInfo,
+        Hydration,
         Notification,
         ProjectedFile,
+        ProjectedFileSystemSource,
+        ProjectionMode,
+        RequestContext,
+        CALLBACK_SUCCESS,
+        ENUMERATION_ENDED,
     };
 
     impl DirectoryEntry {
-        fn get_basic_info(&self) -> PRJ_FILE_BASIC_INFO {
+        fn get_basic_info(&self, read_only_attributes: bool) -> PRJ_FILE_BASIC_INFO {
             let mut basic_info = PRJ_FILE_BASIC_INFO::default();
 
             match self {
@@ -370,8 +1859,12 @@ mod native {
                 Self::File(file) => {
                     basic_info.IsDirectory = BOOLEAN::from(false);
 
-                    basic_info.FileSize = file.file_size as i64;
+                    /* unknown sizes are only resolved for placeholders and reported as zero otherwise */
+                    basic_info.FileSize = file.file_size.known().unwrap_or_default() as i64;
                     basic_info.FileAttributes = file.file_attributes;
+                    if read_only_attributes {
+                        basic_info.FileAttributes |= FileAttributes::READONLY.bits();
+                    }
 
                     basic_info.CreationTime = file.creation_time as i64;
                     basic_info.LastAccessTime = file.last_access_time as i64;
@@ -389,11 +1882,38 @@ mod native {
         }
 
         fn get_extended_info(&self) -> Option<PRJ_EXTENDED_INFO> {
+            /*
+             * The only extended info supported by ProjFS is PRJ_EXT_INFO_TYPE_SYMLINK.
+             * Placeholders always carry the ProjFS reparse tag, custom reparse tags
+             * (e.g. for cloud sync indicators) can not be projected.
+             */
             None
         }
     }
 
     type CallbackData = crate::CallbackData<'static, RawProjectionContext>;
+
+    /// Execute a callback and report unexpected failures to the error sink of the projection.
+    fn execute<F>(callback_data: CallbackData, kind: CallbackKind, executor: F) -> HRESULT
+    where
+        F: FnOnce(&CallbackData) -> Result<(), HRESULT>,
+    {
+        let context = callback_data.context;
+        let path = callback_data.file_path.clone();
+
+        let result = callback_data.execute(kind, executor);
+        let expected = result.is_ok()
+            || result == ERROR_IO_PENDING.to_hresult()
+            || result == ERROR_FILE_NOT_FOUND.to_hresult()
+            || result == ERROR_PATH_NOT_FOUND.to_hresult()
+            || result == ERROR_DIRECTORY_NOT_SUPPORTED.to_hresult()
+            || kind == CallbackKind::Notification;
+
+        if !expected {
+            context.report_error(CallbackError { kind, path, result });
+        }
+        result
+    }
     pub unsafe extern "system" fn start_directory_enumeration_callback(
         callback_data: *const PRJ_CALLBACK_DATA,
         enumeration_id: *const GUID,
@@ -401,13 +1921,18 @@ mod native {
         let enumeration_id = &*enumeration_id;
         let callback_data: CallbackData = callback_data.into();
 
-        callback_data.execute(move |callback_data| {
-            let target = callback_data.file_path.clone().unwrap_or_default();
-            let mut context = callback_data.context.lock();
-            context.register_enumeration(target, enumeration_id.to_u128());
-
-            Ok(())
-        })
+        execute(
+            callback_data,
+            CallbackKind::StartDirectoryEnumeration,
+            move |callback_data| {
+                let target = callback_data.request_path()?;
+                let mut context = callback_data.context.lock();
+                context.source.on_raw_callback(
+                    &callback_data.raw_info(CallbackKind::StartDirectoryEnumeration),
+                );
+                context.register_enumeration(target, EnumerationId::from_guid(enumeration_id))
+            },
+        )
     }
 
     pub unsafe extern "system" fn end_directory_enumeration_callback(
@@ -417,17 +1942,25 @@ mod native {
         let enumeration_id = &*enumeration_id;
         let callback_data: CallbackData = callback_data.into();
 
-        callback_data.execute(move |callback_data| {
-            let mut context = callback_data.context.lock();
-            if !context.finish_enumeration(enumeration_id.to_u128()) {
-                log::warn!(
-                    "Tried to end an non existing enumeration with id {:X}",
-                    enumeration_id.to_u128()
+        execute(
+            callback_data,
+            CallbackKind::EndDirectoryEnumeration,
+            move |callback_data| {
+                let mut context = callback_data.context.lock();
+                context.source.on_raw_callback(
+                    &callback_data.raw_info(CallbackKind::EndDirectoryEnumeration),
                 );
-            }
+                let enumeration_id = EnumerationId::from_guid(enumeration_id);
+                if !context.finish_enumeration(enumeration_id) {
+                    log::warn!(
+                        "Tried to end an non existing enumeration with id {}",
+                        enumeration_id
+                    );
+                }
 
-            Ok(())
-        })
+                Ok(())
+            },
+        )
     }
 
     pub unsafe extern "system" fn get_directory_enumeration_callback(
@@ -451,41 +1984,65 @@ mod native {
             }
         };
 
-        callback_data.execute(move |callback_data| {
-            let mut context = callback_data.context.lock();
-            let library = context.library.clone();
-
-            let enumeration = context
-                .directory_enumerations
-                .get_mut(&enumeration_id.to_u128())
-                /* Return STATUS_SUCCESS to indicate that the enumeration has ended (as it can not be found). */
-                .ok_or(STATUS_SUCCESS.to_hresult())?;
+        execute(
+            callback_data,
+            CallbackKind::GetDirectoryEnumeration,
+            move |callback_data| {
+                let mut context = callback_data.context.lock();
+                context.source.on_raw_callback(
+                    &callback_data.raw_info(CallbackKind::GetDirectoryEnumeration),
+                );
+                let context = &mut *context;
+                let library = context.library.clone();
 
-            if callback_data.flags.0 & PRJ_CB_DATA_FLAG_ENUM_RESTART_SCAN.0 > 0 {
-                enumeration.reset_enumeration();
-            }
-            if let Some(search_expression) = search_expression {
-                /* Update the search expression if given. */
-                enumeration.search_expression = Some(search_expression);
-            }
+                let enumeration = context
+                    .directory_enumerations
+                    .get_mut(&EnumerationId::from_guid(enumeration_id))
+                    /* report the end of the enumeration as it can not be found */
+                    .ok_or(ENUMERATION_ENDED)?;
 
-            let name_cache = enumeration.name_cache.clone();
-            while let Some(entry) = enumeration.peek_entry() {
-                let basic_info = entry.get_basic_info();
-                let extended_info = entry.get_extended_info();
+                if callback_data.flags.0 & PRJ_CB_DATA_FLAG_ENUM_RESTART_SCAN.0 > 0 {
+                    enumeration.reset_enumeration();
+                }
+                if let Some(search_expression) = search_expression {
+                    /* Update the search expression if given. */
+                    enumeration.search_expression = Some(search_expression);
+                }
 
-                let mut name_cache = name_cache.borrow_mut();
-                let name = name_cache.get_or_cache(entry.name().to_string());
+                let read_only_attributes = context.read_only_attributes;
+                let case_sensitive = context.case_sensitive;
+                let search_expression = enumeration.search_expression.clone();
+                while let Some((entry, name)) = enumeration.peek_entry(
+                    &*context.source,
+                    &callback_data.context.enumeration_generation,
+                    &context.prewarmed_names,
+                )? {
+                    let file_match = if let Some(search_expression) = search_expression.as_ref() {
+                        if case_sensitive {
+                            name_match_case_sensitive(name, search_expression)
+                        } else {
+                            unsafe {
+                                library
+                                    .prj_file_name_match(
+                                        PCWSTR(name.as_ptr()),
+                                        PCWSTR(search_expression.as_ptr()),
+                                    )
+                                    .as_bool()
+                            }
+                        }
+                    } else {
+                        true
+                    };
 
-                let file_match = if let Some(search_expression) = enumeration.search_expression.as_ref() {
-                    unsafe {
-                        library.prj_file_name_match(PCWSTR(name.as_ptr()), PCWSTR(search_expression.as_ptr())).as_bool()
+                    if !file_match {
+                        /* filtered entries do not count towards the single entry limit */
+                        enumeration.consume_entry();
+                        continue;
                     }
-                } else {
-                    true
-                };
 
-                if file_match {
+                    let basic_info = entry.get_basic_info(read_only_attributes);
+                    let extended_info = entry.get_extended_info();
+
                     let result = unsafe {
                         library.prj_fill_dir_entry_buffer2(
                             dir_entry_buffer_handle,
@@ -504,16 +2061,17 @@ mod native {
                         /* unexpected... */
                         return Err(err.code());
                     }
-                }
 
-                enumeration.consume_entry();
-                if callback_data.flags.0 & PRJ_CB_DATA_FLAG_ENUM_RETURN_SINGLE_ENTRY.0 > 0 {
-                    break;
+                    enumeration.consume_entry();
+                    enumeration.returned_entries += 1;
+                    if callback_data.flags.0 & PRJ_CB_DATA_FLAG_ENUM_RETURN_SINGLE_ENTRY.0 > 0 {
+                        break;
+                    }
                 }
-            }
 
-            Ok(())
-        })
+                Ok(())
+            },
+        )
     }
 
     pub unsafe extern "system" fn get_placeholder_information_callback(
@@ -521,54 +2079,499 @@ mod native {
     ) -> HRESULT {
         let callback_data: CallbackData = callback_data.into();
 
-        callback_data.execute(move |callback_data| {
-            let path = callback_data.file_path.clone().unwrap_or_default();
+        execute(
+            callback_data,
+            CallbackKind::GetPlaceholderInfo,
+            move |callback_data| {
+                let (path, _) = split_stream_name(&callback_data.request_path()?);
+
+                let mut context = callback_data.context.lock();
+                context
+                    .source
+                    .on_raw_callback(&callback_data.raw_info(CallbackKind::GetPlaceholderInfo));
+                let context = &mut *context;
+                let entry = if context.source.exists(&path) {
+                    context.source.get_directory_entry(&path)
+                } else {
+                    None
+                };
+                let Some(mut entry) = entry else {
+                    return Err(missing_entry_code(&*context.source, &path));
+                };
+
+                let placeholder_info = create_placeholder_info(context, &path, &mut entry)
+                    .map_err(io_result_to_hresult)?;
+
+                /*
+                 * ProjFS expects the path relative to the virtualization root.
+                 * Encode the path itself as `display()` would replace unrepresentable characters.
+                 */
+                let name = context.prewarmed_names.get_or_encode(path.as_os_str());
 
-            let context = callback_data.context.lock();
-            let entry = context
-                .source
-                .get_directory_entry(&path)
-                .ok_or(ERROR_FILE_NOT_FOUND.to_hresult())?;
+                if let Some(extended_info) = entry.get_extended_info() {
+                    unsafe {
+                        context
+                            .library
+                            .prj_write_placeholder_info2(
+                                callback_data.namespace_virtualization_context,
+                                PCWSTR(name.as_ptr()),
+                                &placeholder_info,
+                                mem::size_of_val(&placeholder_info) as u32,
+                                Some(&extended_info),
+                            )
+                            .map_err(|err| err.code())?;
+                    }
+                } else {
+                    unsafe {
+                        context
+                            .library
+                            .prj_write_placeholder_info(
+                                callback_data.namespace_virtualization_context,
+                                PCWSTR(name.as_ptr()),
+                                &placeholder_info,
+                                mem::size_of_val(&placeholder_info) as u32,
+                            )
+                            .map_err(|err| err.code())?;
+                    }
+                };
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Result for a `path` which does not exist within the source.  
+    /// Like Win32 does, `ERROR_PATH_NOT_FOUND` is returned if the parent directory is missing
+    /// as well and `ERROR_FILE_NOT_FOUND` if only the entry itself is missing.
+    fn missing_entry_code(source: &dyn ProjectedFileSystemSource, path: &Path) -> HRESULT {
+        let parent = path.parent().unwrap_or(Path::new(""));
+        let parent_exists = parent.as_os_str().is_empty()
+            || (source.exists(parent)
+                && source
+                    .get_directory_entry(parent)
+                    .is_some_and(|entry| entry.is_dir()));
+
+        if parent_exists {
+            ERROR_FILE_NOT_FOUND.to_hresult()
+        } else {
+            ERROR_PATH_NOT_FOUND.to_hresult()
+        }
+    }
+
+    /// Placeholder information of `entry` located at `path`.  
+    /// Unknown file sizes get resolved by buffering the file contents.
+    pub fn create_placeholder_info(
+        context: &mut ProjectionContext,
+        path: &Path,
+        entry: &mut DirectoryEntry,
+    ) -> io::Result<PRJ_PLACEHOLDER_INFO> {
+        context
+            .buffered_contents
+            .resolve_size(&*context.source, path, entry)?;
+
+        /*
+         * Buffered contents are written with a single full write as the source
+         * is unable to serve arbitrary ranges of the content.
+         */
+        let buffered = context.buffered_contents.get(path).is_some();
+        match &*entry {
+            DirectoryEntry::File(file) if file.hydration == Hydration::Full || buffered => {
+                if let Some(size) = file.file_size.known() {
+                    context.full_hydration.insert(path.to_path_buf(), size);
+                }
+            }
+            _ => {
+                context.full_hydration.remove(path);
+            }
+        }
+
+        if entry.is_file() {
+            context
+                .placeholder_files
+                .insert(path.to_path_buf(), PlaceholderFile::new(entry));
+        } else {
+            context.placeholder_files.remove(path);
+        }
 
-            let mut name_cache = FileNameU16Cache::default();
-            let name = name_cache.get_or_cache(path.display().to_string());
+        Ok(PRJ_PLACEHOLDER_INFO {
+            FileBasicInfo: entry.get_basic_info(context.read_only_attributes),
+            VersionInfo: create_version_info(&context.provider_id, context.source.content_id(path)),
+            ..PRJ_PLACEHOLDER_INFO::default()
+        })
+    }
+
+    /// Write a range of the default data stream of `path` served by the source.
+    fn write_source_range(
+        context: &mut ProjectionContext,
+        callback_data: &CallbackData,
+        path: &Path,
+        byte_offset: u64,
+        length: usize,
+        cancellation: &CancellationToken,
+    ) -> Result<(), HRESULT> {
+        if let Some(content) = context
+            .source
+            .file_content_slice(path, byte_offset as usize, length)
+        {
+            let content = content.map_err(io_result_to_hresult)?;
+            return write_file_slice(
+                &context.library,
+                callback_data.namespace_virtualization_context,
+                context.write_alignment,
+                &callback_data.data_stream_id,
+                path,
+                byte_offset,
+                length,
+                &content,
+                cancellation,
+            );
+        }
 
-            let placeholder_info = PRJ_PLACEHOLDER_INFO {
-                FileBasicInfo: entry.get_basic_info(),
-                ..PRJ_PLACEHOLDER_INFO::default()
+        let mut source: Box<dyn Read> =
+            match context
+                .source
+                .stream_file_chunks(path, byte_offset as usize, length)
+            {
+                Some(chunks) => Box::new(ChunkReader::new(chunks.map_err(io_result_to_hresult)?)),
+                None => context
+                    .source
+                    .stream_file_content_mut(path, byte_offset as usize, length, cancellation)
+                    .map_err(io_result_to_hresult)?,
             };
 
-            if let Some(extended_info) = entry.get_extended_info() {
-                unsafe {
-                    context
-                        .library
-                        .prj_write_placeholder_info2(
-                            callback_data.namespace_virtualization_context,
-                            PCWSTR(name.as_ptr()),
-                            &placeholder_info,
-                            mem::size_of_val(&placeholder_info) as u32,
-                            Some(&extended_info),
-                        )
-                        .map_err(|err| err.code())?;
+        write_file_data(
+            &context.library,
+            callback_data.namespace_virtualization_context,
+            context.write_alignment,
+            &callback_data.data_stream_id,
+            path,
+            byte_offset,
+            length,
+            &mut *source,
+            cancellation,
+        )
+    }
+
+    /// Reader over the chunks of `ProjectedFileSystemSource::stream_file_chunks`.
+    struct ChunkReader {
+        chunks: FileChunks,
+        chunk: Vec<u8>,
+        position: usize,
+    }
+
+    impl ChunkReader {
+        fn new(chunks: FileChunks) -> Self {
+            Self {
+                chunks,
+                chunk: Vec::new(),
+                position: 0,
+            }
+        }
+    }
+
+    impl Read for ChunkReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            while self.position >= self.chunk.len() {
+                match self.chunks.next() {
+                    Some(chunk) => {
+                        self.chunk = chunk?;
+                        self.position = 0;
+                    }
+                    None => return Ok(0),
                 }
-            } else {
-                unsafe {
-                    context
-                        .library
-                        .prj_write_placeholder_info(
-                            callback_data.namespace_virtualization_context,
-                            PCWSTR(name.as_ptr()),
-                            &placeholder_info,
-                            mem::size_of_val(&placeholder_info) as u32,
-                        )
-                        .map_err(|err| err.code())?;
+            }
+
+            let length = buf.len().min(self.chunk.len() - self.position);
+            buf[..length].copy_from_slice(&self.chunk[self.position..self.position + length]);
+            self.position += length;
+            Ok(length)
+        }
+    }
+
+    /// Split a read of `length` bytes at `byte_offset` into segments of
+    /// `(byte_offset, length, zero filled)` according to `zero_ranges`.  
+    /// Zero filled segments are shrunk to `write_alignment` so the
+    /// remaining segments start aligned.
+    fn split_zero_ranges(
+        byte_offset: u64,
+        length: usize,
+        zero_ranges: &[Range<u64>],
+        write_alignment: usize,
+    ) -> Vec<(u64, usize, bool)> {
+        let end = byte_offset + length as u64;
+        let alignment = write_alignment as u64;
+
+        let mut zero_ranges = zero_ranges
+            .iter()
+            .map(|range| {
+                let start = range.start.max(byte_offset).next_multiple_of(alignment);
+                let zero_end = if range.end >= end {
+                    end
+                } else {
+                    range.end - range.end % alignment
+                };
+                start..zero_end
+            })
+            .filter(|range| range.start < range.end)
+            .collect::<Vec<_>>();
+        zero_ranges.sort_by_key(|range| range.start);
+
+        let mut segments = Vec::new();
+        let mut position = byte_offset;
+        for range in zero_ranges {
+            let start = range.start.max(position);
+            if start >= range.end {
+                continue;
+            }
+
+            if start > position {
+                segments.push((position, (start - position) as usize, false));
+            }
+            segments.push((start, (range.end - start) as usize, true));
+            position = range.end;
+        }
+
+        if position < end {
+            segments.push((position, (end - position) as usize, false));
+        }
+        segments
+    }
+
+    /// Clamp a read of `length` bytes at `byte_offset` to the end of the file.  
+    /// Returns `ERROR_HANDLE_EOF` if the read starts at or past the end of the file.
+    fn clamp_read_length(
+        file_size: Option<u64>,
+        byte_offset: u64,
+        length: usize,
+    ) -> Result<usize, HRESULT> {
+        let Some(file_size) = file_size else {
+            /* the size is unknown and the read is served as requested */
+            return Ok(length);
+        };
+
+        if byte_offset >= file_size {
+            log::debug!(
+                "Rejecting read at {} past the end of the file ({} bytes)",
+                byte_offset,
+                file_size
+            );
+            return Err(ERROR_HANDLE_EOF.to_hresult());
+        }
+
+        Ok(length.min((file_size - byte_offset) as usize))
+    }
+
+    /// Write `length` bytes read from `source` into the file identified by `data_stream_id`.  
+    /// The data is written in chunks of a multiple of `write_alignment`.
+    #[allow(clippy::too_many_arguments)]
+    fn write_file_data(
+        library: &Arc<dyn ProjectedFSLibrary>,
+        virtualization_context: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        write_alignment: usize,
+        data_stream_id: &GUID,
+        path: &Path,
+        byte_offset: u64,
+        length: usize,
+        source: &mut dyn Read,
+        cancellation: &CancellationToken,
+    ) -> Result<(), HRESULT> {
+        if length == 0 {
+            /* nothing to write, an aligned buffer of size zero might not be allocatable */
+            return Ok(());
+        }
+
+        /* all chunks except the last one must be a multiple of the write alignment */
+        let chunk_length = length.min(1024 * 1024).next_multiple_of(write_alignment);
+
+        let mut buffer =
+            PrjAlignedBuffer::allocate(library.clone(), virtualization_context, chunk_length)
+                .ok_or(ERROR_OUTOFMEMORY.to_hresult())?;
+        let buffer = buffer.buffer();
+
+        let mut bytes_written = 0;
+        while bytes_written < length {
+            if cancellation.is_canceled() {
+                log::debug!(
+                    "Read of {} has been canceled after {} bytes",
+                    path.display(),
+                    bytes_written
+                );
+                return Err(ERROR_OPERATION_ABORTED.to_hresult());
+            }
+
+            let bytes_pending = length - bytes_written;
+            let chunk_length = bytes_pending.min(buffer.len());
+
+            let mut chunk_filled = 0;
+            while chunk_filled < chunk_length {
+                match source.read(&mut buffer[chunk_filled..chunk_length]) {
+                    Ok(0) => {
+                        /* the stream is shorter than the size reported for the file */
+                        log::warn!(
+                            "Source stream of {} ended after {} bytes but {} bytes were expected (reading at offset {})",
+                            path.display(),
+                            bytes_written + chunk_filled,
+                            length,
+                            byte_offset
+                        );
+                        return Err(ERROR_HANDLE_EOF.to_hresult());
+                    }
+                    Ok(bytes_read) => chunk_filled += bytes_read,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {
+                        if cancellation.is_canceled() {
+                            return Err(ERROR_OPERATION_ABORTED.to_hresult());
+                        }
+                    }
+                    Err(err) => return Err(io_result_to_hresult(err)),
                 }
+            }
+
+            let write_result = unsafe {
+                library.prj_write_file_data(
+                    virtualization_context,
+                    data_stream_id,
+                    buffer.as_ptr() as *const c_void,
+                    byte_offset + bytes_written as u64,
+                    chunk_length as u32,
+                )
             };
+            if let Err(err) = write_result {
+                log::warn!(
+                    "Failed to write projected file data for {}: {}",
+                    path.display(),
+                    err
+                );
+                return Err(err.code());
+            }
 
-            Ok(())
+            bytes_written += chunk_length;
+        }
+
+        Ok(())
+    }
+
+    /// Write the first `length` bytes of `content` into the file identified by `data_stream_id`.  
+    /// Content aligned to `write_alignment` is passed to ProjFS directly, else it will be copied
+    /// into an aligned buffer.
+    #[allow(clippy::too_many_arguments)]
+    fn write_file_slice(
+        library: &Arc<dyn ProjectedFSLibrary>,
+        virtualization_context: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        write_alignment: usize,
+        data_stream_id: &GUID,
+        path: &Path,
+        byte_offset: u64,
+        length: usize,
+        content: &[u8],
+        cancellation: &CancellationToken,
+    ) -> Result<(), HRESULT> {
+        let mut content = content.get(..length).ok_or(ERROR_HANDLE_EOF.to_hresult())?;
+        if !(content.as_ptr() as usize).is_multiple_of(write_alignment)
+            || length > u32::MAX as usize
+        {
+            return write_file_data(
+                library,
+                virtualization_context,
+                write_alignment,
+                data_stream_id,
+                path,
+                byte_offset,
+                length,
+                &mut content,
+                cancellation,
+            );
+        }
+
+        if cancellation.is_canceled() {
+            return Err(ERROR_OPERATION_ABORTED.to_hresult());
+        }
+
+        unsafe {
+            library.prj_write_file_data(
+                virtualization_context,
+                data_stream_id,
+                content.as_ptr() as *const c_void,
+                byte_offset,
+                length as u32,
+            )
+        }
+        .map_err(|err| {
+            log::warn!(
+                "Failed to write projected file data for {}: {}",
+                path.display(),
+                err
+            );
+            err.code()
         })
     }
 
+    /// Serve a read from an `AsyncSource` and complete the command once done.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_async_read(
+        library: Arc<dyn ProjectedFSLibrary>,
+        source: &dyn AsyncSource,
+        executor: &AsyncExecutor,
+        completions: Arc<AsyncCompletions>,
+        virtualization_context: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        write_alignment: usize,
+        command: CommandGuard,
+        request_context: RequestContext,
+        data_stream_id: GUID,
+        path: PathBuf,
+        byte_offset: u64,
+        length: usize,
+    ) -> io::Result<()> {
+        let command_id = request_context.command_id;
+        let read = within_request(
+            request_context,
+            source.read_file_content(path.clone(), byte_offset as usize, length),
+        );
+
+        executor.spawn(Box::pin(async move {
+            let content = read.await;
+            let Some(_completion) = completions.begin() else {
+                log::debug!(
+                    "Dropping read command {} for {} as the projection is stopping",
+                    command_id,
+                    path.display()
+                );
+                return;
+            };
+
+            let result = content.and_then(|content| {
+                write_file_data(
+                    &library,
+                    virtualization_context,
+                    write_alignment,
+                    &data_stream_id,
+                    &path,
+                    byte_offset,
+                    length,
+                    &mut content.as_slice(),
+                    command.cancellation(),
+                )
+            });
+
+            /* the command must no longer be cancelable once it has been completed */
+            drop(command);
+
+            let result = match result {
+                Ok(()) => CALLBACK_SUCCESS,
+                Err(code) => code,
+            };
+            if let Err(err) = unsafe {
+                library.prj_complete_command(virtualization_context, command_id, result, None)
+            } {
+                log::warn!(
+                    "Failed to complete read command {} for {}: {}",
+                    command_id,
+                    path.display(),
+                    err
+                );
+            }
+        }))
+    }
+
     pub unsafe extern "system" fn get_file_data_callback(
         callback_data: *const PRJ_CALLBACK_DATA,
         byte_offset: u64,
@@ -577,61 +2580,172 @@ mod native {
         let length = length as usize;
         let callback_data: CallbackData = callback_data.into();
 
-        callback_data.execute(move |callback_data| {
-            let path = callback_data.file_path.clone().unwrap_or_default();
+        execute(
+            callback_data,
+            CallbackKind::GetFileData,
+            move |callback_data| {
+                let (path, stream_name) = split_stream_name(&callback_data.request_path()?);
+
+                let command = callback_data
+                    .context
+                    .begin_command(callback_data.command_id);
+
+                let mut context = callback_data.context.lock();
+                context
+                    .source
+                    .on_raw_callback(&callback_data.raw_info(CallbackKind::GetFileData));
+                if length == 0 {
+                    /* empty files do not require any data to be written */
+                    if context
+                        .buffered_contents
+                        .get(&path)
+                        .is_some_and(<[u8]>::is_empty)
+                    {
+                        context.buffered_contents.release(&path);
+                    }
 
-            let context = callback_data.context.lock();
-            let mut source = context
-                .source
-                .stream_file_content(&path, byte_offset as usize, length)
-                .map_err(io_result_to_hresult)?;
+                    return Ok(());
+                }
 
-            let chunk_length = if length <= 1024 * 1024 {
-                length
-            } else {
-                1024 * 1024
-            };
+                let (byte_offset, length) = match context.full_hydration.get(&path) {
+                    Some(size) if stream_name.is_none() => {
+                        /* write the whole file instead of the requested range */
+                        let size = *size;
+                        context.full_hydration.remove(&path);
+                        (0, size as usize)
+                    }
+                    _ => (byte_offset, length),
+                };
 
-            let mut buffer = PrjAlignedBuffer::allocate(
-                context.library.clone(),
-                callback_data.namespace_virtualization_context,
-                chunk_length,
-            )
-            .ok_or(ERROR_OUTOFMEMORY.to_hresult())?;
-            let buffer = buffer.buffer();
+                let placeholder = if stream_name.is_none()
+                    && context.buffered_contents.get(&path).is_none()
+                {
+                    match context.placeholder_files.get(&path) {
+                        Some(placeholder) => Some(*placeholder),
+                        /* the placeholder has been written by a previous projection (or renamed) */
+                        None => context
+                            .source
+                            .get_directory_entry(&path)
+                            .map(|entry| PlaceholderFile::new(&entry)),
+                    }
+                } else {
+                    None
+                };
 
-            let mut bytes_written = 0;
-            while bytes_written < length {
-                let bytes_pending = length - bytes_written;
-                let chunk_length = bytes_pending.min(buffer.len());
+                /* never request data past the file size declared by the source */
+                let file_size = placeholder.and_then(|placeholder| placeholder.size);
+                let length = clamp_read_length(file_size, byte_offset, length)?;
+
+                if stream_name.is_none() && context.buffered_contents.get(&path).is_none() {
+                    if let Some((async_source, executor)) = &context.async_source {
+                        spawn_async_read(
+                            context.library.clone(),
+                            async_source.as_ref(),
+                            executor,
+                            callback_data.context.async_completions.clone(),
+                            callback_data.namespace_virtualization_context,
+                            context.write_alignment,
+                            command,
+                            callback_data.request_context(),
+                            callback_data.data_stream_id,
+                            path,
+                            byte_offset,
+                            length,
+                        )
+                        .map_err(io_result_to_hresult)?;
 
-                source
-                    .read_exact(&mut buffer[0..chunk_length])
-                    .map_err(io_result_to_hresult)?;
+                        return Err(ERROR_IO_PENDING.to_hresult());
+                    }
+                }
+
+                let cancellation = command.cancellation();
+                if stream_name.is_none() && context.buffered_contents.get(&path).is_none() {
+                    let segments = match placeholder {
+                        Some(placeholder) if placeholder.sparse => split_zero_ranges(
+                            byte_offset,
+                            length,
+                            &context.source.zero_ranges(&path),
+                            context.write_alignment,
+                        ),
+                        _ => vec![(byte_offset, length, false)],
+                    };
 
-                let write_result = unsafe {
-                    context.library.prj_write_file_data(
-                        callback_data.namespace_virtualization_context,
-                        &callback_data.data_stream_id,
-                        buffer.as_ptr() as *const c_void,
-                        byte_offset + bytes_written as u64,
-                        chunk_length as u32,
-                    )
+                    for (byte_offset, length, zero) in segments {
+                        if zero {
+                            /* zero filled ranges are not requested from the source */
+                            write_file_data(
+                                &context.library,
+                                callback_data.namespace_virtualization_context,
+                                context.write_alignment,
+                                &callback_data.data_stream_id,
+                                &path,
+                                byte_offset,
+                                length,
+                                &mut io::repeat(0).take(length as u64),
+                                cancellation,
+                            )?;
+                        } else {
+                            write_source_range(
+                                &mut context,
+                                callback_data,
+                                &path,
+                                byte_offset,
+                                length,
+                                cancellation,
+                            )?;
+                        }
+                    }
+
+                    return Ok(());
+                }
+
+                let mut buffered_content_consumed = false;
+                let mut source: Box<dyn Read + '_> = if let Some(stream_name) = stream_name.as_ref()
+                {
+                    context
+                        .source
+                        .stream_named_content(&path, stream_name, byte_offset as usize, length)
+                        .map_err(io_result_to_hresult)?
+                } else {
+                    let content = context.buffered_contents.get(&path).unwrap_or_default();
+
+                    let offset = byte_offset as usize;
+                    buffered_content_consumed = offset == 0 && length >= content.len();
+
+                    let content = content
+                        .get(offset..offset + length)
+                        .ok_or(ERROR_HANDLE_EOF.to_hresult())?;
+                    Box::new(content)
                 };
-                if let Err(err) = write_result {
-                    log::warn!(
-                        "Failed to write projected file data for {}: {}",
-                        path.display(),
-                        err
-                    );
-                    return Err(err.code());
+
+                write_file_data(
+                    &context.library,
+                    callback_data.namespace_virtualization_context,
+                    context.write_alignment,
+                    &callback_data.data_stream_id,
+                    &path,
+                    byte_offset,
+                    length,
+                    &mut *source,
+                    cancellation,
+                )?;
+
+                drop(source);
+                if buffered_content_consumed {
+                    /* The whole buffered content has been written and is no longer needed. */
+                    context.buffered_contents.release(&path);
                 }
 
-                bytes_written += chunk_length;
-            }
+                Ok(())
+            },
+        )
+    }
 
-            Ok(())
-        })
+    pub unsafe extern "system" fn cancel_command_callback(callback_data: *const PRJ_CALLBACK_DATA) {
+        let callback_data: CallbackData = callback_data.into();
+        callback_data
+            .context
+            .cancel_command(callback_data.command_id);
     }
 
     pub unsafe extern "system" fn notification_callback(
@@ -646,69 +2760,117 @@ mod native {
         let destination_filename = if destination_filename.is_null() {
             None
         } else {
-            Some(PathBuf::from(OsString::from_wide(
+            normalize_relative_path(&PathBuf::from(OsString::from_wide(
                 destination_filename.as_wide(),
             )))
         };
 
-        callback_data.execute(move |callback_data| {
-            let target_file = ProjectedFile {
-                file_id: callback_data.file_id.to_u128(),
-                is_directory: is_directory.as_bool(),
-                path: callback_data.file_path.clone().unwrap_or_default(),
-            };
+        execute(
+            callback_data,
+            CallbackKind::Notification,
+            move |callback_data| {
+                let target_file = ProjectedFile {
+                    file_id: callback_data.file_id.to_u128(),
+                    is_directory: is_directory.as_bool(),
+                    path: callback_data.request_path()?,
+                };
 
-            let notification = match notification {
-                PRJ_NOTIFICATION_NEW_FILE_CREATED => Notification::FileCreated(target_file),
-                PRJ_NOTIFICATION_FILE_OPENED => Notification::FileOpened(target_file),
-                PRJ_NOTIFICATION_FILE_HANDLE_CLOSED_FILE_DELETED => {
-                    Notification::FileClosed(target_file, FileCloseAction::Deleted)
-                }
-                PRJ_NOTIFICATION_FILE_HANDLE_CLOSED_FILE_MODIFIED => {
-                    Notification::FileClosed(target_file, FileCloseAction::Modified)
+                let notification = match notification {
+                    PRJ_NOTIFICATION_NEW_FILE_CREATED => Notification::FileCreated(target_file),
+                    PRJ_NOTIFICATION_FILE_OPENED => Notification::FileOpened(target_file),
+                    PRJ_NOTIFICATION_FILE_HANDLE_CLOSED_FILE_DELETED => {
+                        Notification::FileClosed(target_file, FileCloseAction::Deleted)
+                    }
+                    PRJ_NOTIFICATION_FILE_HANDLE_CLOSED_FILE_MODIFIED => {
+                        Notification::FileClosed(target_file, FileCloseAction::Modified)
+                    }
+                    PRJ_NOTIFICATION_FILE_HANDLE_CLOSED_NO_MODIFICATION => {
+                        Notification::FileClosed(target_file, FileCloseAction::NoModification)
+                    }
+                    PRJ_NOTIFICATION_FILE_OVERWRITTEN => {
+                        /*
+                         * The parameters contain PostCreate.NotificationMask which is an output
+                         * for a new notification mask and does not describe the overwritten file.
+                         */
+                        Notification::FileOverwritten(target_file)
+                    }
+
+                    PRJ_NOTIFICATION_PRE_RENAME => Notification::PreFileRename(FileRenameInfo {
+                        source: callback_data
+                            .file_path
+                            .as_deref()
+                            .and_then(normalize_relative_path),
+                        destination: destination_filename,
+                        is_directory: is_directory.as_bool(),
+                    }),
+                    PRJ_NOTIFICATION_FILE_RENAMED => Notification::FileRenamed(FileRenameInfo {
+                        source: callback_data
+                            .file_path
+                            .as_deref()
+                            .and_then(normalize_relative_path),
+                        destination: destination_filename,
+                        is_directory: is_directory.as_bool(),
+                    }),
+
+                    PRJ_NOTIFICATION_PRE_SET_HARDLINK => Notification::PreSetHardlink(target_file),
+                    PRJ_NOTIFICATION_HARDLINK_CREATED => Notification::HardlinkCreated(target_file),
+
+                    PRJ_NOTIFICATION_FILE_PRE_CONVERT_TO_FULL => {
+                        Notification::FilePreConvertToFull(target_file)
+                    }
+                    PRJ_NOTIFICATION_PRE_DELETE => Notification::PreFileDelete(target_file),
+
+                    notification => {
+                        log::warn!("Invalid notification {}", notification.0);
+                        return Ok(());
+                    }
+                };
+
+                let matches_filter = callback_data.context.notification_matches(&notification);
+                if !matches_filter && !notification.is_cancelable() {
+                    /* neither dispatched nor rejected, no need to lock the context */
+                    return Ok(());
                 }
-                PRJ_NOTIFICATION_FILE_HANDLE_CLOSED_NO_MODIFICATION => {
-                    Notification::FileClosed(target_file, FileCloseAction::NoModification)
+
+                let mut context = callback_data.context.lock();
+                if matches_filter {
+                    context
+                        .source
+                        .on_raw_callback(&callback_data.raw_info(CallbackKind::Notification));
                 }
-                PRJ_NOTIFICATION_FILE_OVERWRITTEN => Notification::FileOverwritten(target_file),
-
-                PRJ_NOTIFICATION_PRE_RENAME => Notification::PreFileRename(FileRenameInfo {
-                    source: callback_data.file_path.clone(),
-                    destination: destination_filename,
-                }),
-                PRJ_NOTIFICATION_FILE_RENAMED => Notification::FileRenamed(FileRenameInfo {
-                    source: callback_data.file_path.clone(),
-                    destination: destination_filename,
-                }),
-
-                PRJ_NOTIFICATION_PRE_SET_HARDLINK => Notification::PreSetHardlink(target_file),
-                PRJ_NOTIFICATION_HARDLINK_CREATED => Notification::HardlinkCreated(target_file),
-
-                PRJ_NOTIFICATION_FILE_PRE_CONVERT_TO_FULL => {
-                    Notification::FilePreConvertToFull(target_file)
+
+                if context.mode == ProjectionMode::ReadOnly && notification.is_cancelable() {
+                    log::debug!("Rejecting {:?} in read only mode", notification);
+                    return Err(match notification {
+                        Notification::PreFileDelete(_) => STATUS_CANNOT_DELETE.to_hresult(),
+                        _ => ERROR_ACCESS_DENIED.to_hresult(),
+                    });
                 }
-                PRJ_NOTIFICATION_PRE_DELETE => Notification::PreFileDelete(target_file),
 
-                notification => {
-                    log::warn!("Invalid notification {}", notification.0);
+                if !matches_filter {
                     return Ok(());
                 }
-            };
 
-            let context = callback_data.context.lock();
-            let action = context.source.handle_notification(&notification);
-            if matches!(action, ControlFlow::Break(_)) {
-                if notification.is_cancelable() {
-                    return Err(STATUS_CANNOT_DELETE.to_hresult());
+                let action = context.source.handle_notification_mut(&notification);
+                if let ControlFlow::Break(veto) = action {
+                    if notification.is_cancelable() {
+                        return Err(veto
+                            .result()
+                            .unwrap_or_else(|| notification.default_veto_result()));
+                    }
+
+                    log::warn!(
+                        "Tried to cancel a non cancelable action: {:?}",
+                        notification
+                    );
                 }
 
-                log::warn!(
-                    "Tried to cancel a non cancelable action: {:?}",
-                    notification
-                );
-            }
+                if !notification.is_cancelable() {
+                    context.forward_notification(&notification);
+                }
 
-            Ok(())
-        })
+                Ok(())
+            },
+        )
     }
 }
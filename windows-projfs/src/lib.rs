@@ -10,9 +10,67 @@ pub use source::*;
 mod fs;
 pub use fs::*;
 
+mod builder;
+pub use builder::*;
+
+mod attributes;
+pub use attributes::*;
+
+mod file_state;
+pub use file_state::*;
+
+mod metadata_view;
+pub use metadata_view::*;
+
+mod cancellation;
+pub use cancellation::*;
+
+mod enumeration;
+pub use enumeration::*;
+
+mod collation;
+pub use collation::*;
+
+mod fn_source;
+pub use fn_source::*;
+
+mod overlay;
+pub use overlay::*;
+
+mod filter;
+pub use filter::*;
+
+mod logged;
+pub use logged::*;
+
+mod async_source;
+pub use async_source::*;
+
+mod process_image;
+pub use process_image::*;
+
+mod request_context;
+pub use request_context::*;
+
+mod raw_callback;
+pub use raw_callback::*;
+
+mod callback_error;
+pub use callback_error::*;
+
+mod windows_feature;
+pub use windows_feature::*;
+
 mod callback_data;
 use callback_data::*;
 
-mod aligned_buffer;
 mod library;
+pub use library::{
+    is_projfs_available,
+    load_library,
+    ProjectedFSLibrary,
+};
+
+mod aligned_buffer;
+mod listing_cache;
 mod utils;
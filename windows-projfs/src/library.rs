@@ -2,25 +2,47 @@ use std::ffi::c_void;
 
 use windows::{
     core::{
+        w,
         GUID,
+        HRESULT,
         PCWSTR,
     },
     Win32::{
-        Foundation::BOOLEAN,
+        Foundation::{
+            FreeLibrary,
+            BOOLEAN,
+        },
         Storage::ProjectedFileSystem::{
             PRJ_CALLBACKS,
+            PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS,
             PRJ_DIR_ENTRY_BUFFER_HANDLE,
             PRJ_EXTENDED_INFO,
             PRJ_FILE_BASIC_INFO,
+            PRJ_FILE_STATE,
             PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
             PRJ_PLACEHOLDER_INFO,
             PRJ_PLACEHOLDER_VERSION_INFO,
             PRJ_STARTVIRTUALIZING_OPTIONS,
+            PRJ_UPDATE_FAILURE_CAUSES,
+            PRJ_UPDATE_TYPES,
+            PRJ_VIRTUALIZATION_INSTANCE_INFO,
+        },
+        System::LibraryLoader::{
+            LoadLibraryExW,
+            LOAD_LIBRARY_SEARCH_SYSTEM32,
         },
     },
 };
 
-pub trait ProjectedFSLibrary {
+/// Abstraction of the ProjFS API (`projectedfslib.dll`).  
+/// Every method mirrors the ProjFS function with the same name.
+///
+/// # Safety
+/// All methods are thin wrappers around the raw ProjFS API.
+/// Callers must uphold the requirements of the corresponding ProjFS function
+/// and implementations must behave like the ProjFS function they mirror.
+#[allow(clippy::missing_safety_doc)]
+pub trait ProjectedFSLibrary: Send + Sync {
     unsafe fn prj_allocate_aligned_buffer(
         &self,
         namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
@@ -48,6 +70,8 @@ pub trait ProjectedFSLibrary {
         options: Option<*const PRJ_STARTVIRTUALIZING_OPTIONS>,
     ) -> windows::core::Result<PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT>;
 
+    /// Implementations must only return once all callbacks have finished
+    /// as the projection context is reclaimed right after the call returns.
     unsafe fn prj_stop_virtualizing(
         &self,
         namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
@@ -86,6 +110,71 @@ pub trait ProjectedFSLibrary {
         placeholderinfosize: u32,
         extendedinfo: ::core::option::Option<*const PRJ_EXTENDED_INFO>,
     ) -> windows::core::Result<()>;
+
+    unsafe fn prj_complete_command(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        commandid: i32,
+        completionresult: HRESULT,
+        extendedparameters: Option<*const PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS>,
+    ) -> windows::core::Result<()>;
+
+    unsafe fn prj_clear_negative_path_cache(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        totalentrynumber: Option<*mut u32>,
+    ) -> windows::core::Result<()>;
+
+    unsafe fn prj_get_on_disk_file_state(
+        &self,
+        destinationfilename: PCWSTR,
+    ) -> windows::core::Result<PRJ_FILE_STATE>;
+
+    unsafe fn prj_get_virtualization_instance_info(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+    ) -> windows::core::Result<PRJ_VIRTUALIZATION_INSTANCE_INFO>;
+
+    unsafe fn prj_update_file_if_needed(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+        placeholderinfosize: u32,
+        updateflags: PRJ_UPDATE_TYPES,
+        failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+    ) -> windows::core::Result<()>;
+
+    unsafe fn prj_delete_file(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        updateflags: PRJ_UPDATE_TYPES,
+        failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+    ) -> windows::core::Result<()>;
+}
+
+/// Check if the ProjFS API (`projectedfslib.dll`) can be loaded from the system directory.  
+/// Works with and without feature "dynamic-import" and can be called before `load_library`
+/// to fall back gracefully on systems where the Windows feature "Projected File System"
+/// is not enabled.
+///
+/// Note:  
+/// Without feature "dynamic-import" the ProjFS API is linked at build time and Windows
+/// resolves it when the process starts, unless it is delay loaded (`/DELAYLOAD:projectedfslib.dll`).
+/// Distributing a single binary for systems with and without ProjFS therefore
+/// requires either feature "dynamic-import" or delay loading.
+pub fn is_projfs_available() -> bool {
+    let module =
+        unsafe { LoadLibraryExW(w!("projectedfslib.dll"), None, LOAD_LIBRARY_SEARCH_SYSTEM32) };
+
+    match module {
+        Ok(module) => {
+            let _ = unsafe { FreeLibrary(module) };
+            true
+        }
+        Err(_) => false,
+    }
 }
 
 #[cfg(not(feature = "dynamic-import"))]
@@ -98,24 +187,34 @@ mod lib_impl {
     use windows::{
         core::{
             GUID,
+            HRESULT,
             PCWSTR,
         },
         Win32::{
             Foundation::BOOLEAN,
             Storage::ProjectedFileSystem::{
                 PRJ_CALLBACKS,
+                PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS,
                 PRJ_DIR_ENTRY_BUFFER_HANDLE,
                 PRJ_EXTENDED_INFO,
                 PRJ_FILE_BASIC_INFO,
+                PRJ_FILE_STATE,
                 PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
                 PRJ_PLACEHOLDER_INFO,
                 PRJ_PLACEHOLDER_VERSION_INFO,
                 PRJ_STARTVIRTUALIZING_OPTIONS,
+                PRJ_UPDATE_FAILURE_CAUSES,
+                PRJ_UPDATE_TYPES,
+                PRJ_VIRTUALIZATION_INSTANCE_INFO,
             },
         },
     };
 
-    use super::ProjectedFSLibrary;
+    use super::{
+        is_projfs_available,
+        ProjectedFSLibrary,
+    };
+    use crate::Error;
 
     pub struct StaticallyLinkedLibrary;
 
@@ -241,9 +340,94 @@ mod lib_impl {
                 extendedinfo,
             )
         }
+
+        unsafe fn prj_complete_command(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            commandid: i32,
+            completionresult: HRESULT,
+            extendedparameters: Option<*const PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS>,
+        ) -> windows::core::Result<()> {
+            use windows::Win32::Storage::ProjectedFileSystem::PrjCompleteCommand;
+            PrjCompleteCommand(
+                namespacevirtualizationcontext,
+                commandid,
+                completionresult,
+                extendedparameters,
+            )
+        }
+
+        unsafe fn prj_clear_negative_path_cache(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            totalentrynumber: Option<*mut u32>,
+        ) -> windows::core::Result<()> {
+            use windows::Win32::Storage::ProjectedFileSystem::PrjClearNegativePathCache;
+            PrjClearNegativePathCache(namespacevirtualizationcontext, totalentrynumber)
+        }
+
+        unsafe fn prj_get_on_disk_file_state(
+            &self,
+            destinationfilename: PCWSTR,
+        ) -> windows::core::Result<PRJ_FILE_STATE> {
+            use windows::Win32::Storage::ProjectedFileSystem::PrjGetOnDiskFileState;
+            PrjGetOnDiskFileState(destinationfilename)
+        }
+
+        unsafe fn prj_get_virtualization_instance_info(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        ) -> windows::core::Result<PRJ_VIRTUALIZATION_INSTANCE_INFO> {
+            use windows::Win32::Storage::ProjectedFileSystem::PrjGetVirtualizationInstanceInfo;
+            let mut instance_info = PRJ_VIRTUALIZATION_INSTANCE_INFO::default();
+            PrjGetVirtualizationInstanceInfo(namespacevirtualizationcontext, &mut instance_info)?;
+            Ok(instance_info)
+        }
+
+        unsafe fn prj_update_file_if_needed(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            destinationfilename: PCWSTR,
+            placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+            placeholderinfosize: u32,
+            updateflags: PRJ_UPDATE_TYPES,
+            failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+        ) -> windows::core::Result<()> {
+            use windows::Win32::Storage::ProjectedFileSystem::PrjUpdateFileIfNeeded;
+            PrjUpdateFileIfNeeded(
+                namespacevirtualizationcontext,
+                destinationfilename,
+                placeholderinfo,
+                placeholderinfosize,
+                updateflags,
+                failurereason,
+            )
+        }
+
+        unsafe fn prj_delete_file(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            destinationfilename: PCWSTR,
+            updateflags: PRJ_UPDATE_TYPES,
+            failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+        ) -> windows::core::Result<()> {
+            use windows::Win32::Storage::ProjectedFileSystem::PrjDeleteFile;
+            PrjDeleteFile(
+                namespacevirtualizationcontext,
+                destinationfilename,
+                updateflags,
+                failurereason,
+            )
+        }
     }
 
+    /// Load the ProjFS API provided by the system.  
+    /// Returns `Error::WindowsFeatureNotEnabled` if ProjFS is not available (see `is_projfs_available`).
     pub fn load_library() -> crate::Result<Arc<dyn ProjectedFSLibrary>> {
+        if !is_projfs_available() {
+            return Err(Error::WindowsFeatureNotEnabled);
+        }
+
         Ok(Arc::new(StaticallyLinkedLibrary))
     }
 }
@@ -266,13 +450,18 @@ mod lib_impl {
             Foundation::BOOLEAN,
             Storage::ProjectedFileSystem::{
                 PRJ_CALLBACKS,
+                PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS,
                 PRJ_DIR_ENTRY_BUFFER_HANDLE,
                 PRJ_EXTENDED_INFO,
                 PRJ_FILE_BASIC_INFO,
+                PRJ_FILE_STATE,
                 PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
                 PRJ_PLACEHOLDER_INFO,
                 PRJ_PLACEHOLDER_VERSION_INFO,
                 PRJ_STARTVIRTUALIZING_OPTIONS,
+                PRJ_UPDATE_FAILURE_CAUSES,
+                PRJ_UPDATE_TYPES,
+                PRJ_VIRTUALIZATION_INSTANCE_INFO,
             },
         },
     };
@@ -331,6 +520,14 @@ mod lib_impl {
             fn PrjWriteFileData(namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT, datastreamid: *const GUID, buffer : *const ::core::ffi::c_void, byteoffset : u64, length : u32) -> HRESULT,
             fn PrjWritePlaceholderInfo(namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT, destinationfilename: PCWSTR, placeholderinfo: *const PRJ_PLACEHOLDER_INFO, placeholderinfosize : u32) -> HRESULT,
             fn PrjWritePlaceholderInfo2(namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT, destinationfilename: PCWSTR, placeholderinfo: *const PRJ_PLACEHOLDER_INFO, placeholderinfosize : u32, extendedinfo : *const PRJ_EXTENDED_INFO) -> HRESULT,
+
+            fn PrjCompleteCommand(namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT, commandid: i32, completionresult: HRESULT, extendedparameters: *const PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS) -> HRESULT,
+            fn PrjClearNegativePathCache(namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT, totalentrynumber: *mut u32) -> HRESULT,
+            fn PrjGetOnDiskFileState(destinationfilename: PCWSTR, filestate: *mut PRJ_FILE_STATE) -> HRESULT,
+            fn PrjGetVirtualizationInstanceInfo(namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT, virtualizationinstanceinfo: *mut PRJ_VIRTUALIZATION_INSTANCE_INFO) -> HRESULT,
+
+            fn PrjUpdateFileIfNeeded(namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT, destinationfilename: PCWSTR, placeholderinfo: *const PRJ_PLACEHOLDER_INFO, placeholderinfosize: u32, updateflags: PRJ_UPDATE_TYPES, failurereason: *mut PRJ_UPDATE_FAILURE_CAUSES) -> HRESULT,
+            fn PrjDeleteFile(namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT, destinationfilename: PCWSTR, updateflags: PRJ_UPDATE_TYPES, failurereason: *mut PRJ_UPDATE_FAILURE_CAUSES) -> HRESULT,
         }
     }
 
@@ -463,10 +660,111 @@ mod lib_impl {
             )
             .ok()
         }
+
+        unsafe fn prj_complete_command(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            commandid: i32,
+            completionresult: HRESULT,
+            extendedparameters: Option<*const PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS>,
+        ) -> windows::core::Result<()> {
+            (self.PrjCompleteCommand)(
+                namespacevirtualizationcontext,
+                commandid,
+                completionresult,
+                extendedparameters.unwrap_or(ptr::null()),
+            )
+            .ok()
+        }
+
+        unsafe fn prj_clear_negative_path_cache(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            totalentrynumber: Option<*mut u32>,
+        ) -> windows::core::Result<()> {
+            (self.PrjClearNegativePathCache)(
+                namespacevirtualizationcontext,
+                totalentrynumber.unwrap_or(ptr::null_mut()),
+            )
+            .ok()
+        }
+
+        unsafe fn prj_get_on_disk_file_state(
+            &self,
+            destinationfilename: PCWSTR,
+        ) -> windows::core::Result<PRJ_FILE_STATE> {
+            let mut file_state = PRJ_FILE_STATE::default();
+            (self.PrjGetOnDiskFileState)(destinationfilename, &mut file_state).ok()?;
+            Ok(file_state)
+        }
+
+        unsafe fn prj_get_virtualization_instance_info(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        ) -> windows::core::Result<PRJ_VIRTUALIZATION_INSTANCE_INFO> {
+            let mut instance_info = PRJ_VIRTUALIZATION_INSTANCE_INFO::default();
+            (self.PrjGetVirtualizationInstanceInfo)(
+                namespacevirtualizationcontext,
+                &mut instance_info,
+            )
+            .ok()?;
+            Ok(instance_info)
+        }
+
+        unsafe fn prj_update_file_if_needed(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            destinationfilename: PCWSTR,
+            placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+            placeholderinfosize: u32,
+            updateflags: PRJ_UPDATE_TYPES,
+            failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+        ) -> windows::core::Result<()> {
+            (self.PrjUpdateFileIfNeeded)(
+                namespacevirtualizationcontext,
+                destinationfilename,
+                placeholderinfo,
+                placeholderinfosize,
+                updateflags,
+                failurereason.unwrap_or(ptr::null_mut()),
+            )
+            .ok()
+        }
+
+        unsafe fn prj_delete_file(
+            &self,
+            namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            destinationfilename: PCWSTR,
+            updateflags: PRJ_UPDATE_TYPES,
+            failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+        ) -> windows::core::Result<()> {
+            (self.PrjDeleteFile)(
+                namespacevirtualizationcontext,
+                destinationfilename,
+                updateflags,
+                failurereason.unwrap_or(ptr::null_mut()),
+            )
+            .ok()
+        }
+    }
+
+    /// Open `projectedfslib.dll` from the system directory only (like `is_projfs_available`),
+    /// so a DLL planted within the search path can not be loaded instead.
+    fn open_library() -> std::result::Result<libloading::Library, libloading::Error> {
+        let library = unsafe {
+            libloading::os::windows::Library::load_with_flags(
+                "projectedfslib.dll",
+                libloading::os::windows::LOAD_LIBRARY_SEARCH_SYSTEM32,
+            )?
+        };
+
+        Ok(library.into())
     }
 
+    /// Load the ProjFS API provided by the system.  
+    /// Returns `Error::WindowsFeatureNotEnabled` if ProjFS is not available (see `is_projfs_available`).
     pub fn load_library() -> Result<Arc<dyn ProjectedFSLibrary>> {
-        let library = match unsafe { libloading::Library::new("projectedfslib") } {
+        let library = match open_library() {
             Ok(library) => DynamicallyLoadedLibrary::new(library)?,
             Err(error) => {
                 return Err(match &error {
@@ -0,0 +1,90 @@
+use std::{
+    cell::RefCell,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use crate::{
+    DirectoryEntry,
+    RequestContext,
+};
+
+thread_local! {
+    static CACHED_LISTING: RefCell<Option<CachedListing>> = const { RefCell::new(None) };
+}
+
+/// Identifies a source by its address and type.  
+/// The type is required as the address of a wrapper equals the address of its first field.
+type SourceKey = (usize, &'static str);
+
+struct CachedListing {
+    source: SourceKey,
+    command_id: i32,
+    directory: PathBuf,
+    entries: Vec<DirectoryEntry>,
+}
+
+fn source_key<S: ?Sized>(source: &S) -> SourceKey {
+    (
+        source as *const S as *const () as usize,
+        std::any::type_name::<S>(),
+    )
+}
+
+/// Find the entry `name` within the listing of `directory` returned by `list`.  
+/// While serving a ProjFS request the last listing is cached, therefore
+/// subsequent lookups within the same directory do not list it again.
+pub(crate) fn find_entry<S: ?Sized>(
+    source: &S,
+    directory: &Path,
+    name: &str,
+    list: impl FnOnce() -> Vec<DirectoryEntry>,
+) -> Option<DirectoryEntry> {
+    let command_id = match RequestContext::current() {
+        Some(context) => context.command_id,
+        None => return list().into_iter().find(|entry| entry.name() == name),
+    };
+
+    let source = source_key(source);
+    let cached = CACHED_LISTING.with(|cache| {
+        cache.borrow().as_ref().and_then(|cached| {
+            if cached.source == source
+                && cached.command_id == command_id
+                && cached.directory == directory
+            {
+                Some(
+                    cached
+                        .entries
+                        .iter()
+                        .find(|entry| entry.name() == name)
+                        .cloned(),
+                )
+            } else {
+                None
+            }
+        })
+    });
+    if let Some(entry) = cached {
+        return entry;
+    }
+
+    /* the cache must not be borrowed while listing as the source may perform lookups as well */
+    let entries = list();
+    let entry = entries.iter().find(|entry| entry.name() == name).cloned();
+    CACHED_LISTING.with(|cache| {
+        *cache.borrow_mut() = Some(CachedListing {
+            source,
+            command_id,
+            directory: directory.to_path_buf(),
+            entries,
+        })
+    });
+    entry
+}
+
+/// Drop the cached listing of the current thread.
+pub(crate) fn clear() {
+    CACHED_LISTING.with(|cache| cache.borrow_mut().take());
+}
@@ -0,0 +1,297 @@
+use std::{
+    borrow::Cow,
+    io::{
+        self,
+        Read,
+    },
+    ops::{
+        ControlFlow,
+        Range,
+    },
+    path::Path,
+};
+
+use crate::{
+    CancellationToken,
+    DirectoryEntry,
+    DirectoryProducer,
+    FileChunks,
+    Notification,
+    NotificationVeto,
+    ProjectedFileSystemSource,
+    RawCallbackInfo,
+};
+
+/// Source logging every call to `source` and its outcome with `log::trace!`
+/// (e.g. for diagnosing why an entry does not show up).
+///
+/// Note:
+/// Listings are logged by their number of entries to keep the output readable
+/// for large directories. File contents are never logged.
+pub struct Logged<S>(pub S);
+
+impl<S: ProjectedFileSystemSource> Logged<S> {
+    pub fn new(source: S) -> Self {
+        Self(source)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+fn describe_stream(result: &io::Result<Box<dyn Read>>) -> String {
+    match result {
+        Ok(_) => "Ok".to_string(),
+        Err(error) => format!("Err({})", error),
+    }
+}
+
+impl<S: ProjectedFileSystemSource> ProjectedFileSystemSource for Logged<S> {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        log::trace!("list_directory({})", path.display());
+        let entries = self.0.list_directory(path);
+        log::trace!(
+            "list_directory({}) -> {} entries",
+            path.display(),
+            entries.len()
+        );
+        entries
+    }
+
+    fn list_directory_chunk(
+        &self,
+        path: &Path,
+        offset: usize,
+        count: usize,
+    ) -> Option<io::Result<Vec<DirectoryEntry>>> {
+        log::trace!(
+            "list_directory_chunk({}, offset: {}, count: {})",
+            path.display(),
+            offset,
+            count
+        );
+        let result = self.0.list_directory_chunk(path, offset, count);
+        match &result {
+            None => log::trace!("list_directory_chunk({}) -> None", path.display()),
+            Some(Ok(entries)) => log::trace!(
+                "list_directory_chunk({}) -> {} entries",
+                path.display(),
+                entries.len()
+            ),
+            Some(Err(error)) => {
+                log::trace!("list_directory_chunk({}) -> Err({})", path.display(), error)
+            }
+        }
+        result
+    }
+
+    fn directory_producer(&self, path: &Path) -> Option<io::Result<Box<dyn DirectoryProducer>>> {
+        log::trace!("directory_producer({})", path.display());
+        let result = self.0.directory_producer(path);
+        match &result {
+            None => log::trace!("directory_producer({}) -> None", path.display()),
+            Some(Ok(_)) => log::trace!("directory_producer({}) -> Ok", path.display()),
+            Some(Err(error)) => {
+                log::trace!("directory_producer({}) -> Err({})", path.display(), error)
+            }
+        }
+        result
+    }
+
+    fn get_directory_entry(&self, path: &Path) -> Option<DirectoryEntry> {
+        log::trace!("get_directory_entry({})", path.display());
+        let entry = self.0.get_directory_entry(path);
+        log::trace!("get_directory_entry({}) -> {:?}", path.display(), entry);
+        entry
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let exists = self.0.exists(path);
+        log::trace!("exists({}) -> {}", path.display(), exists);
+        exists
+    }
+
+    fn content_id(&self, path: &Path) -> Option<[u8; 16]> {
+        let content_id = self.0.content_id(path);
+        log::trace!("content_id({}) -> {:?}", path.display(), content_id);
+        content_id
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        log::trace!(
+            "stream_file_content({}, offset: {}, length: {})",
+            path.display(),
+            byte_offset,
+            length
+        );
+        let result = self.0.stream_file_content(path, byte_offset, length);
+        log::trace!(
+            "stream_file_content({}) -> {}",
+            path.display(),
+            describe_stream(&result)
+        );
+        result
+    }
+
+    fn stream_file_content_cancelable(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+        cancellation: &CancellationToken,
+    ) -> io::Result<Box<dyn Read>> {
+        log::trace!(
+            "stream_file_content_cancelable({}, offset: {}, length: {})",
+            path.display(),
+            byte_offset,
+            length
+        );
+        let result = self
+            .0
+            .stream_file_content_cancelable(path, byte_offset, length, cancellation);
+        log::trace!(
+            "stream_file_content_cancelable({}) -> {}",
+            path.display(),
+            describe_stream(&result)
+        );
+        result
+    }
+
+    fn stream_file_content_mut(
+        &mut self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+        cancellation: &CancellationToken,
+    ) -> io::Result<Box<dyn Read>> {
+        log::trace!(
+            "stream_file_content_mut({}, offset: {}, length: {})",
+            path.display(),
+            byte_offset,
+            length
+        );
+        let result = self
+            .0
+            .stream_file_content_mut(path, byte_offset, length, cancellation);
+        log::trace!(
+            "stream_file_content_mut({}) -> {}",
+            path.display(),
+            describe_stream(&result)
+        );
+        result
+    }
+
+    fn file_content_slice(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> Option<io::Result<Cow<'_, [u8]>>> {
+        log::trace!(
+            "file_content_slice({}, offset: {}, length: {})",
+            path.display(),
+            byte_offset,
+            length
+        );
+        let result = self.0.file_content_slice(path, byte_offset, length);
+        match &result {
+            None => log::trace!("file_content_slice({}) -> None", path.display()),
+            Some(Ok(slice)) => log::trace!(
+                "file_content_slice({}) -> {} bytes",
+                path.display(),
+                slice.len()
+            ),
+            Some(Err(error)) => {
+                log::trace!("file_content_slice({}) -> Err({})", path.display(), error)
+            }
+        }
+        result
+    }
+
+    fn stream_file_chunks(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> Option<io::Result<FileChunks>> {
+        log::trace!(
+            "stream_file_chunks({}, offset: {}, length: {})",
+            path.display(),
+            byte_offset,
+            length
+        );
+        let result = self.0.stream_file_chunks(path, byte_offset, length);
+        match &result {
+            None => log::trace!("stream_file_chunks({}) -> None", path.display()),
+            Some(Ok(_)) => log::trace!("stream_file_chunks({}) -> Ok", path.display()),
+            Some(Err(error)) => {
+                log::trace!("stream_file_chunks({}) -> Err({})", path.display(), error)
+            }
+        }
+        result
+    }
+
+    fn zero_ranges(&self, path: &Path) -> Vec<Range<u64>> {
+        let ranges = self.0.zero_ranges(path);
+        log::trace!("zero_ranges({}) -> {:?}", path.display(), ranges);
+        ranges
+    }
+
+    fn stream_named_content(
+        &self,
+        path: &Path,
+        stream_name: &str,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        log::trace!(
+            "stream_named_content({}:{}, offset: {}, length: {})",
+            path.display(),
+            stream_name,
+            byte_offset,
+            length
+        );
+        let result = self
+            .0
+            .stream_named_content(path, stream_name, byte_offset, length);
+        log::trace!(
+            "stream_named_content({}:{}) -> {}",
+            path.display(),
+            stream_name,
+            describe_stream(&result)
+        );
+        result
+    }
+
+    fn handle_notification(&self, notification: &Notification) -> ControlFlow<NotificationVeto> {
+        log::trace!("handle_notification({:?})", notification);
+        let action = self.0.handle_notification(notification);
+        log::trace!("handle_notification({:?}) -> {:?}", notification, action);
+        action
+    }
+
+    fn handle_notification_mut(
+        &mut self,
+        notification: &Notification,
+    ) -> ControlFlow<NotificationVeto> {
+        log::trace!("handle_notification({:?})", notification);
+        let action = self.0.handle_notification_mut(notification);
+        log::trace!("handle_notification({:?}) -> {:?}", notification, action);
+        action
+    }
+
+    fn on_stop(&self) {
+        log::trace!("on_stop()");
+        self.0.on_stop();
+    }
+
+    fn on_raw_callback(&self, info: &RawCallbackInfo) {
+        self.0.on_raw_callback(info);
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::SystemTime;
+
+use crate::{
+    DirectoryEntry,
+    FileAttributes,
+};
+
+/// Metadata of a `DirectoryEntry` resembling `std::fs::Metadata`.  
+/// Allows treating projected entries and files on disk uniformly without accessing the disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataView {
+    is_dir: bool,
+    len: u64,
+    file_attributes: u32,
+
+    created: Option<SystemTime>,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+}
+
+impl MetadataView {
+    /// Returns the size of the file in bytes.  
+    /// Returns zero for directories and files with `FileSize::Unknown`.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    /// Returns the raw file attributes like `MetadataExt::file_attributes`.  
+    /// `FILE_ATTRIBUTE_DIRECTORY` is always set for directories.
+    pub fn file_attributes(&self) -> u32 {
+        self.file_attributes
+    }
+
+    /// Returns the creation time or `None` if not set.
+    pub fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+
+    /// Returns the last access time or `None` if not set.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        self.accessed
+    }
+
+    /// Returns the last write time or `None` if not set.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+
+impl DirectoryEntry {
+    /// Create a metadata view of this entry (the inverse of `DirectoryEntry::try_from(DirEntry)`).
+    pub fn to_metadata_view(&self) -> MetadataView {
+        match self {
+            Self::Directory(dir) => MetadataView {
+                is_dir: true,
+                len: 0,
+                file_attributes: (dir.attributes() | FileAttributes::DIRECTORY).bits(),
+
+                created: dir.created(),
+                accessed: dir.accessed(),
+                modified: dir.modified(),
+            },
+            Self::File(file) => MetadataView {
+                is_dir: false,
+                len: file.file_size.known().unwrap_or(0),
+                file_attributes: file.attributes().bits(),
+
+                created: file.created(),
+                accessed: file.accessed(),
+                modified: file.modified(),
+            },
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    io::{
+        self,
+        Read,
+    },
+    ops::{
+        ControlFlow,
+        Range,
+    },
+    path::Path,
+};
+
+use crate::{
+    collation::upcase_name,
+    CancellationToken,
+    DirectoryEntry,
+    FileChunks,
+    Notification,
+    NotificationVeto,
+    ProjectedFileSystemSource,
+    RawCallbackInfo,
+};
+
+/// Source projecting the entries of `upper` on top of the entries of `lower` (like a union mount).
+///
+/// Entries of `upper` take precedence over entries of `lower` with the same name
+/// regardless of their type. A directory of `upper` therefore hides a file with the same name
+/// in `lower` and vice versa. Names are compared case-insensitive like ProjFS does (`PrjFileNameCompare`).
+/// File contents are served by the source owning the file.
+///
+/// Note:
+/// The owner of a file is determined via `exists` of `upper` on every call serving its contents,
+/// which happens several times per read (e.g. `zero_ranges`, `file_content_slice` and
+/// `stream_file_content_mut`). The default `exists` looks the path up within the listing of its
+/// parent, which is listed once per request (see `ProjectedFileSystemSource::entry_at`).
+/// Override `exists` of `upper` if this lookup is expensive (e.g. for large directories).  
+/// Notifications are passed to `upper` first. `lower` only receives the notification
+/// if `upper` did not cancel it.
+pub struct Overlay<U, L> {
+    pub upper: U,
+    pub lower: L,
+}
+
+impl<U, L> Overlay<U, L>
+where
+    U: ProjectedFileSystemSource,
+    L: ProjectedFileSystemSource,
+{
+    pub fn new(upper: U, lower: L) -> Self {
+        Self { upper, lower }
+    }
+
+    /// Returns `true` if `path` is served by `upper`.
+    fn is_upper(&self, path: &Path) -> bool {
+        self.upper.exists(path)
+    }
+}
+
+impl<U, L> ProjectedFileSystemSource for Overlay<U, L>
+where
+    U: ProjectedFileSystemSource,
+    L: ProjectedFileSystemSource,
+{
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        let mut entries = self.upper.list_directory(path);
+        let upper_names = entries
+            .iter()
+            .map(|entry| upcase_name(entry.name()))
+            .collect::<HashSet<_>>();
+
+        entries.extend(
+            self.lower
+                .list_directory(path)
+                .into_iter()
+                .filter(|entry| !upper_names.contains(&upcase_name(entry.name()))),
+        );
+        entries
+    }
+
+    fn get_directory_entry(&self, path: &Path) -> Option<DirectoryEntry> {
+        self.upper
+            .get_directory_entry(path)
+            .or_else(|| self.lower.get_directory_entry(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.upper.exists(path) || self.lower.exists(path)
+    }
+
+    fn content_id(&self, path: &Path) -> Option<[u8; 16]> {
+        if self.is_upper(path) {
+            self.upper.content_id(path)
+        } else {
+            self.lower.content_id(path)
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        if self.is_upper(path) {
+            self.upper.stream_file_content(path, byte_offset, length)
+        } else {
+            self.lower.stream_file_content(path, byte_offset, length)
+        }
+    }
+
+    fn stream_file_content_cancelable(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+        cancellation: &CancellationToken,
+    ) -> io::Result<Box<dyn Read>> {
+        if self.is_upper(path) {
+            self.upper
+                .stream_file_content_cancelable(path, byte_offset, length, cancellation)
+        } else {
+            self.lower
+                .stream_file_content_cancelable(path, byte_offset, length, cancellation)
+        }
+    }
+
+    fn stream_file_content_mut(
+        &mut self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+        cancellation: &CancellationToken,
+    ) -> io::Result<Box<dyn Read>> {
+        if self.is_upper(path) {
+            self.upper
+                .stream_file_content_mut(path, byte_offset, length, cancellation)
+        } else {
+            self.lower
+                .stream_file_content_mut(path, byte_offset, length, cancellation)
+        }
+    }
+
+    fn file_content_slice(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> Option<io::Result<Cow<'_, [u8]>>> {
+        if self.is_upper(path) {
+            self.upper.file_content_slice(path, byte_offset, length)
+        } else {
+            self.lower.file_content_slice(path, byte_offset, length)
+        }
+    }
+
+    fn stream_file_chunks(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> Option<io::Result<FileChunks>> {
+        if self.is_upper(path) {
+            self.upper.stream_file_chunks(path, byte_offset, length)
+        } else {
+            self.lower.stream_file_chunks(path, byte_offset, length)
+        }
+    }
+
+    fn zero_ranges(&self, path: &Path) -> Vec<Range<u64>> {
+        if self.is_upper(path) {
+            self.upper.zero_ranges(path)
+        } else {
+            self.lower.zero_ranges(path)
+        }
+    }
+
+    fn stream_named_content(
+        &self,
+        path: &Path,
+        stream_name: &str,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        if self.is_upper(path) {
+            self.upper
+                .stream_named_content(path, stream_name, byte_offset, length)
+        } else {
+            self.lower
+                .stream_named_content(path, stream_name, byte_offset, length)
+        }
+    }
+
+    fn handle_notification(&self, notification: &Notification) -> ControlFlow<NotificationVeto> {
+        self.upper.handle_notification(notification)?;
+        self.lower.handle_notification(notification)
+    }
+
+    fn handle_notification_mut(
+        &mut self,
+        notification: &Notification,
+    ) -> ControlFlow<NotificationVeto> {
+        self.upper.handle_notification_mut(notification)?;
+        self.lower.handle_notification_mut(notification)
+    }
+
+    fn on_stop(&self) {
+        self.upper.on_stop();
+        self.lower.on_stop();
+    }
+
+    fn on_raw_callback(&self, info: &RawCallbackInfo) {
+        self.upper.on_raw_callback(info);
+        self.lower.on_raw_callback(info);
+    }
+}
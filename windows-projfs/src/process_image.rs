@@ -0,0 +1,103 @@
+use std::{
+    ffi::{
+        OsStr,
+        OsString,
+    },
+    os::windows::ffi::{
+        OsStrExt,
+        OsStringExt,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::MAX_PATH,
+        Storage::FileSystem::{
+            GetLogicalDrives,
+            QueryDosDeviceW,
+        },
+    },
+};
+
+use crate::utils::encode_wide_null;
+
+/// Image file of the process which triggered a callback.
+///
+/// ProjFS reports the image as NT device path
+/// (e.g. `\Device\HarddiskVolume3\Windows\explorer.exe`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessImage {
+    nt_path: PathBuf,
+}
+
+impl ProcessImage {
+    pub fn new(nt_path: impl Into<PathBuf>) -> Self {
+        Self {
+            nt_path: nt_path.into(),
+        }
+    }
+
+    /// The image path as reported by ProjFS.
+    pub fn nt_path(&self) -> &Path {
+        &self.nt_path
+    }
+
+    /// File name of the executable (e.g. `explorer.exe`).
+    pub fn executable_name(&self) -> Option<&OsStr> {
+        self.nt_path.file_name()
+    }
+
+    /// Best-effort conversion of the image path into a Win32 path
+    /// (e.g. `C:\Windows\explorer.exe`).
+    /// Returns `None` if the device is not mapped to any drive letter.
+    ///
+    /// Note:
+    /// The path is resolved on every call by querying the devices of all drive letters.
+    pub fn win32_path(&self) -> Option<PathBuf> {
+        let path = self.nt_path.as_os_str().encode_wide().collect::<Vec<_>>();
+        let path = path
+            .strip_prefix(&encode_wide_null("\\??\\")[..4])
+            .unwrap_or(&path);
+
+        if path.get(1) == Some(&(b':' as u16)) {
+            /* already a Win32 path */
+            return Some(PathBuf::from(OsString::from_wide(path)));
+        }
+
+        let drives = unsafe { GetLogicalDrives() };
+        for index in 0..26u8 {
+            if drives & (1 << index) == 0 {
+                continue;
+            }
+
+            let drive = encode_wide_null(format!("{}:", (b'A' + index) as char));
+            let mut device = [0u16; MAX_PATH as usize];
+            let length = unsafe { QueryDosDeviceW(PCWSTR(drive.as_ptr()), Some(&mut device)) };
+            if length == 0 {
+                continue;
+            }
+
+            /* the result is a list of zero terminated strings where the first one is the active mapping */
+            let device = device.split(|char| *char == 0).next().unwrap_or_default();
+            let remainder = match path.strip_prefix(device) {
+                Some(remainder)
+                    if !device.is_empty() && remainder.first() == Some(&(b'\\' as u16)) =>
+                {
+                    remainder
+                }
+                _ => continue,
+            };
+
+            let mut result = drive[..2].to_vec();
+            result.extend_from_slice(remainder);
+            return Some(PathBuf::from(OsString::from_wide(&result)));
+        }
+
+        None
+    }
+}
@@ -0,0 +1,51 @@
+/// The ProjFS callback which is being served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallbackKind {
+    StartDirectoryEnumeration,
+    GetDirectoryEnumeration,
+    EndDirectoryEnumeration,
+    GetPlaceholderInfo,
+    GetFileData,
+    Notification,
+}
+
+/// Unprocessed information ProjFS passed to a callback.
+///
+/// Intended for advanced use cases which need access to details
+/// not exposed otherwise. Most sources do not need this.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawCallbackInfo {
+    pub kind: CallbackKind,
+
+    /// Raw `PRJ_CALLBACK_DATA_FLAGS` of the callback.
+    ///
+    /// Flags are only set for `CallbackKind::GetDirectoryEnumeration`:
+    /// - `FLAG_ENUM_RESTART_SCAN`: The enumeration should be restarted from the beginning
+    ///   (e.g. because the search expression changed).
+    /// - `FLAG_ENUM_RETURN_SINGLE_ENTRY`: Only a single entry should be returned.
+    pub flags: u32,
+
+    /// Id of the ProjFS command. Unique as long as the command is active.
+    pub command_id: i32,
+
+    /// Id of the file or directory the callback is for.
+    /// Stays the same for the lifetime of the file.
+    pub file_id: u128,
+
+    /// Id of the data stream for `CallbackKind::GetFileData`.
+    /// Differs for every opened handle of the file.
+    pub data_stream_id: u128,
+
+    /// Id of the process which triggered the callback
+    pub triggering_process_id: u32,
+}
+
+impl RawCallbackInfo {
+    pub const FLAG_ENUM_RESTART_SCAN: u32 = 0x01;
+    pub const FLAG_ENUM_RETURN_SINGLE_ENTRY: u32 = 0x02;
+
+    /// Returns `true` if all bits of `flag` are set.
+    pub fn has_flag(&self, flag: u32) -> bool {
+        self.flags & flag == flag
+    }
+}
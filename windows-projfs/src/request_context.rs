@@ -0,0 +1,52 @@
+use std::cell::RefCell;
+
+use crate::{
+    listing_cache,
+    ProcessImage,
+};
+
+thread_local! {
+    static CURRENT_REQUEST: RefCell<Option<RequestContext>> = const { RefCell::new(None) };
+}
+
+/// Information about the ProjFS request which is currently being served.
+///
+/// The context is available to all source methods via `RequestContext::current`
+/// and allows correlating log lines or asynchronous completions with the request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    /// Id of the ProjFS command. Unique as long as the command is active.
+    pub command_id: i32,
+
+    /// Id of the process which triggered the request
+    pub triggering_process_id: u32,
+
+    /// Image of the process which triggered the request if known
+    pub triggering_process_image: Option<ProcessImage>,
+}
+
+impl RequestContext {
+    /// Returns the context of the request which is currently being served on this thread.  
+    /// Returns `None` if the current thread is not serving a ProjFS request.
+    pub fn current() -> Option<RequestContext> {
+        CURRENT_REQUEST.with(|current| current.borrow().clone())
+    }
+
+    /// Make this context the current context until the returned guard gets dropped.
+    pub(crate) fn enter(self) -> RequestContextGuard {
+        let previous = CURRENT_REQUEST.with(|current| current.replace(Some(self)));
+        RequestContextGuard { previous }
+    }
+}
+
+pub(crate) struct RequestContextGuard {
+    previous: Option<RequestContext>,
+}
+
+impl Drop for RequestContextGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_REQUEST.with(|current| *current.borrow_mut() = previous);
+        listing_cache::clear();
+    }
+}
@@ -1,20 +1,50 @@
 use std::{
     self,
+    borrow::Cow,
     ffi::OsStr,
-    fs::DirEntry,
+    fmt,
+    fs::{
+        self,
+        DirEntry,
+        Metadata,
+    },
     io::{
         self,
         Read,
     },
-    ops::ControlFlow,
+    ops::{
+        ControlFlow,
+        Range,
+    },
+    os::windows::fs::MetadataExt,
     path::{
+        Component,
         Path,
         PathBuf,
     },
+    time::SystemTime,
+};
+
+use windows::{
+    core::HRESULT,
+    Win32::Foundation::STATUS_CANNOT_DELETE,
+};
+
+use crate::{
+    listing_cache,
+    utils::filetime_to_system_time,
+    CancellationToken,
+    FileAttributes,
+    RawCallbackInfo,
 };
 
 /// A `DirectoryEntry` represents all possible entry types
 /// which can be contained within the file system.
+///
+/// Note:
+/// ProjFS stores its own reparse tag (`IO_REPARSE_TAG_PROJFS`) on every placeholder,
+/// therefore entries can not carry custom reparse tags or payloads.
+/// Use the Cloud Files API for cloud sync indicators instead.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DirectoryEntry {
     /// The entry is a directory
@@ -25,12 +55,139 @@ pub enum DirectoryEntry {
 }
 
 impl DirectoryEntry {
+    /// Create a new file entry with the given name and size.
+    pub fn file(name: impl Into<String>, size: impl Into<FileSize>) -> Self {
+        Self::File(FileInfo {
+            file_name: name.into(),
+            file_size: size.into(),
+
+            ..Default::default()
+        })
+    }
+
+    /// Create a new directory entry with the given name.
+    pub fn directory(name: impl Into<String>) -> Self {
+        Self::Directory(DirectoryInfo {
+            directory_name: name.into(),
+
+            ..Default::default()
+        })
+    }
+
+    /// Set the file or directory attributes of this entry.
+    pub fn with_attributes(mut self, attributes: u32) -> Self {
+        match &mut self {
+            Self::Directory(dir) => dir.directory_attributes = attributes,
+            Self::File(file) => file.file_attributes = attributes,
+        }
+        self
+    }
+
+    /// Set the creation, last access and last write time of this entry.
+    pub fn with_times(
+        mut self,
+        creation_time: u64,
+        last_access_time: u64,
+        last_write_time: u64,
+    ) -> Self {
+        match &mut self {
+            Self::Directory(dir) => {
+                dir.creation_time = creation_time;
+                dir.last_access_time = last_access_time;
+                dir.last_write_time = last_write_time;
+            }
+            Self::File(file) => {
+                file.creation_time = creation_time;
+                file.last_access_time = last_access_time;
+                file.last_write_time = last_write_time;
+            }
+        }
+        self
+    }
+
+    /// Set the secondary sort key of this entry (see `FileInfo::sort_key`).
+    pub fn with_sort_key(mut self, sort_key: u64) -> Self {
+        match &mut self {
+            Self::Directory(dir) => dir.sort_key = Some(sort_key),
+            Self::File(file) => file.sort_key = Some(sort_key),
+        }
+        self
+    }
+
+    /// Read all entries of a directory on disk.  
+    /// Entries which are neither files nor directories (e.g. symbolic links)
+    /// are skipped if `skip_unsupported` is set, else an error will be returned.
+    pub fn from_read_dir(path: impl AsRef<Path>, skip_unsupported: bool) -> io::Result<Vec<Self>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if !file_type.is_dir() && !file_type.is_file() {
+                if skip_unsupported {
+                    log::trace!("Skipping unsupported entry {}", entry.path().display());
+                    continue;
+                }
+
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("file type of {} is not supported", entry.path().display()),
+                ));
+            }
+
+            entries.push(entry.try_into()?);
+        }
+
+        Ok(entries)
+    }
+
     pub fn name(&self) -> &str {
         match self {
             Self::Directory(dir) => &dir.directory_name,
             Self::File(file) => &file.file_name,
         }
     }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Self::Directory(_))
+    }
+
+    pub fn is_file(&self) -> bool {
+        matches!(self, Self::File(_))
+    }
+
+    /// Returns the file size in bytes.  
+    /// Returns `None` for directories and files with `FileSize::Unknown`.
+    pub fn size(&self) -> Option<u64> {
+        match self {
+            Self::Directory(_) => None,
+            Self::File(file) => file.file_size.known(),
+        }
+    }
+
+    pub fn attributes(&self) -> FileAttributes {
+        match self {
+            Self::Directory(dir) => dir.attributes(),
+            Self::File(file) => file.attributes(),
+        }
+    }
+
+    pub fn sort_key(&self) -> Option<u64> {
+        match self {
+            Self::Directory(dir) => dir.sort_key,
+            Self::File(file) => file.sort_key,
+        }
+    }
+
+    /// Compare two entries ignoring their creation, last access and last write time
+    /// (see `FileInfo::matches_ignoring_times`).  
+    /// A file never matches a directory.
+    pub fn matches_ignoring_times(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Directory(dir), Self::Directory(other)) => dir.matches_ignoring_times(other),
+            (Self::File(file), Self::File(other)) => file.matches_ignoring_times(other),
+            _ => false,
+        }
+    }
 }
 
 impl From<FileInfo> for DirectoryEntry {
@@ -49,52 +206,284 @@ impl TryFrom<DirEntry> for DirectoryEntry {
     type Error = std::io::Error;
 
     fn try_from(value: DirEntry) -> Result<Self, Self::Error> {
-        use std::os::windows::fs::MetadataExt;
-
         let file_name = value.file_name().to_string_lossy().to_string();
         let file_type = value.file_type()?;
         let metadata = value.metadata()?;
         if file_type.is_dir() {
-            Ok(DirectoryInfo {
-                directory_name: file_name,
-                directory_attributes: metadata.file_attributes(),
-
-                creation_time: metadata.creation_time(),
-                last_access_time: metadata.last_access_time(),
-                last_write_time: metadata.last_write_time(),
-            }
-            .into())
+            Ok(DirectoryInfo::from_metadata(file_name, &metadata).into())
         } else if file_type.is_file() {
-            Ok(FileInfo {
-                file_name,
-                file_size: metadata.len(),
-                file_attributes: metadata.file_attributes(),
-
-                creation_time: metadata.creation_time(),
-                last_access_time: metadata.last_access_time(),
-                last_write_time: metadata.last_write_time(),
-            }
-            .into())
+            Ok(FileInfo::from_metadata(file_name, &metadata).into())
         } else {
             Err(io::Error::other("file type is not supported"))
         }
     }
 }
 
+/// Error signaling that the requested byte range of a file is currently not available
+/// (e.g. sparse or partially downloaded content). Use `io::Error::from` to return it
+/// from `ProjectedFileSystemSource::stream_file_content` or the returned reader.
+///
+/// Note:
+/// ProjFS fails the read which triggered the request with `ERROR_FILE_OFFLINE`
+/// instead of the error of other failed reads. The file stays a placeholder, therefore
+/// the next read of the range requests the content from the source again.  
+/// ProjFS may request a larger range than the application is reading, therefore
+/// reads of the available part of the file can fail as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeNotAvailable {
+    pub byte_offset: usize,
+    pub length: usize,
+}
+
+impl fmt::Display for RangeNotAvailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "byte range {}..{} is not available",
+            self.byte_offset,
+            self.byte_offset.saturating_add(self.length)
+        )
+    }
+}
+
+impl std::error::Error for RangeNotAvailable {}
+
+impl From<RangeNotAvailable> for io::Error {
+    fn from(value: RangeNotAvailable) -> Self {
+        io::Error::other(value)
+    }
+}
+
+/// Errors a source can return when the requested path is no file (any more),
+/// e.g. because the source changed while ProjFS requested the content.
+/// Use `io::Error::from` to return it from `ProjectedFileSystemSource::stream_file_content`.
+///
+/// Note:
+/// Errors without an OS error code are mapped by their `io::ErrorKind`,
+/// therefore `io::ErrorKind::NotFound` and `io::ErrorKind::IsADirectory` behave the same.
+/// Failures of all other kinds are reported to ProjFS as `ERROR_IO_INCOMPLETE`.  
+/// Both errors are not reported to `ProjectedFileSystemBuilder::on_callback_error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceError {
+    /// The path does not exist.  
+    /// ProjFS fails the request with `ERROR_FILE_NOT_FOUND`.
+    NotFound,
+
+    /// The path is a directory.  
+    /// ProjFS fails the request with `ERROR_DIRECTORY_NOT_SUPPORTED`.
+    IsADirectory,
+}
+
+impl SourceError {
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Self::NotFound => io::ErrorKind::NotFound,
+            Self::IsADirectory => io::ErrorKind::IsADirectory,
+        }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "entry does not exist"),
+            Self::IsADirectory => write!(f, "entry is a directory"),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl From<SourceError> for io::Error {
+    fn from(value: SourceError) -> Self {
+        io::Error::new(value.kind(), value)
+    }
+}
+
+/// The size of a projected file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FileSize {
+    /// The exact size of the file in bytes.
+    Known(u64),
+
+    /// The size of the file can not be determined without reading it.
+    ///
+    /// ProjFS requires an accurate file size. Therefore the whole file content will be
+    /// buffered in memory once (by calling `stream_file_content` with a `length` of `usize::MAX`)
+    /// when its placeholder gets created. The file will be fully hydrated by the first read,
+    /// which is served from the buffered content, and the buffer is released afterwards.
+    ///
+    /// Note:
+    /// Directory listings report a size of zero as the content is not buffered for enumerations.  
+    /// Every file whose placeholder has been created but which has not been read yet is kept in memory.
+    /// The buffered contents are bounded to 64 MiB, the oldest contents are released and streamed
+    /// again from the start on their first read once the bound has been exceeded.
+    /// Only use this for small or few files.
+    Unknown,
+}
+
+impl FileSize {
+    /// Returns the size in bytes if known.
+    pub fn known(&self) -> Option<u64> {
+        match self {
+            Self::Known(size) => Some(*size),
+            Self::Unknown => None,
+        }
+    }
+}
+
+impl Default for FileSize {
+    fn default() -> Self {
+        Self::Known(0)
+    }
+}
+
+impl From<u64> for FileSize {
+    fn from(value: u64) -> Self {
+        Self::Known(value)
+    }
+}
+
 /// Supported attributes for files.
 ///
 /// Note:
 /// The file size should be matching else the client might expect more
 /// or less content when trying to receive the file.
+/// Reads of a file whose content stream ends before the reported size fail with `ERROR_HANDLE_EOF`.
+/// Use `FileSize::Unknown` if the size can not be determined ahead of time.
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FileInfo {
     pub file_name: String,
-    pub file_size: u64,
+    pub file_size: FileSize,
     pub file_attributes: u32,
 
     pub creation_time: u64,
     pub last_access_time: u64,
     pub last_write_time: u64,
+
+    /// Alternate data streams of the file.  
+    /// The content of these streams will be requested via `ProjectedFileSystemSource::stream_named_content`.
+    pub named_streams: Vec<NamedStream>,
+
+    /// How the content of the file should be hydrated.
+    pub hydration: Hydration,
+
+    /// Secondary sort key for the provider's own use (e.g. within wrapping sources).  
+    /// Ignored by ProjFS, which always receives the entries in its own collation order
+    /// unless configured otherwise by the `SortStrategy`.
+    pub sort_key: Option<u64>,
+}
+
+impl FileInfo {
+    /// Create the file info from already queried metadata
+    /// (e.g. from `fs::symlink_metadata`) without accessing the file again.
+    pub fn from_metadata(file_name: impl Into<String>, metadata: &Metadata) -> Self {
+        Self {
+            file_name: file_name.into(),
+            file_size: metadata.len().into(),
+            file_attributes: metadata.file_attributes(),
+
+            creation_time: metadata.creation_time(),
+            last_access_time: metadata.last_access_time(),
+            last_write_time: metadata.last_write_time(),
+
+            ..Default::default()
+        }
+    }
+
+    /// Returns the creation time or `None` if not set.
+    pub fn created(&self) -> Option<SystemTime> {
+        filetime_to_system_time(self.creation_time)
+    }
+
+    /// Returns the last access time or `None` if not set.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        filetime_to_system_time(self.last_access_time)
+    }
+
+    /// Returns the last write time or `None` if not set.
+    pub fn modified(&self) -> Option<SystemTime> {
+        filetime_to_system_time(self.last_write_time)
+    }
+
+    pub fn attributes(&self) -> FileAttributes {
+        FileAttributes(self.file_attributes)
+    }
+
+    /// Set or clear `FILE_ATTRIBUTE_HIDDEN`.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        set_attribute(&mut self.file_attributes, FileAttributes::HIDDEN, hidden);
+        self
+    }
+
+    /// Set or clear `FILE_ATTRIBUTE_READONLY`.
+    pub fn readonly(mut self, readonly: bool) -> Self {
+        set_attribute(
+            &mut self.file_attributes,
+            FileAttributes::READONLY,
+            readonly,
+        );
+        self
+    }
+
+    /// Set or clear `FILE_ATTRIBUTE_SYSTEM`.
+    pub fn system(mut self, system: bool) -> Self {
+        set_attribute(&mut self.file_attributes, FileAttributes::SYSTEM, system);
+        self
+    }
+
+    /// Set how the content of the file should be hydrated.
+    pub fn hydration(mut self, hydration: Hydration) -> Self {
+        self.hydration = hydration;
+        self
+    }
+
+    /// Compare all fields except the creation, last access and last write time
+    /// (e.g. for sources which do not report times).  
+    /// Use `==` to compare the timestamps as well.
+    pub fn matches_ignoring_times(&self, other: &Self) -> bool {
+        self.file_name == other.file_name
+            && self.file_size == other.file_size
+            && self.file_attributes == other.file_attributes
+            && self.named_streams == other.named_streams
+            && self.hydration == other.hydration
+            && self.sort_key == other.sort_key
+    }
+}
+
+/// Hydration strategy of a file.
+///
+/// Note:
+/// ProjFS has no per file hydration policy and always requests the ranges
+/// an application reads. `Hydration::Full` is implemented by the projection
+/// writing the whole file on the first read, which ProjFS accepts as data beyond the
+/// requested range. Nothing can prevent ProjFS from hydrating `Hydration::OnDemand` files
+/// if an application reads them completely.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Hydration {
+    /// Only the ranges read by applications are requested (default).
+    #[default]
+    OnDemand,
+
+    /// The whole file content is written on the first read of any range.  
+    /// Only applies to files with a known size and not to their named streams.
+    Full,
+}
+
+fn set_attribute(attributes: &mut u32, attribute: FileAttributes, enabled: bool) {
+    if enabled {
+        *attributes |= attribute.bits();
+    } else {
+        *attributes &= !attribute.bits();
+    }
+}
+
+/// An alternate (named) data stream of a file (e.g. `file.txt:metadata`).
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NamedStream {
+    /// Name of the stream without the leading colon and stream type.
+    pub name: String,
+    pub size: u64,
 }
 
 /// Supported attributes for directories
@@ -106,14 +495,156 @@ pub struct DirectoryInfo {
     pub creation_time: u64,
     pub last_access_time: u64,
     pub last_write_time: u64,
+
+    /// Secondary sort key for the provider's own use (e.g. within wrapping sources).  
+    /// Ignored by ProjFS, which always receives the entries in its own collation order
+    /// unless configured otherwise by the `SortStrategy`.
+    pub sort_key: Option<u64>,
 }
 
+impl DirectoryInfo {
+    /// Create the directory info from already queried metadata
+    /// (e.g. from `fs::symlink_metadata`) without accessing the directory again.
+    pub fn from_metadata(directory_name: impl Into<String>, metadata: &Metadata) -> Self {
+        Self {
+            directory_name: directory_name.into(),
+            directory_attributes: metadata.file_attributes(),
+
+            creation_time: metadata.creation_time(),
+            last_access_time: metadata.last_access_time(),
+            last_write_time: metadata.last_write_time(),
+
+            sort_key: None,
+        }
+    }
+
+    /// Returns the creation time or `None` if not set.
+    pub fn created(&self) -> Option<SystemTime> {
+        filetime_to_system_time(self.creation_time)
+    }
+
+    /// Returns the last access time or `None` if not set.
+    pub fn accessed(&self) -> Option<SystemTime> {
+        filetime_to_system_time(self.last_access_time)
+    }
+
+    /// Returns the last write time or `None` if not set.
+    pub fn modified(&self) -> Option<SystemTime> {
+        filetime_to_system_time(self.last_write_time)
+    }
+
+    pub fn attributes(&self) -> FileAttributes {
+        FileAttributes(self.directory_attributes)
+    }
+
+    /// Set or clear `FILE_ATTRIBUTE_HIDDEN`.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        set_attribute(
+            &mut self.directory_attributes,
+            FileAttributes::HIDDEN,
+            hidden,
+        );
+        self
+    }
+
+    /// Set or clear `FILE_ATTRIBUTE_READONLY`.
+    pub fn readonly(mut self, readonly: bool) -> Self {
+        set_attribute(
+            &mut self.directory_attributes,
+            FileAttributes::READONLY,
+            readonly,
+        );
+        self
+    }
+
+    /// Set or clear `FILE_ATTRIBUTE_SYSTEM`.
+    pub fn system(mut self, system: bool) -> Self {
+        set_attribute(
+            &mut self.directory_attributes,
+            FileAttributes::SYSTEM,
+            system,
+        );
+        self
+    }
+
+    /// Compare the name and attributes ignoring the creation, last access and last write time.  
+    /// Use `==` to compare the timestamps as well.
+    pub fn matches_ignoring_times(&self, other: &Self) -> bool {
+        self.directory_name == other.directory_name
+            && self.directory_attributes == other.directory_attributes
+            && self.sort_key == other.sort_key
+    }
+}
+
+/// Lazily produces the entries of a single directory listing.
+///
+/// The producer is polled whenever ProjFS requests more entries and
+/// therefore has to remember where it left off.
+pub trait DirectoryProducer {
+    /// Return the next entry or `None` once all entries have been produced.  
+    /// Returning an error aborts the enumeration.
+    fn next_entry(&mut self) -> Option<io::Result<DirectoryEntry>>;
+
+    /// Restart producing from the first entry.  
+    /// Invoked when ProjFS restarts the scan of the directory.
+    fn reset(&mut self);
+}
+
+/// Chunks of file contents returned by `ProjectedFileSystemSource::stream_file_chunks`.
+pub type FileChunks = Box<dyn Iterator<Item = io::Result<Vec<u8>>>>;
+
+/// Write alignment assumed if ProjFS does not report one (see `ProjectedFileSystem::write_alignment`).  
+/// Slices returned by `ProjectedFileSystemSource::file_content_slice` aligned to this
+/// are written without copying on most volumes.
+pub const DIRECT_WRITE_ALIGNMENT: usize = 4096;
+
 /// Implementation for the data source of the projected file system.
 pub trait ProjectedFileSystemSource {
     /// Return a list of directory entries contained at that specific path.
     /// Return an empty list to indicate that the directory is empty or does not exists.
+    ///
+    /// Note:  
+    /// Paths can be nested arbitrarily deep. The crate only ever lists the requested
+    /// directory (or the parent of a looked up entry) and never walks its ancestors,
+    /// therefore implementations should not recurse per path component either
+    /// (see `resolve_path` for an iterative lookup within in-memory trees).
     fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry>;
 
+    /// Return up to `count` directory entries contained at `path` starting with the entry at `offset`.  
+    /// Implementing this allows enumerating very large directories as only one chunk
+    /// of entries will be held in memory at a time.  
+    /// Return `None` to indicate that chunked listing is not supported and `list_directory` should be used.
+    ///
+    /// Note:  
+    /// Chunked listings will not be sorted. The entries of all chunks must be returned
+    /// in the ProjFS collation order (`PrjFileNameCompare`, case-insensitive) and must be
+    /// stable across calls, else ProjFS might report duplicate or missing entries.  
+    /// Returning less then `count` entries indicates the end of the directory.  
+    /// Returning an error aborts the enumeration (e.g. if the backend went away).
+    /// The raw OS error of the error will be reported to the caller or
+    /// `ERROR_OPERATION_ABORTED` if not present.
+    fn list_directory_chunk(
+        &self,
+        _path: &Path,
+        _offset: usize,
+        _count: usize,
+    ) -> Option<io::Result<Vec<DirectoryEntry>>> {
+        None
+    }
+
+    /// Return a producer yielding the directory entries contained at `path` one by one.  
+    /// Implementing this allows generating large directory listings lazily as entries
+    /// are only requested when ProjFS has room for them.  
+    /// Return `None` to indicate that producers are not supported and
+    /// `list_directory_chunk` or `list_directory` should be used.
+    ///
+    /// Note:  
+    /// The same ordering requirements as for `list_directory_chunk` apply.  
+    /// Returning an error aborts the enumeration.
+    fn directory_producer(&self, _path: &Path) -> Option<io::Result<Box<dyn DirectoryProducer>>> {
+        None
+    }
+
     /// Return information about the target path.  
     /// The path can be any of the previously returned `DirectoryEntry`s.  
     ///  
@@ -121,21 +652,75 @@ pub trait ProjectedFileSystemSource {
     ///
     /// Note:  
     /// The default implementation is for convinience and should be overridden as  
-    /// looping trough all directory entries might come with a performance penalty.
+    /// looping trough all directory entries might come with a performance penalty
+    /// (see `entry_at`).  
+    /// Opening a path which is not on disk yet (e.g. `fs::metadata` or reading a file)
+    /// is served by this method alone. Sources overriding it will not be asked to list
+    /// the parent directory until the directory itself gets enumerated.  
+    /// ProjFS does not report whether it expects a file or a directory at `path`
+    /// (the callback flags only concern directory enumerations), therefore a path must
+    /// identify a single entry within its parent directory.
     fn get_directory_entry(&self, path: &Path) -> Option<DirectoryEntry> {
-        let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.entry_at(path)
+    }
+
+    /// Return `true` if `path` exists within the source.  
+    /// ProjFS requests placeholder information for every path it does not know,
+    /// including paths which do not exist (e.g. when probing for configuration files).
+    /// These lookups are answered by this method without calling `get_directory_entry`.
+    ///
+    /// Note:  
+    /// The default implementation uses `get_directory_entry`. Overriding it with a cheap check
+    /// (e.g. a lookup within an index) can speed up negative lookups dramatically.  
+    /// For existing paths `get_directory_entry` is called afterwards.
+    fn exists(&self, path: &Path) -> bool {
+        self.get_directory_entry(path).is_some()
+    }
+
+    /// Look up `path` within the listing of its parent directory.  
+    /// While serving a ProjFS request the last listed directory is cached,
+    /// therefore repeated lookups within the same request (e.g. by wrapping sources)
+    /// list the parent directory only once.
+    ///
+    /// Note:
+    /// This is not intended to be overridden. Use it as fallback within `get_directory_entry`.  
+    /// Changes to the listing during the same request will not be observed.  
+    /// Only the parent directory is listed, independent of the depth of `path`.
+    fn entry_at(&self, path: &Path) -> Option<DirectoryEntry> {
+        let directory = path.parent().unwrap_or(Path::new(""));
         let file_name = path.file_name().map(OsStr::to_string_lossy)?;
 
-        self.list_directory(&directory)
-            .into_iter()
-            .find(|entry| entry.name() == file_name)
+        listing_cache::find_entry(self, directory, &file_name, || {
+            self.list_directory(directory)
+        })
+    }
+
+    /// Return an identifier for the current content of `path`.  
+    /// The content id will be stored within the placeholder which allows ProjFS
+    /// to detect if the cached content does no longer match the source.  
+    ///
+    /// Note:  
+    /// Content ids are limited to 16 bytes (e.g. a GUID or a truncated hash).  
+    /// Returning `None` stores an all zero content id.
+    fn content_id(&self, _path: &Path) -> Option<[u8; 16]> {
+        None
     }
 
     /// Return a stream to the file contents of `path`.  
     ///   
     /// Note:
     /// The returned Box<dyn Read> must respect the byte_offset and will not be read  
-    /// past `length` bytes.
+    /// past `length` bytes.  
+    /// The requested range never exceeds the size declared in `FileInfo::file_size`.
+    /// Reads starting at or past the end of the file fail with `ERROR_HANDLE_EOF`
+    /// without invoking the source.  
+    /// For files with `FileSize::Unknown` the whole content will be requested once  
+    /// with a `length` of `usize::MAX`. The stream must end at the end of the file.  
+    /// If the buffered content has been released before it was read (see `FileSize::Unknown`),
+    /// the whole content will be requested again starting at `byte_offset` zero.  
+    /// Return (or fail reading with) `RangeNotAvailable` if the requested range is
+    /// currently not available.  
+    /// Return a `SourceError` if `path` is no file (any more).
     fn stream_file_content(
         &self,
         path: &Path,
@@ -143,11 +728,144 @@ pub trait ProjectedFileSystemSource {
         length: usize,
     ) -> std::io::Result<Box<dyn Read>>;
 
+    /// Return a stream to the file contents of `path` for a read which can be canceled.  
+    /// Sources reading from slow backends should regularly check `cancellation` and
+    /// return an error with `io::ErrorKind::Interrupted` once it has been canceled.
+    ///
+    /// Note:  
+    /// The default implementation forwards to `stream_file_content`. The projection stops
+    /// writing the returned stream once the read has been canceled regardless.
+    fn stream_file_content_cancelable(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+        _cancellation: &CancellationToken,
+    ) -> std::io::Result<Box<dyn Read>> {
+        self.stream_file_content(path, byte_offset, length)
+    }
+
+    /// Return a stream to the file contents of `path` with mutable access to the source
+    /// (e.g. for sources reusing a single decompressor or connection).  
+    /// This avoids wrapping such state in a `Mutex` as the projection already
+    /// holds the source exclusively while serving a read.
+    ///
+    /// Note:  
+    /// This is the method invoked by the projection. The default implementation
+    /// forwards to `stream_file_content_cancelable`.  
+    /// Reads are serialized with all other callbacks: while a read is being served,
+    /// no other file can be read and no directory can be listed. Slow backends
+    /// should prefer an `AsyncSource`, which does not block the projection.
+    fn stream_file_content_mut(
+        &mut self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+        cancellation: &CancellationToken,
+    ) -> std::io::Result<Box<dyn Read>> {
+        self.stream_file_content_cancelable(path, byte_offset, length, cancellation)
+    }
+
+    /// Return the file contents of `path` starting at `byte_offset` as slice
+    /// (e.g. for sources holding the contents in memory).  
+    /// Return `None` to serve the read via `stream_file_content_mut` instead.
+    ///
+    /// Note:  
+    /// The slice must contain at least `length` bytes, further bytes are ignored.  
+    /// ProjFS requires the written data to be aligned for the storage device. Slices aligned to
+    /// `ProjectedFileSystem::write_alignment` bytes are passed to ProjFS directly, others are copied
+    /// into an aligned buffer (without the overhead of a stream).  
+    /// Reads of an `AsyncSource` and files with `FileSize::Unknown` do not use this.
+    fn file_content_slice(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> Option<io::Result<Cow<'_, [u8]>>> {
+        None
+    }
+
+    /// Return the file contents of `path` as a sequence of chunks
+    /// (e.g. for sources fetching the contents page by page).  
+    /// Return `None` to serve the read via `stream_file_content_mut` instead.
+    ///
+    /// Note:  
+    /// The same offset and length constraints as for `stream_file_content` apply.
+    /// Chunks may have any size. They are written in order starting at `byte_offset`
+    /// and are collected into aligned buffers as required by ProjFS.  
+    /// Reads of an `AsyncSource` and files with `FileSize::Unknown` do not use this.
+    fn stream_file_chunks(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> Option<io::Result<FileChunks>> {
+        None
+    }
+
+    /// Return the ranges of `path` which only contain zeros (e.g. the holes of a sparse file).  
+    /// These ranges are written without requesting them from the source.
+    ///
+    /// Note:  
+    /// This is only queried for files with `FILE_ATTRIBUTE_SPARSE_FILE`.
+    /// ProjFS does not know about holes and requires all requested data to be written,
+    /// therefore the zeros still end up in the hydrated file.  
+    /// Ranges are shrunk to multiples of `ProjectedFileSystem::write_alignment` unless they reach
+    /// the end of the requested range. Reads of an `AsyncSource` do not use this.
+    fn zero_ranges(&self, _path: &Path) -> Vec<Range<u64>> {
+        Vec::new()
+    }
+
+    /// Return a stream to the contents of the alternate data stream `stream_name` of `path`.  
+    /// The same offset and length constraints as for `stream_file_content` apply.  
+    ///
+    /// Note:  
+    /// This will only be called for streams listed in `FileInfo::named_streams`.
+    /// The default (unnamed) stream is always served by `stream_file_content`.
+    fn stream_named_content(
+        &self,
+        _path: &Path,
+        _stream_name: &str,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> std::io::Result<Box<dyn Read>> {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "named streams are not supported",
+        ))
+    }
+
     /// Handle file system notifications.
     /// All pre-notifications can be cancelled.
-    fn handle_notification(&self, _notification: &Notification) -> ControlFlow<()> {
+    fn handle_notification(&self, _notification: &Notification) -> ControlFlow<NotificationVeto> {
         ControlFlow::Continue(())
     }
+
+    /// Handle file system notifications with mutable access to the source.  
+    /// This allows recording state without interior mutability as all
+    /// callbacks are serialized by the projection anyways.
+    ///
+    /// Note:  
+    /// This is the method invoked by the projection. The default implementation
+    /// forwards to `handle_notification`.
+    fn handle_notification_mut(
+        &mut self,
+        notification: &Notification,
+    ) -> ControlFlow<NotificationVeto> {
+        self.handle_notification(notification)
+    }
+
+    /// Invoked once the projection has been stopped.  
+    /// All running callbacks have finished and no further callbacks will arrive
+    /// which makes this the place to release external resources.
+    fn on_stop(&self) {}
+
+    /// Invoked with the unprocessed callback information before the projection
+    /// serves a callback (advanced use only).
+    ///
+    /// Note:
+    /// Cancellations are not reported as they arrive while other callbacks are being served.
+    fn on_raw_callback(&self, _info: &RawCallbackInfo) {}
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -162,6 +880,30 @@ pub enum FileCloseAction {
     NoModification,
 }
 
+/// Resolve `path` within a tree starting at `root` by walking its components iteratively.  
+/// `child` returns the child of a node with the given name.
+///
+/// Note:  
+/// The stack usage does not depend on the depth of `path`.  
+/// Returns `None` if any component does not exist, is not valid UTF-8 or `path`
+/// is not relative (e.g. contains `..`).
+pub fn resolve_path<'a, N: ?Sized>(
+    root: &'a N,
+    path: &Path,
+    mut child: impl FnMut(&'a N, &str) -> Option<&'a N>,
+) -> Option<&'a N> {
+    let mut node = root;
+    for component in path.components() {
+        match component {
+            Component::CurDir => continue,
+            Component::Normal(name) => node = child(node, name.to_str()?)?,
+            Component::Prefix(_) | Component::RootDir | Component::ParentDir => return None,
+        }
+    }
+
+    Some(node)
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ProjectedFile {
     pub file_id: u128,
@@ -169,10 +911,43 @@ pub struct ProjectedFile {
     pub path: PathBuf,
 }
 
+impl ProjectedFile {
+    /// Resolve the on-disk path of the file.  
+    /// `root` is the root of the projection (see `ProjectedFileSystem::root`).
+    pub fn absolute(&self, root: &Path) -> PathBuf {
+        root.join(&self.path)
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FileRenameInfo {
+    /// Path relative to the projection root.  
+    /// `None` if the entry has been moved into the projection root.
     pub source: Option<PathBuf>,
+
+    /// Path relative to the projection root.  
+    /// `None` if the entry is being moved out of the projection root.
     pub destination: Option<PathBuf>,
+
+    /// `true` if a directory is being renamed
+    pub is_directory: bool,
+}
+
+impl FileRenameInfo {
+    /// `true` if the entry is moved into another directory.  
+    /// Moves into or out of the projection root are moves as well.
+    pub fn is_move(&self) -> bool {
+        match (&self.source, &self.destination) {
+            (Some(source), Some(destination)) => source.parent() != destination.parent(),
+            _ => true,
+        }
+    }
+
+    /// File name of the entry after the rename.  
+    /// `None` if the entry is being moved out of the projection root.
+    pub fn new_name(&self) -> Option<&OsStr> {
+        self.destination.as_deref().and_then(Path::file_name)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -180,6 +955,14 @@ pub enum Notification {
     FileCreated(ProjectedFile),
     FileOpened(ProjectedFile),
     FileClosed(ProjectedFile, FileCloseAction),
+
+    /// The file has been overwritten or superseded.  
+    /// Its previous contents have been discarded and the file is no longer a placeholder,
+    /// therefore any cached state for the file should be invalidated.
+    ///
+    /// Note:
+    /// ProjFS does not report the resulting file. The notification parameters only allow
+    /// the provider to change the notifications it receives for the file.
     FileOverwritten(ProjectedFile),
 
     PreFileRename(FileRenameInfo),
@@ -196,13 +979,115 @@ impl Notification {
     /// Returns `true` if the action can be cancelled  
     /// by returning `ControlFlow::Break`
     pub fn is_cancelable(&self) -> bool {
-        #[allow(clippy::match_like_matches_macro)]
+        self.kind().is_cancelable()
+    }
+
+    /// The result reported to ProjFS when cancelling the action
+    /// without specifying a result.
+    pub fn default_veto_result(&self) -> HRESULT {
+        STATUS_CANNOT_DELETE.to_hresult()
+    }
+
+    /// Returns the kind of the notification without its payload.
+    pub fn kind(&self) -> NotificationKind {
         match self {
-            Self::PreFileRename(_) => true,
-            Self::PreFileDelete(_) => true,
-            Self::PreSetHardlink(_) => true,
-            Self::FilePreConvertToFull(_) => true,
-            _ => false,
+            Self::FileCreated(_) => NotificationKind::FileCreated,
+            Self::FileOpened(_) => NotificationKind::FileOpened,
+            Self::FileClosed(_, _) => NotificationKind::FileClosed,
+            Self::FileOverwritten(_) => NotificationKind::FileOverwritten,
+            Self::PreFileRename(_) => NotificationKind::PreFileRename,
+            Self::FileRenamed(_) => NotificationKind::FileRenamed,
+            Self::PreSetHardlink(_) => NotificationKind::PreSetHardlink,
+            Self::HardlinkCreated(_) => NotificationKind::HardlinkCreated,
+            Self::PreFileDelete(_) => NotificationKind::PreFileDelete,
+            Self::FilePreConvertToFull(_) => NotificationKind::FilePreConvertToFull,
         }
     }
 }
+
+/// The kind of a `Notification` without its payload.
+///
+/// Note:  
+/// Every notification kind is available since the first ProjFS release
+/// (Windows 10, version 1809). The projection therefore always subscribes to all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotificationKind {
+    FileCreated,
+    FileOpened,
+    /// The file has been closed regardless of the `FileCloseAction`
+    FileClosed,
+    FileOverwritten,
+    PreFileRename,
+    FileRenamed,
+    PreSetHardlink,
+    HardlinkCreated,
+    PreFileDelete,
+    FilePreConvertToFull,
+}
+
+impl NotificationKind {
+    /// All notification kinds.
+    pub const ALL: [Self; 10] = [
+        Self::FileCreated,
+        Self::FileOpened,
+        Self::FileClosed,
+        Self::FileOverwritten,
+        Self::PreFileRename,
+        Self::FileRenamed,
+        Self::PreSetHardlink,
+        Self::HardlinkCreated,
+        Self::PreFileDelete,
+        Self::FilePreConvertToFull,
+    ];
+
+    /// Returns `true` if notifications of this kind can be cancelled  
+    /// by returning `ControlFlow::Break`
+    pub fn is_cancelable(self) -> bool {
+        matches!(
+            self,
+            Self::PreFileRename
+                | Self::PreFileDelete
+                | Self::PreSetHardlink
+                | Self::FilePreConvertToFull
+        )
+    }
+}
+
+/// Reason for cancelling a notification via `ControlFlow::Break`.
+///
+/// The default veto reports `Notification::default_veto_result` to ProjFS.
+/// Use `NotificationVeto::with_result` to report a specific result
+/// (e.g. `STATUS_SHARING_VIOLATION` to defer a delete).
+///
+/// Note:
+/// Existing handlers returning `ControlFlow::Break(())` have to be changed
+/// to `ControlFlow::Break(().into())` or `ControlFlow::Break(NotificationVeto::default())`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NotificationVeto {
+    result: Option<HRESULT>,
+}
+
+impl NotificationVeto {
+    pub fn with_result(result: HRESULT) -> Self {
+        Self {
+            result: Some(result),
+        }
+    }
+
+    /// The result reported to ProjFS or `None` to use the default of the notification.
+    pub fn result(&self) -> Option<HRESULT> {
+        self.result
+    }
+}
+
+impl From<()> for NotificationVeto {
+    fn from(_: ()) -> Self {
+        Self::default()
+    }
+}
+
+impl From<HRESULT> for NotificationVeto {
+    fn from(value: HRESULT) -> Self {
+        Self::with_result(value)
+    }
+}
@@ -1,8 +1,208 @@
+use std::{
+    ffi::{
+        OsStr,
+        OsString,
+    },
+    io,
+    iter,
+    os::windows::ffi::{
+        OsStrExt,
+        OsStringExt,
+    },
+    panic::{
+        self,
+        AssertUnwindSafe,
+    },
+    path::{
+        Component,
+        Path,
+        PathBuf,
+        Prefix,
+    },
+    time::{
+        Duration,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
 use windows::{
     core::HRESULT,
-    Win32::Foundation::ERROR_IO_INCOMPLETE,
+    Win32::Foundation::{
+        ERROR_DIRECTORY_NOT_SUPPORTED,
+        ERROR_FILE_NOT_FOUND,
+        ERROR_FILE_OFFLINE,
+        ERROR_INVALID_NAME,
+        ERROR_IO_INCOMPLETE,
+        E_UNEXPECTED,
+        MAX_PATH,
+    },
+};
+
+use crate::{
+    CallbackKind,
+    RangeNotAvailable,
 };
 
 pub fn io_result_to_hresult(error: std::io::Error) -> HRESULT {
-    HRESULT::from_win32(error.raw_os_error().unwrap_or(ERROR_IO_INCOMPLETE.0 as i32) as u32)
+    if error
+        .get_ref()
+        .is_some_and(|inner| inner.is::<RangeNotAvailable>())
+    {
+        return ERROR_FILE_OFFLINE.to_hresult();
+    }
+
+    if let Some(code) = error.raw_os_error() {
+        return HRESULT::from_win32(code as u32);
+    }
+
+    match error.kind() {
+        io::ErrorKind::NotFound => ERROR_FILE_NOT_FOUND.to_hresult(),
+        io::ErrorKind::IsADirectory => ERROR_DIRECTORY_NOT_SUPPORTED.to_hresult(),
+        _ => ERROR_IO_INCOMPLETE.to_hresult(),
+    }
+}
+
+/// Run `operation` and convert a panic into `E_UNEXPECTED`.  
+/// Panics must not unwind into ProjFS as the callbacks are `extern "system"` functions.
+///
+/// Note:
+/// The panic hook still runs and prints the panic message as usual.
+pub fn catch_panic<T>(
+    kind: CallbackKind,
+    executor: impl FnOnce() -> Result<T, HRESULT>,
+) -> Result<T, HRESULT> {
+    panic::catch_unwind(AssertUnwindSafe(executor)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("<unknown>");
+
+        log::error!("Source panicked while handling {:?}: {}", kind, message);
+        Err(E_UNEXPECTED)
+    })
+}
+
+/// Encode `value` as zero terminated UTF-16 string.  
+/// Unlike a conversion via `String` this preserves unpaired surrogates.
+pub fn encode_wide_null(value: impl AsRef<OsStr>) -> Vec<u16> {
+    value.as_ref().encode_wide().chain(iter::once(0)).collect()
+}
+
+/// Encode `path` as zero terminated UTF-16 string for Win32 APIs.  
+/// Paths exceeding `MAX_PATH` are converted into absolute paths prefixed with `\\?\`
+/// (or `\\?\UNC\`) as otherwise these APIs would reject them.
+pub fn encode_path_null(path: &Path) -> Vec<u16> {
+    if path.as_os_str().encode_wide().count() < MAX_PATH as usize {
+        return encode_wide_null(path);
+    }
+
+    let path = match std::path::absolute(path) {
+        Ok(path) => path,
+        Err(_) => return encode_wide_null(path),
+    };
+
+    let (mut result, skip) = match path.components().next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => (OsString::from("\\\\?\\"), 0),
+            /* \\server\share becomes \\?\UNC\server\share */
+            Prefix::UNC(_, _) => (OsString::from("\\\\?\\UNC"), 1),
+            /* verbatim and device paths are not limited by MAX_PATH */
+            _ => return encode_wide_null(path),
+        },
+        _ => return encode_wide_null(path),
+    };
+
+    let wide = path
+        .as_os_str()
+        .encode_wide()
+        .skip(skip)
+        .collect::<Vec<_>>();
+    result.push(OsString::from_wide(&wide));
+    encode_wide_null(result)
+}
+
+/// Number of 100ns intervals between the Windows epoch (1601-01-01) and the Unix epoch.
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+/// Convert a Windows file time into a `SystemTime`.  
+/// A file time of zero is considered as not set.
+pub fn filetime_to_system_time(filetime: u64) -> Option<SystemTime> {
+    if filetime == 0 {
+        return None;
+    }
+
+    let intervals_to_duration = |intervals: u64| {
+        Duration::new(
+            intervals / 10_000_000,
+            (intervals % 10_000_000) as u32 * 100,
+        )
+    };
+
+    if filetime >= FILETIME_UNIX_EPOCH {
+        UNIX_EPOCH.checked_add(intervals_to_duration(filetime - FILETIME_UNIX_EPOCH))
+    } else {
+        UNIX_EPOCH.checked_sub(intervals_to_duration(FILETIME_UNIX_EPOCH - filetime))
+    }
+}
+
+/// Normalize the path of a ProjFS request into a path relative to the projection root.  
+/// Both `\\` and `/` are accepted as separator and empty or `.` components are dropped.  
+/// Returns `ERROR_INVALID_NAME` if the path contains `..` as sources must never be
+/// asked for paths outside of the projection root.
+pub fn normalize_request_path(path: Option<&Path>) -> Result<PathBuf, HRESULT> {
+    let Some(path) = path else {
+        return Ok(PathBuf::new());
+    };
+
+    let wide = path.as_os_str().encode_wide().collect::<Vec<_>>();
+    let mut result = PathBuf::new();
+    for component in wide.split(|char| *char == b'\\' as u16 || *char == b'/' as u16) {
+        match component {
+            [] => continue,
+            [dot] if *dot == b'.' as u16 => continue,
+            [dot1, dot2] if *dot1 == b'.' as u16 && *dot2 == b'.' as u16 => {
+                log::warn!("Rejecting request path {}", path.display());
+                return Err(ERROR_INVALID_NAME.to_hresult());
+            }
+            component => result.push(OsString::from_wide(component)),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Normalize a path reported by ProjFS into a path relative to the projection root.  
+/// Returns `None` for an empty path (ProjFS reports paths outside of the root as empty)
+/// or a path which is rejected by `normalize_request_path`.
+///
+/// Note:
+/// ProjFS always reports root relative paths. A single file name therefore refers
+/// to an entry within the root and must not be joined with the parent of another path.
+pub fn normalize_relative_path(path: &Path) -> Option<PathBuf> {
+    normalize_request_path(Some(path))
+        .ok()
+        .filter(|path| !path.as_os_str().is_empty())
+}
+
+/// Split a path like `dir/file.txt:stream:$DATA` into the file path
+/// and the name of the alternate data stream (if any).
+pub fn split_stream_name(path: &Path) -> (PathBuf, Option<String>) {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return (path.to_path_buf(), None);
+    };
+
+    let Some((file_name, stream)) = file_name.split_once(':') else {
+        return (path.to_path_buf(), None);
+    };
+
+    let stream_name = stream.strip_suffix(":$DATA").unwrap_or(stream);
+    let path = path.with_file_name(file_name);
+    if stream_name.is_empty() {
+        /* "file.txt::$DATA" refers to the default stream */
+        (path, None)
+    } else {
+        (path, Some(stream_name.to_string()))
+    }
 }
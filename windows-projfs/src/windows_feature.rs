@@ -0,0 +1,193 @@
+/// Instructions for enabling the Windows feature "Projected File System".
+/// Intended to be shown to the user when receiving `Error::WindowsFeatureNotEnabled`.
+pub fn projfs_enable_instructions() -> &'static str {
+    "The Windows feature \"Projected File System\" is not enabled.\n\
+     Enable it by running the following command in an elevated PowerShell:\n\
+     \n\
+     \tEnable-WindowsOptionalFeature -Online -FeatureName Client-ProjFS -NoRestart\n\
+     \n\
+     Alternatively enable \"Windows Projected File System\" within \"Turn Windows features on or off\"."
+}
+
+#[cfg(feature = "admin")]
+mod enable {
+    use std::{
+        ffi::c_void,
+        ptr,
+    };
+
+    use windows::{
+        core::{
+            HRESULT,
+            PCWSTR,
+        },
+        Win32::{
+            Foundation::{
+                CloseHandle,
+                BOOL,
+                ERROR_SUCCESS_REBOOT_REQUIRED,
+                HANDLE,
+            },
+            Security::{
+                GetTokenInformation,
+                TokenElevation,
+                TOKEN_ELEVATION,
+                TOKEN_QUERY,
+            },
+            System::Threading::{
+                GetCurrentProcess,
+                OpenProcessToken,
+            },
+        },
+    };
+
+    use crate::{
+        is_projfs_available,
+        utils::encode_wide_null,
+        Error,
+        Result,
+    };
+
+    /// Image path of the running operating system for `DismOpenSession`.
+    const DISM_ONLINE_IMAGE: &str = "DISM_{53BFAE52-B167-4E2F-A258-0A37B57FF845}";
+    const PROJFS_FEATURE_NAME: &str = "Client-ProjFS";
+
+    const DISM_LOG_ERRORS: i32 = 0;
+    const DISM_PACKAGE_NONE: i32 = 0;
+
+    type DismInitialize = extern "system" fn(i32, PCWSTR, PCWSTR) -> HRESULT;
+    type DismOpenSession = extern "system" fn(PCWSTR, PCWSTR, PCWSTR, *mut u32) -> HRESULT;
+    type DismEnableFeature = extern "system" fn(
+        u32,
+        PCWSTR,
+        PCWSTR,
+        i32,
+        BOOL,
+        *const PCWSTR,
+        u32,
+        BOOL,
+        HANDLE,
+        *const c_void,
+        *const c_void,
+    ) -> HRESULT;
+    type DismCloseSession = extern "system" fn(u32) -> HRESULT;
+    type DismShutdown = extern "system" fn() -> HRESULT;
+
+    /// Calls `DismShutdown` once dropped.
+    struct DismShutdownGuard(DismShutdown);
+
+    impl Drop for DismShutdownGuard {
+        fn drop(&mut self) {
+            let _ = (self.0)();
+        }
+    }
+
+    fn is_elevated() -> Result<bool> {
+        let mut token = HANDLE::default();
+        unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token)? };
+
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut length = 0;
+        let result = unsafe {
+            GetTokenInformation(
+                token,
+                TokenElevation,
+                Some(&mut elevation as *mut _ as *mut c_void),
+                std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+                &mut length,
+            )
+        };
+        let _ = unsafe { CloseHandle(token) };
+
+        result?;
+        Ok(elevation.TokenIsElevated != 0)
+    }
+
+    /// Enable the Windows feature "Projected File System" using the DISM API.
+    ///
+    /// Returns `Error::WindowsFeatureAlreadyEnabled` if the feature is already enabled
+    /// and `Error::NotElevated` if the current process is not elevated.
+    ///
+    /// Note:
+    /// Enabling the feature may require a restart before ProjFS can be used.
+    pub fn try_enable_projfs() -> Result<()> {
+        if is_projfs_available() {
+            return Err(Error::WindowsFeatureAlreadyEnabled);
+        }
+
+        if !is_elevated()? {
+            return Err(Error::NotElevated);
+        }
+
+        /* the process is elevated, never load the DISM API from anywhere else than the system directory */
+        let library: libloading::Library = unsafe {
+            libloading::os::windows::Library::load_with_flags(
+                "dismapi.dll",
+                libloading::os::windows::LOAD_LIBRARY_SEARCH_SYSTEM32,
+            )?
+        }
+        .into();
+        let (initialize, open_session, enable_feature, close_session, shutdown) = unsafe {
+            (
+                *library.get::<DismInitialize>(b"DismInitialize\0")?,
+                *library.get::<DismOpenSession>(b"DismOpenSession\0")?,
+                *library.get::<DismEnableFeature>(b"DismEnableFeature\0")?,
+                *library.get::<DismCloseSession>(b"DismCloseSession\0")?,
+                *library.get::<DismShutdown>(b"DismShutdown\0")?,
+            )
+        };
+
+        initialize(DISM_LOG_ERRORS, PCWSTR::null(), PCWSTR::null())
+            .ok()
+            .map_err(Error::EnableFeature)?;
+        let shutdown = DismShutdownGuard(shutdown);
+
+        let result = {
+            let image = encode_wide_null(DISM_ONLINE_IMAGE);
+            let mut session = 0;
+            match open_session(
+                PCWSTR(image.as_ptr()),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                &mut session,
+            )
+            .ok()
+            {
+                Ok(()) => {
+                    let feature = encode_wide_null(PROJFS_FEATURE_NAME);
+                    let result = enable_feature(
+                        session,
+                        PCWSTR(feature.as_ptr()),
+                        PCWSTR::null(),
+                        DISM_PACKAGE_NONE,
+                        false.into(),
+                        ptr::null(),
+                        0,
+                        true.into(),
+                        HANDLE::default(),
+                        ptr::null(),
+                        ptr::null(),
+                    );
+
+                    let _ = close_session(session);
+                    result
+                }
+                Err(error) => error.code(),
+            }
+        };
+        drop(shutdown);
+
+        if result == ERROR_SUCCESS_REBOOT_REQUIRED.to_hresult() {
+            log::info!(
+                "Enabled Windows feature {}. A restart is required.",
+                PROJFS_FEATURE_NAME
+            );
+            return Ok(());
+        }
+
+        result.ok().map_err(Error::EnableFeature)
+    }
+}
+
+#[cfg(feature = "admin")]
+pub use enable::try_enable_projfs;
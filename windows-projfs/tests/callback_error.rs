@@ -0,0 +1,68 @@
+mod common;
+
+use std::{
+    io::{
+        self,
+        Cursor,
+    },
+    path::PathBuf,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows_projfs::{
+    CallbackError,
+    CallbackKind,
+    DirectoryEntry,
+    FnSource,
+    ProjectedFileSystemBuilder,
+};
+
+#[test]
+fn callback_error_sink() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_callback_error_sink")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new()
+        .on_list(|_| {
+            vec![
+                DirectoryEntry::file("ok.txt", 4),
+                DirectoryEntry::file("broken.txt", 4),
+            ]
+        })
+        .on_stream(|path, _byte_offset, _length| {
+            if path.ends_with("broken.txt") {
+                return Err(io::Error::other("backend failure"));
+            }
+            Ok(Box::new(Cursor::new(b"data".to_vec())))
+        });
+
+    let errors = Arc::new(Mutex::new(Vec::<CallbackError>::new()));
+    let _pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .on_callback_error({
+            let errors = errors.clone();
+            move |error| errors.lock().unwrap().push(error)
+        })
+        .build(target_dir.path(), source)?;
+
+    /* expected results are not reported */
+    assert!(library.placeholder_info("missing.txt").is_err());
+    assert!(library.read_file("ok.txt", 0, 4).is_ok());
+    assert!(errors.lock().unwrap().is_empty());
+
+    let result = library.read_file("broken.txt", 0, 4);
+    assert!(result.is_err());
+
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, CallbackKind::GetFileData);
+    assert_eq!(errors[0].path, Some(PathBuf::from("broken.txt")));
+    assert_eq!(Err(errors[0].result), result);
+    Ok(())
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::cmp::Ordering;
+
+use common::MockLibrary;
+use windows_projfs::{
+    projfs_name_compare,
+    projfs_sorted_insert,
+    DirectoryEntry,
+};
+
+#[test]
+fn name_compare() {
+    let library = MockLibrary::new();
+    assert_eq!(
+        projfs_name_compare(&*library, "a.txt", "B.txt"),
+        Ordering::Less
+    );
+    assert_eq!(
+        projfs_name_compare(&*library, "b.txt", "A.txt"),
+        Ordering::Greater
+    );
+    assert_eq!(
+        projfs_name_compare(&*library, "a.txt", "A.TXT"),
+        Ordering::Equal
+    );
+}
+
+#[test]
+fn sorted_insert() {
+    let library = MockLibrary::new();
+
+    let mut entries = Vec::new();
+    for name in ["delta", "Alpha", "charlie", "Echo", "bravo"] {
+        projfs_sorted_insert(&*library, &mut entries, DirectoryEntry::file(name, 0));
+    }
+
+    let names = entries.iter().map(DirectoryEntry::name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Alpha", "bravo", "charlie", "delta", "Echo"]);
+
+    /* entries with the same name are replaced */
+    let index = projfs_sorted_insert(
+        &*library,
+        &mut entries,
+        DirectoryEntry::directory("CHARLIE"),
+    );
+    assert_eq!(index, 2);
+    assert_eq!(entries.len(), 5);
+    assert!(entries[2].is_dir());
+}
@@ -0,0 +1,919 @@
+#![allow(dead_code)]
+
+use std::{
+    alloc::{
+        self,
+        Layout,
+    },
+    cmp::Ordering,
+    collections::HashMap,
+    ffi::c_void,
+    sync::{
+        atomic::{
+            self,
+            AtomicI32,
+            AtomicUsize,
+        },
+        Arc,
+        Mutex,
+    },
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use windows::{
+    core::{
+        GUID,
+        HRESULT,
+        PCWSTR,
+    },
+    Win32::{
+        Foundation::{
+            BOOLEAN,
+            ERROR_FILE_NOT_FOUND,
+            ERROR_INSUFFICIENT_BUFFER,
+            ERROR_IO_PENDING,
+            S_OK,
+        },
+        Storage::ProjectedFileSystem::{
+            PRJ_CALLBACKS,
+            PRJ_CALLBACK_DATA,
+            PRJ_CB_DATA_FLAG_ENUM_RETURN_SINGLE_ENTRY,
+            PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS,
+            PRJ_DIR_ENTRY_BUFFER_HANDLE,
+            PRJ_EXTENDED_INFO,
+            PRJ_FILE_BASIC_INFO,
+            PRJ_FILE_STATE,
+            PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+            PRJ_NOTIFICATION,
+            PRJ_NOTIFICATION_PARAMETERS,
+            PRJ_PLACEHOLDER_INFO,
+            PRJ_PLACEHOLDER_VERSION_INFO,
+            PRJ_STARTVIRTUALIZING_FLAGS,
+            PRJ_STARTVIRTUALIZING_OPTIONS,
+            PRJ_UPDATE_FAILURE_CAUSES,
+            PRJ_UPDATE_TYPES,
+            PRJ_VIRTUALIZATION_INSTANCE_INFO,
+        },
+    },
+};
+use windows_projfs::{
+    load_library,
+    ProjectedFSLibrary,
+};
+
+/// Library forwarding all calls to the system library
+/// unless a failure has been injected.
+pub struct FaultInjectingLibrary {
+    inner: Arc<dyn ProjectedFSLibrary>,
+
+    /// Fail `prj_start_virtualizing` with the given code
+    pub fail_start: Option<HRESULT>,
+}
+
+impl FaultInjectingLibrary {
+    pub fn new() -> windows_projfs::Result<Self> {
+        Ok(Self {
+            inner: load_library()?,
+            fail_start: None,
+        })
+    }
+}
+
+impl ProjectedFSLibrary for FaultInjectingLibrary {
+    unsafe fn prj_allocate_aligned_buffer(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        size: usize,
+    ) -> *mut c_void {
+        self.inner
+            .prj_allocate_aligned_buffer(namespacevirtualizationcontext, size)
+    }
+
+    unsafe fn prj_free_aligned_buffer(&self, buffer: *const c_void) {
+        self.inner.prj_free_aligned_buffer(buffer)
+    }
+
+    unsafe fn prj_file_name_compare(&self, filename1: PCWSTR, filename2: PCWSTR) -> i32 {
+        self.inner.prj_file_name_compare(filename1, filename2)
+    }
+
+    unsafe fn prj_file_name_match(&self, filenametocheck: PCWSTR, pattern: PCWSTR) -> BOOLEAN {
+        self.inner.prj_file_name_match(filenametocheck, pattern)
+    }
+
+    unsafe fn prj_mark_directory_as_placeholder(
+        &self,
+        rootpathname: PCWSTR,
+        targetpathname: PCWSTR,
+        versioninfo: Option<*const PRJ_PLACEHOLDER_VERSION_INFO>,
+        virtualizationinstanceid: *const GUID,
+    ) -> windows::core::Result<()> {
+        self.inner.prj_mark_directory_as_placeholder(
+            rootpathname,
+            targetpathname,
+            versioninfo,
+            virtualizationinstanceid,
+        )
+    }
+
+    unsafe fn prj_start_virtualizing(
+        &self,
+        virtualizationrootpath: PCWSTR,
+        callbacks: *const PRJ_CALLBACKS,
+        instancecontext: Option<*const c_void>,
+        options: Option<*const PRJ_STARTVIRTUALIZING_OPTIONS>,
+    ) -> windows::core::Result<PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT> {
+        if let Some(code) = self.fail_start {
+            return Err(code.into());
+        }
+
+        self.inner.prj_start_virtualizing(
+            virtualizationrootpath,
+            callbacks,
+            instancecontext,
+            options,
+        )
+    }
+
+    unsafe fn prj_stop_virtualizing(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+    ) {
+        self.inner
+            .prj_stop_virtualizing(namespacevirtualizationcontext)
+    }
+
+    unsafe fn prj_fill_dir_entry_buffer2(
+        &self,
+        direntrybufferhandle: PRJ_DIR_ENTRY_BUFFER_HANDLE,
+        filename: PCWSTR,
+        filebasicinfo: Option<*const PRJ_FILE_BASIC_INFO>,
+        extendedinfo: Option<*const PRJ_EXTENDED_INFO>,
+    ) -> windows::core::Result<()> {
+        self.inner.prj_fill_dir_entry_buffer2(
+            direntrybufferhandle,
+            filename,
+            filebasicinfo,
+            extendedinfo,
+        )
+    }
+
+    unsafe fn prj_write_file_data(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        datastreamid: *const GUID,
+        buffer: *const c_void,
+        byteoffset: u64,
+        length: u32,
+    ) -> windows::core::Result<()> {
+        self.inner.prj_write_file_data(
+            namespacevirtualizationcontext,
+            datastreamid,
+            buffer,
+            byteoffset,
+            length,
+        )
+    }
+
+    unsafe fn prj_write_placeholder_info(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+        placeholderinfosize: u32,
+    ) -> windows::core::Result<()> {
+        self.inner.prj_write_placeholder_info(
+            namespacevirtualizationcontext,
+            destinationfilename,
+            placeholderinfo,
+            placeholderinfosize,
+        )
+    }
+
+    unsafe fn prj_write_placeholder_info2(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+        placeholderinfosize: u32,
+        extendedinfo: Option<*const PRJ_EXTENDED_INFO>,
+    ) -> windows::core::Result<()> {
+        self.inner.prj_write_placeholder_info2(
+            namespacevirtualizationcontext,
+            destinationfilename,
+            placeholderinfo,
+            placeholderinfosize,
+            extendedinfo,
+        )
+    }
+
+    unsafe fn prj_complete_command(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        commandid: i32,
+        completionresult: HRESULT,
+        extendedparameters: Option<*const PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS>,
+    ) -> windows::core::Result<()> {
+        self.inner.prj_complete_command(
+            namespacevirtualizationcontext,
+            commandid,
+            completionresult,
+            extendedparameters,
+        )
+    }
+
+    unsafe fn prj_clear_negative_path_cache(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        totalentrynumber: Option<*mut u32>,
+    ) -> windows::core::Result<()> {
+        self.inner
+            .prj_clear_negative_path_cache(namespacevirtualizationcontext, totalentrynumber)
+    }
+
+    unsafe fn prj_get_on_disk_file_state(
+        &self,
+        destinationfilename: PCWSTR,
+    ) -> windows::core::Result<PRJ_FILE_STATE> {
+        self.inner.prj_get_on_disk_file_state(destinationfilename)
+    }
+
+    unsafe fn prj_get_virtualization_instance_info(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+    ) -> windows::core::Result<PRJ_VIRTUALIZATION_INSTANCE_INFO> {
+        self.inner
+            .prj_get_virtualization_instance_info(namespacevirtualizationcontext)
+    }
+
+    unsafe fn prj_update_file_if_needed(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+        placeholderinfosize: u32,
+        updateflags: PRJ_UPDATE_TYPES,
+        failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+    ) -> windows::core::Result<()> {
+        self.inner.prj_update_file_if_needed(
+            namespacevirtualizationcontext,
+            destinationfilename,
+            placeholderinfo,
+            placeholderinfosize,
+            updateflags,
+            failurereason,
+        )
+    }
+
+    unsafe fn prj_delete_file(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        updateflags: PRJ_UPDATE_TYPES,
+        failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+    ) -> windows::core::Result<()> {
+        self.inner.prj_delete_file(
+            namespacevirtualizationcontext,
+            destinationfilename,
+            updateflags,
+            failurereason,
+        )
+    }
+}
+
+/// Directory entry written by `prj_fill_dir_entry_buffer2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilledEntry {
+    pub name: String,
+    pub is_directory: bool,
+    pub file_size: i64,
+    pub file_attributes: u32,
+}
+
+/// Placeholder written by `prj_write_placeholder_info` or `prj_update_file_if_needed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenPlaceholder {
+    pub name: String,
+    pub is_directory: bool,
+    pub file_size: i64,
+    pub file_attributes: u32,
+    pub placeholder_info_size: u32,
+}
+
+/// Enumeration started by `MockLibrary::start_enumeration`.
+pub struct MockEnumeration {
+    /// Referenced by `data`
+    _path: Vec<u16>,
+    data: PRJ_CALLBACK_DATA,
+    enumeration_id: GUID,
+}
+
+struct DirEntryBuffer {
+    capacity: usize,
+    entries: Vec<FilledEntry>,
+}
+
+/// Match `name` against a pattern supporting `*` and `?` wildcards.
+fn glob_match(name: &[char], pattern: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', pattern)) => (0..=name.len()).any(|skip| glob_match(&name[skip..], pattern)),
+        Some(('?', pattern)) => !name.is_empty() && glob_match(&name[1..], pattern),
+        Some((char, pattern)) => name.first() == Some(char) && glob_match(&name[1..], pattern),
+    }
+}
+
+/// Convert the result of a callback, requiring successful callbacks to return exactly `S_OK`.
+fn callback_result(result: HRESULT) -> Result<(), HRESULT> {
+    if result.is_err() {
+        return Err(result);
+    }
+
+    assert_eq!(result, S_OK, "successful callbacks must return S_OK");
+    Ok(())
+}
+
+fn encode_wide(value: &str) -> Vec<u16> {
+    value.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// ProjFS API replacement which records all calls and allows
+/// invoking the registered callbacks without involving ProjFS.
+#[derive(Default)]
+pub struct MockLibrary {
+    callbacks: Mutex<Option<(PRJ_CALLBACKS, usize)>>,
+    buffers: Mutex<HashMap<usize, Layout>>,
+    next_command_id: AtomicI32,
+
+    pub file_data: Mutex<Vec<(GUID, u64, Vec<u8>)>>,
+    /// Addresses of all buffers passed to `prj_write_file_data`
+    pub file_data_buffers: Mutex<Vec<usize>>,
+    /// Offset and length of all writes passed to `prj_write_file_data`
+    pub file_data_writes: Mutex<Vec<(u64, u32)>>,
+    pub placeholders: Mutex<Vec<WrittenPlaceholder>>,
+    pub completions: Mutex<Vec<(i32, HRESULT)>>,
+    pub start_flags: Mutex<Option<PRJ_STARTVIRTUALIZING_FLAGS>>,
+    /// Pool and concurrent thread count of the start options
+    pub start_thread_counts: Mutex<Option<(u32, u32)>>,
+    pub negative_path_cache_clears: AtomicUsize,
+    /// Instance ids passed to `prj_mark_directory_as_placeholder`
+    pub marked_instance_ids: Mutex<Vec<u128>>,
+    /// Root path passed to `prj_start_virtualizing`
+    pub virtualization_root: Mutex<Option<String>>,
+    /// On disk states reported by `prj_get_on_disk_file_state` by absolute path.
+    /// Other paths do not exist on disk.
+    pub on_disk_states: Mutex<HashMap<String, u32>>,
+    /// Placeholders passed to `prj_update_file_if_needed`
+    pub updated_files: Mutex<Vec<WrittenPlaceholder>>,
+    /// Paths passed to `prj_delete_file`
+    pub deleted_files: Mutex<Vec<String>>,
+    /// Fail `prj_update_file_if_needed` and `prj_delete_file` with the given code
+    pub fail_update: Mutex<Option<HRESULT>>,
+    /// Write alignment reported by `prj_get_virtualization_instance_info` (default: 4096)
+    pub write_alignment: Mutex<Option<u32>>,
+    /// Fail `prj_get_virtualization_instance_info` with the given code
+    pub fail_instance_info: Mutex<Option<HRESULT>>,
+}
+
+impl MockLibrary {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    fn started(&self) -> (PRJ_CALLBACKS, usize) {
+        self.callbacks
+            .lock()
+            .unwrap()
+            .expect("virtualization to be started")
+    }
+
+    fn callback_data(&self, path: &[u16], data_stream_id: GUID) -> PRJ_CALLBACK_DATA {
+        let (_, instance_context) = self.started();
+        PRJ_CALLBACK_DATA {
+            Size: std::mem::size_of::<PRJ_CALLBACK_DATA>() as u32,
+            NamespaceVirtualizationContext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT(1),
+            CommandId: self.next_command_id.fetch_add(1, atomic::Ordering::Relaxed),
+            DataStreamId: data_stream_id,
+            FilePathName: PCWSTR(path.as_ptr()),
+            InstanceContext: instance_context as *mut c_void,
+            TriggeringProcessId: std::process::id(),
+            ..Default::default()
+        }
+    }
+
+    /// Enumerate `path` like ProjFS would, requesting at most `buffer_capacity` entries per callback.
+    pub fn enumerate(
+        &self,
+        path: &str,
+        search_expression: Option<&str>,
+        buffer_capacity: usize,
+    ) -> Result<Vec<FilledEntry>, HRESULT> {
+        let (callbacks, _) = self.started();
+        let path = encode_wide(path);
+        let search_expression = search_expression.map(encode_wide);
+        let data = self.callback_data(&path, GUID::zeroed());
+        let enumeration_id = GUID::from_u128(data.CommandId as u128);
+
+        let result = unsafe {
+            (callbacks.StartDirectoryEnumerationCallback.unwrap())(&data, &enumeration_id)
+        };
+        callback_result(result)?;
+
+        let mut entries = Vec::new();
+        let result = loop {
+            let mut buffer = DirEntryBuffer {
+                capacity: buffer_capacity,
+                entries: Vec::new(),
+            };
+
+            let result = unsafe {
+                (callbacks.GetDirectoryEnumerationCallback.unwrap())(
+                    &data,
+                    &enumeration_id,
+                    search_expression
+                        .as_ref()
+                        .map_or(PCWSTR::null(), |expression| PCWSTR(expression.as_ptr())),
+                    PRJ_DIR_ENTRY_BUFFER_HANDLE(&mut buffer as *mut DirEntryBuffer as isize),
+                )
+            };
+            if let Err(result) = callback_result(result) {
+                break Err(result);
+            }
+
+            if buffer.entries.is_empty() {
+                break Ok(entries);
+            }
+            entries.extend(buffer.entries);
+        };
+
+        let end_result =
+            unsafe { (callbacks.EndDirectoryEnumerationCallback.unwrap())(&data, &enumeration_id) };
+        assert_eq!(end_result, S_OK);
+        result
+    }
+
+    /// Start an enumeration of `path` which is continued step by step via `next_entries`.
+    pub fn start_enumeration(&self, path: &str) -> Result<MockEnumeration, HRESULT> {
+        let (callbacks, _) = self.started();
+        let path = encode_wide(path);
+        let data = self.callback_data(&path, GUID::zeroed());
+        let enumeration_id = GUID::from_u128(data.CommandId as u128);
+
+        let result = unsafe {
+            (callbacks.StartDirectoryEnumerationCallback.unwrap())(&data, &enumeration_id)
+        };
+        callback_result(result)?;
+
+        Ok(MockEnumeration {
+            _path: path,
+            data,
+            enumeration_id,
+        })
+    }
+
+    /// Request the next entries of `enumeration` fitting into a buffer of `buffer_capacity` entries.
+    pub fn next_entries(
+        &self,
+        enumeration: &MockEnumeration,
+        buffer_capacity: usize,
+    ) -> Result<Vec<FilledEntry>, HRESULT> {
+        let (callbacks, _) = self.started();
+        let mut buffer = DirEntryBuffer {
+            capacity: buffer_capacity,
+            entries: Vec::new(),
+        };
+
+        let result = unsafe {
+            (callbacks.GetDirectoryEnumerationCallback.unwrap())(
+                &enumeration.data,
+                &enumeration.enumeration_id,
+                PCWSTR::null(),
+                PRJ_DIR_ENTRY_BUFFER_HANDLE(&mut buffer as *mut DirEntryBuffer as isize),
+            )
+        };
+        callback_result(result)?;
+        Ok(buffer.entries)
+    }
+
+    /// End `enumeration` like ProjFS would once the handle has been closed.  
+    /// Requesting further entries afterwards behaves like ProjFS using an unknown enumeration.
+    pub fn end_enumeration(&self, enumeration: &MockEnumeration) {
+        let (callbacks, _) = self.started();
+        let result = unsafe {
+            (callbacks.EndDirectoryEnumerationCallback.unwrap())(
+                &enumeration.data,
+                &enumeration.enumeration_id,
+            )
+        };
+        assert_eq!(result, S_OK);
+    }
+
+    /// Request a single entry of `path` per callback like `FindFirstFile`/`FindNextFile` would.
+    pub fn enumerate_single_entries(
+        &self,
+        path: &str,
+        search_expression: Option<&str>,
+    ) -> Result<Vec<Vec<FilledEntry>>, HRESULT> {
+        let (callbacks, _) = self.started();
+        let path = encode_wide(path);
+        let search_expression = search_expression.map(encode_wide);
+        let mut data = self.callback_data(&path, GUID::zeroed());
+        data.Flags = PRJ_CB_DATA_FLAG_ENUM_RETURN_SINGLE_ENTRY;
+        let enumeration_id = GUID::from_u128(data.CommandId as u128);
+
+        let result = unsafe {
+            (callbacks.StartDirectoryEnumerationCallback.unwrap())(&data, &enumeration_id)
+        };
+        callback_result(result)?;
+
+        let mut callbacks_entries = Vec::new();
+        let result = loop {
+            let mut buffer = DirEntryBuffer {
+                capacity: usize::MAX,
+                entries: Vec::new(),
+            };
+
+            let result = unsafe {
+                (callbacks.GetDirectoryEnumerationCallback.unwrap())(
+                    &data,
+                    &enumeration_id,
+                    search_expression
+                        .as_ref()
+                        .map_or(PCWSTR::null(), |expression| PCWSTR(expression.as_ptr())),
+                    PRJ_DIR_ENTRY_BUFFER_HANDLE(&mut buffer as *mut DirEntryBuffer as isize),
+                )
+            };
+            if let Err(result) = callback_result(result) {
+                break Err(result);
+            }
+
+            if buffer.entries.is_empty() {
+                break Ok(callbacks_entries);
+            }
+            callbacks_entries.push(buffer.entries);
+        };
+
+        let end_result =
+            unsafe { (callbacks.EndDirectoryEnumerationCallback.unwrap())(&data, &enumeration_id) };
+        assert_eq!(end_result, S_OK);
+        result
+    }
+
+    /// Send `notification` for `path` like ProjFS would after the operation happened.
+    pub fn notify(
+        &self,
+        path: &str,
+        is_directory: bool,
+        notification: PRJ_NOTIFICATION,
+    ) -> HRESULT {
+        self.notify_with_destination(path, None, is_directory, notification)
+    }
+
+    /// Send `notification` for `path` including a destination path (e.g. for renames).
+    pub fn notify_with_destination(
+        &self,
+        path: &str,
+        destination: Option<&str>,
+        is_directory: bool,
+        notification: PRJ_NOTIFICATION,
+    ) -> HRESULT {
+        let (callbacks, _) = self.started();
+        let path = encode_wide(path);
+        let destination = destination.map(encode_wide);
+        let data = self.callback_data(&path, GUID::zeroed());
+        let mut parameters = PRJ_NOTIFICATION_PARAMETERS::default();
+
+        unsafe {
+            (callbacks.NotificationCallback.unwrap())(
+                &data,
+                BOOLEAN::from(is_directory),
+                notification,
+                destination
+                    .as_ref()
+                    .map_or(PCWSTR::null(), |destination| PCWSTR(destination.as_ptr())),
+                &mut parameters,
+            )
+        }
+    }
+
+    /// Request the placeholder information of `path`.
+    pub fn placeholder_info(&self, path: &str) -> HRESULT {
+        let (callbacks, _) = self.started();
+        let path = encode_wide(path);
+        let data = self.callback_data(&path, GUID::zeroed());
+
+        unsafe { (callbacks.GetPlaceholderInfoCallback.unwrap())(&data) }
+    }
+
+    /// Request `length` bytes of `path` starting at `byte_offset` without awaiting asynchronous reads.
+    pub fn request_file_data(&self, path: &str, byte_offset: u64, length: u32) -> HRESULT {
+        let (callbacks, _) = self.started();
+        let path = encode_wide(path);
+        let data = self.callback_data(&path, GUID::new().expect("a new guid"));
+
+        unsafe { (callbacks.GetFileDataCallback.unwrap())(&data, byte_offset, length) }
+    }
+
+    /// Request `length` bytes of `path` starting at `byte_offset` and return the written data.  
+    /// Asynchronous reads are awaited.
+    pub fn read_file(&self, path: &str, byte_offset: u64, length: u32) -> Result<Vec<u8>, HRESULT> {
+        let (callbacks, _) = self.started();
+        let path = encode_wide(path);
+        let data_stream_id = GUID::new().expect("a new guid");
+        let data = self.callback_data(&path, data_stream_id);
+
+        let mut result =
+            unsafe { (callbacks.GetFileDataCallback.unwrap())(&data, byte_offset, length) };
+        if result == ERROR_IO_PENDING.to_hresult() {
+            let timeout = Instant::now() + Duration::from_secs(5);
+            result = loop {
+                let completion = self
+                    .completions
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|(command_id, _)| *command_id == data.CommandId)
+                    .map(|(_, result)| *result);
+
+                match completion {
+                    Some(result) => break result,
+                    None if Instant::now() > timeout => panic!("read has not been completed"),
+                    None => thread::sleep(Duration::from_millis(1)),
+                }
+            };
+        }
+        callback_result(result)?;
+
+        let mut file_data = self.file_data.lock().unwrap();
+        let mut chunks = file_data
+            .iter()
+            .filter(|(stream_id, _, _)| *stream_id == data_stream_id)
+            .map(|(_, offset, chunk)| (*offset, chunk.clone()))
+            .collect::<Vec<_>>();
+        file_data.retain(|(stream_id, _, _)| *stream_id != data_stream_id);
+
+        chunks.sort_by_key(|(offset, _)| *offset);
+        Ok(chunks.into_iter().flat_map(|(_, chunk)| chunk).collect())
+    }
+}
+
+impl ProjectedFSLibrary for MockLibrary {
+    unsafe fn prj_allocate_aligned_buffer(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        size: usize,
+    ) -> *mut c_void {
+        let layout = Layout::from_size_align(size.max(1), 4096).unwrap();
+        let buffer = alloc::alloc(layout);
+        self.buffers.lock().unwrap().insert(buffer as usize, layout);
+        buffer as *mut c_void
+    }
+
+    unsafe fn prj_free_aligned_buffer(&self, buffer: *const c_void) {
+        let layout = self
+            .buffers
+            .lock()
+            .unwrap()
+            .remove(&(buffer as usize))
+            .expect("buffer to be allocated");
+        alloc::dealloc(buffer as *mut u8, layout);
+    }
+
+    unsafe fn prj_file_name_compare(&self, filename1: PCWSTR, filename2: PCWSTR) -> i32 {
+        let filename1 = filename1.to_string().unwrap().to_uppercase();
+        let filename2 = filename2.to_string().unwrap().to_uppercase();
+        match filename1.cmp(&filename2) {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        }
+    }
+
+    unsafe fn prj_file_name_match(&self, filenametocheck: PCWSTR, pattern: PCWSTR) -> BOOLEAN {
+        let filename = filenametocheck
+            .to_string()
+            .unwrap()
+            .to_uppercase()
+            .chars()
+            .collect::<Vec<_>>();
+        let pattern = pattern
+            .to_string()
+            .unwrap()
+            .to_uppercase()
+            .chars()
+            .collect::<Vec<_>>();
+        BOOLEAN::from(glob_match(&filename, &pattern))
+    }
+
+    unsafe fn prj_mark_directory_as_placeholder(
+        &self,
+        _rootpathname: PCWSTR,
+        _targetpathname: PCWSTR,
+        _versioninfo: Option<*const PRJ_PLACEHOLDER_VERSION_INFO>,
+        virtualizationinstanceid: *const GUID,
+    ) -> windows::core::Result<()> {
+        self.marked_instance_ids
+            .lock()
+            .unwrap()
+            .push((*virtualizationinstanceid).to_u128());
+        Ok(())
+    }
+
+    unsafe fn prj_start_virtualizing(
+        &self,
+        virtualizationrootpath: PCWSTR,
+        callbacks: *const PRJ_CALLBACKS,
+        instancecontext: Option<*const c_void>,
+        options: Option<*const PRJ_STARTVIRTUALIZING_OPTIONS>,
+    ) -> windows::core::Result<PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT> {
+        *self.virtualization_root.lock().unwrap() = virtualizationrootpath.to_string().ok();
+        *self.start_flags.lock().unwrap() = options.map(|options| (*options).Flags);
+        *self.start_thread_counts.lock().unwrap() =
+            options.map(|options| ((*options).PoolThreadCount, (*options).ConcurrentThreadCount));
+        *self.callbacks.lock().unwrap() =
+            Some((*callbacks, instancecontext.unwrap_or_default() as usize));
+        Ok(PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT(1))
+    }
+
+    unsafe fn prj_stop_virtualizing(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+    ) {
+        *self.callbacks.lock().unwrap() = None;
+    }
+
+    unsafe fn prj_fill_dir_entry_buffer2(
+        &self,
+        direntrybufferhandle: PRJ_DIR_ENTRY_BUFFER_HANDLE,
+        filename: PCWSTR,
+        filebasicinfo: Option<*const PRJ_FILE_BASIC_INFO>,
+        _extendedinfo: Option<*const PRJ_EXTENDED_INFO>,
+    ) -> windows::core::Result<()> {
+        let buffer = &mut *(direntrybufferhandle.0 as *mut DirEntryBuffer);
+        if buffer.entries.len() >= buffer.capacity {
+            return Err(ERROR_INSUFFICIENT_BUFFER.to_hresult().into());
+        }
+
+        let basic_info = *filebasicinfo.expect("basic info to be present");
+        buffer.entries.push(FilledEntry {
+            name: filename.to_string().unwrap(),
+            is_directory: basic_info.IsDirectory.as_bool(),
+            file_size: basic_info.FileSize,
+            file_attributes: basic_info.FileAttributes,
+        });
+        Ok(())
+    }
+
+    unsafe fn prj_write_file_data(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        datastreamid: *const GUID,
+        buffer: *const c_void,
+        byteoffset: u64,
+        length: u32,
+    ) -> windows::core::Result<()> {
+        self.file_data_buffers.lock().unwrap().push(buffer as usize);
+        self.file_data_writes
+            .lock()
+            .unwrap()
+            .push((byteoffset, length));
+        let chunk = std::slice::from_raw_parts(buffer as *const u8, length as usize).to_vec();
+        self.file_data
+            .lock()
+            .unwrap()
+            .push((*datastreamid, byteoffset, chunk));
+        Ok(())
+    }
+
+    unsafe fn prj_write_placeholder_info(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+        placeholderinfosize: u32,
+    ) -> windows::core::Result<()> {
+        let basic_info = (*placeholderinfo).FileBasicInfo;
+        self.placeholders.lock().unwrap().push(WrittenPlaceholder {
+            name: destinationfilename.to_string().unwrap(),
+            is_directory: basic_info.IsDirectory.as_bool(),
+            file_size: basic_info.FileSize,
+            file_attributes: basic_info.FileAttributes,
+            placeholder_info_size: placeholderinfosize,
+        });
+        Ok(())
+    }
+
+    unsafe fn prj_write_placeholder_info2(
+        &self,
+        namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+        placeholderinfosize: u32,
+        _extendedinfo: Option<*const PRJ_EXTENDED_INFO>,
+    ) -> windows::core::Result<()> {
+        self.prj_write_placeholder_info(
+            namespacevirtualizationcontext,
+            destinationfilename,
+            placeholderinfo,
+            placeholderinfosize,
+        )
+    }
+
+    unsafe fn prj_complete_command(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        commandid: i32,
+        completionresult: HRESULT,
+        _extendedparameters: Option<*const PRJ_COMPLETE_COMMAND_EXTENDED_PARAMETERS>,
+    ) -> windows::core::Result<()> {
+        self.completions
+            .lock()
+            .unwrap()
+            .push((commandid, completionresult));
+        Ok(())
+    }
+
+    unsafe fn prj_clear_negative_path_cache(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        totalentrynumber: Option<*mut u32>,
+    ) -> windows::core::Result<()> {
+        self.negative_path_cache_clears
+            .fetch_add(1, atomic::Ordering::Relaxed);
+        if let Some(totalentrynumber) = totalentrynumber {
+            *totalentrynumber = 0;
+        }
+        Ok(())
+    }
+
+    unsafe fn prj_get_on_disk_file_state(
+        &self,
+        destinationfilename: PCWSTR,
+    ) -> windows::core::Result<PRJ_FILE_STATE> {
+        let path = destinationfilename.to_string().unwrap();
+        match self.on_disk_states.lock().unwrap().get(&path) {
+            Some(state) => Ok(PRJ_FILE_STATE(*state as _)),
+            None => Err(ERROR_FILE_NOT_FOUND.to_hresult().into()),
+        }
+    }
+
+    unsafe fn prj_get_virtualization_instance_info(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+    ) -> windows::core::Result<PRJ_VIRTUALIZATION_INSTANCE_INFO> {
+        if let Some(code) = *self.fail_instance_info.lock().unwrap() {
+            return Err(code.into());
+        }
+
+        Ok(PRJ_VIRTUALIZATION_INSTANCE_INFO {
+            InstanceID: GUID::zeroed(),
+            WriteAlignment: self.write_alignment.lock().unwrap().unwrap_or(4096),
+        })
+    }
+
+    unsafe fn prj_update_file_if_needed(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        placeholderinfo: *const PRJ_PLACEHOLDER_INFO,
+        placeholderinfosize: u32,
+        _updateflags: PRJ_UPDATE_TYPES,
+        _failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+    ) -> windows::core::Result<()> {
+        if let Some(code) = *self.fail_update.lock().unwrap() {
+            return Err(code.into());
+        }
+
+        let basic_info = (*placeholderinfo).FileBasicInfo;
+        self.updated_files.lock().unwrap().push(WrittenPlaceholder {
+            name: destinationfilename.to_string().unwrap(),
+            is_directory: basic_info.IsDirectory.as_bool(),
+            file_size: basic_info.FileSize,
+            file_attributes: basic_info.FileAttributes,
+            placeholder_info_size: placeholderinfosize,
+        });
+        Ok(())
+    }
+
+    unsafe fn prj_delete_file(
+        &self,
+        _namespacevirtualizationcontext: PRJ_NAMESPACE_VIRTUALIZATION_CONTEXT,
+        destinationfilename: PCWSTR,
+        _updateflags: PRJ_UPDATE_TYPES,
+        _failurereason: Option<*mut PRJ_UPDATE_FAILURE_CAUSES>,
+    ) -> windows::core::Result<()> {
+        if let Some(code) = *self.fail_update.lock().unwrap() {
+            return Err(code.into());
+        }
+
+        self.deleted_files
+            .lock()
+            .unwrap()
+            .push(destinationfilename.to_string().unwrap());
+        Ok(())
+    }
+}
@@ -3,12 +3,17 @@ use std::{
     fs,
     io,
     path::PathBuf,
+    sync::{
+        Arc,
+        Mutex,
+    },
 };
 
 use tempdir::TempDir;
 use windows_projfs::{
     DirectoryEntry,
     DirectoryInfo,
+    DirectoryProducer,
     FileInfo,
     ProjectedFileSystem,
     ProjectedFileSystemSource,
@@ -33,6 +38,26 @@ impl ProjectedFileSystemSource for TestProjectionSource {
     }
 }
 
+struct SharedProjectionSource(Arc<Mutex<TestProjectionSource>>);
+
+impl ProjectedFileSystemSource for SharedProjectionSource {
+    fn list_directory(&self, path: &std::path::Path) -> Vec<DirectoryEntry> {
+        self.0.lock().unwrap().list_directory(path)
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &std::path::Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> std::io::Result<Box<dyn std::io::prelude::Read>> {
+        self.0
+            .lock()
+            .unwrap()
+            .stream_file_content(path, byte_offset, length)
+    }
+}
+
 #[test]
 fn directory_metadata() -> anyhow::Result<()> {
     let _ = env_logger::try_init();
@@ -51,6 +76,8 @@ fn directory_metadata() -> anyhow::Result<()> {
                 creation_time: 133482410013464001,
                 last_access_time: 133482410013464002,
                 last_write_time: 133482410013464003,
+
+                ..Default::default()
             }
             .into(),
             DirectoryInfo {
@@ -60,16 +87,20 @@ fn directory_metadata() -> anyhow::Result<()> {
                 creation_time: 133482410013464001,
                 last_access_time: 133482410013464002,
                 last_write_time: 133482410013464003,
+
+                ..Default::default()
             }
             .into(),
             FileInfo {
                 file_name: "My_File.txt".to_string(),
                 file_attributes: 4456448,
-                file_size: 667,
+                file_size: 667.into(),
 
                 creation_time: 133482410012464001,
                 last_access_time: 133482410012464002,
                 last_write_time: 133482410012464003,
+
+                ..Default::default()
             }
             .into(),
         ],
@@ -79,11 +110,13 @@ fn directory_metadata() -> anyhow::Result<()> {
         vec![FileInfo {
             file_name: "In-A File.txt".to_string(),
             file_attributes: 4456448,
-            file_size: 123,
+            file_size: 123.into(),
 
             creation_time: 133482410012464011,
             last_access_time: 133482410012464012,
             last_write_time: 133482410012464013,
+
+            ..Default::default()
         }
         .into()],
     );
@@ -96,6 +129,8 @@ fn directory_metadata() -> anyhow::Result<()> {
             creation_time: 133482410013464001,
             last_access_time: 133482410013464002,
             last_write_time: 133482410013464003,
+
+            ..Default::default()
         }
         .into()],
     );
@@ -104,11 +139,13 @@ fn directory_metadata() -> anyhow::Result<()> {
         vec![FileInfo {
             file_name: "This_File_in_B_C.txt".to_string(),
             file_attributes: 4456448,
-            file_size: 333,
+            file_size: 333.into(),
 
             creation_time: 133482410012464021,
             last_access_time: 133482410012464022,
             last_write_time: 133482410012464023,
+
+            ..Default::default()
         }
         .into()],
     );
@@ -138,3 +175,252 @@ fn directory_metadata() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+struct ChunkedProjectionSource {
+    entry_count: usize,
+    fail_at: Option<usize>,
+}
+
+impl ProjectedFileSystemSource for ChunkedProjectionSource {
+    fn list_directory(&self, _path: &std::path::Path) -> Vec<DirectoryEntry> {
+        panic!("the full directory listing should not be requested")
+    }
+
+    fn list_directory_chunk(
+        &self,
+        path: &std::path::Path,
+        offset: usize,
+        count: usize,
+    ) -> Option<io::Result<Vec<DirectoryEntry>>> {
+        if !path.as_os_str().is_empty() {
+            return Some(Ok(vec![]));
+        }
+
+        if self.fail_at.is_some_and(|fail_at| offset >= fail_at) {
+            return Some(Err(io::Error::other("backend went away")));
+        }
+
+        let end = (offset + count).min(self.entry_count);
+        Some(Ok((offset..end)
+            .map(|index| DirectoryEntry::file(format!("file-{:06}.txt", index), 0))
+            .collect()))
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &std::path::Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> std::io::Result<Box<dyn std::io::prelude::Read>> {
+        panic!("file contents should not be requested")
+    }
+}
+
+#[test]
+fn directory_chunked() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_directory_chunked")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::builder()
+        .enumeration_chunk_size(100)
+        .build(
+            target_dir,
+            ChunkedProjectionSource {
+                entry_count: 5_555,
+                fail_at: None,
+            },
+        )?;
+
+    let entries = fs::read_dir(target_dir)?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    assert_eq!(entries.len(), 5_555);
+    for (index, name) in entries.iter().enumerate() {
+        assert_eq!(name, &format!("file-{:06}.txt", index));
+    }
+    Ok(())
+}
+
+#[test]
+fn directory_chunked_aborted() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_directory_chunked_aborted")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::builder()
+        .enumeration_chunk_size(100)
+        .build(
+            target_dir,
+            ChunkedProjectionSource {
+                entry_count: 5_555,
+                fail_at: Some(200),
+            },
+        )?;
+
+    let result = fs::read_dir(target_dir)?
+        .map(|entry| Ok(entry?.file_name()))
+        .collect::<io::Result<Vec<_>>>();
+
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn directory_missing() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_directory_missing")?;
+    let target_dir = target_dir.path();
+
+    let source = Arc::new(Mutex::new(TestProjectionSource::default()));
+    source.lock().unwrap().entries.insert(
+        PathBuf::from(""),
+        vec![
+            DirectoryEntry::directory("empty"),
+            DirectoryEntry::directory("removed"),
+        ],
+    );
+
+    let _pfs = ProjectedFileSystem::builder()
+        .report_missing_directories(true)
+        .build(target_dir, SharedProjectionSource(source.clone()))?;
+
+    /* materialize both directories before removing one from the source */
+    assert!(fs::metadata(target_dir.join("empty"))?.is_dir());
+    assert!(fs::metadata(target_dir.join("removed"))?.is_dir());
+    source
+        .lock()
+        .unwrap()
+        .entries
+        .insert(PathBuf::from(""), vec![DirectoryEntry::directory("empty")]);
+
+    assert_eq!(fs::read_dir(target_dir.join("empty"))?.count(), 0);
+
+    let error = fs::read_dir(target_dir.join("removed"))
+        .and_then(|entries| entries.collect::<io::Result<Vec<_>>>())
+        .expect_err("the directory to be missing");
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    Ok(())
+}
+
+#[test]
+fn directory_concurrent_enumerations() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_directory_concurrent_enumerations")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::builder()
+        .enumeration_chunk_size(10)
+        .build(
+            target_dir,
+            ChunkedProjectionSource {
+                entry_count: 250,
+                fail_at: None,
+            },
+        )?;
+
+    std::thread::scope(|scope| {
+        let workers = (0..16)
+            .map(|_| {
+                scope.spawn(|| -> io::Result<()> {
+                    for _ in 0..50 {
+                        let entry_count = fs::read_dir(target_dir)?.count();
+                        assert_eq!(entry_count, 250);
+                    }
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for worker in workers {
+            worker.join().expect("worker to not panic")?;
+        }
+
+        Ok(())
+    })
+}
+
+struct CountingProducer {
+    entry_count: usize,
+    current_entry: usize,
+}
+
+impl DirectoryProducer for CountingProducer {
+    fn next_entry(&mut self) -> Option<io::Result<DirectoryEntry>> {
+        if self.current_entry >= self.entry_count {
+            return None;
+        }
+
+        let entry = DirectoryEntry::file(format!("file-{:06}.txt", self.current_entry), 0);
+        self.current_entry += 1;
+        Some(Ok(entry))
+    }
+
+    fn reset(&mut self) {
+        self.current_entry = 0;
+    }
+}
+
+struct ProducerProjectionSource {
+    entry_count: usize,
+}
+
+impl ProjectedFileSystemSource for ProducerProjectionSource {
+    fn list_directory(&self, _path: &std::path::Path) -> Vec<DirectoryEntry> {
+        panic!("the full directory listing should not be requested")
+    }
+
+    fn directory_producer(
+        &self,
+        path: &std::path::Path,
+    ) -> Option<io::Result<Box<dyn DirectoryProducer>>> {
+        let entry_count = if path.as_os_str().is_empty() {
+            self.entry_count
+        } else {
+            0
+        };
+
+        Some(Ok(Box::new(CountingProducer {
+            entry_count,
+            current_entry: 0,
+        })))
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &std::path::Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> std::io::Result<Box<dyn std::io::prelude::Read>> {
+        panic!("file contents should not be requested")
+    }
+}
+
+#[test]
+fn directory_producer() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_directory_producer")?;
+    let target_dir = target_dir.path();
+
+    let _pfs =
+        ProjectedFileSystem::new(target_dir, ProducerProjectionSource { entry_count: 3_333 })?;
+
+    /* enumerate twice to ensure a new producer is requested for every enumeration */
+    for _ in 0..2 {
+        let entries = fs::read_dir(target_dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        assert_eq!(entries.len(), 3_333);
+        for (index, name) in entries.iter().enumerate() {
+            assert_eq!(name, &format!("file-{:06}.txt", index));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        UNIX_EPOCH,
+    },
+};
+
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    DirectoryInfo,
+    FileAttributes,
+    FileInfo,
+    FileSize,
+    ProjectedFile,
+};
+
+#[test]
+fn entry_times() {
+    let DirectoryEntry::File(file) = DirectoryEntry::file("test.txt", 0).with_times(
+        0,
+        116_444_736_000_000_000,
+        133_482_410_012_464_003,
+    ) else {
+        unreachable!()
+    };
+
+    assert_eq!(file.created(), None);
+    assert_eq!(file.accessed(), Some(UNIX_EPOCH));
+    assert_eq!(
+        file.modified(),
+        Some(UNIX_EPOCH + Duration::new(1_703_767_401, 246_400_300))
+    );
+}
+
+#[test]
+fn entry_attributes() {
+    let DirectoryEntry::Directory(directory) =
+        DirectoryEntry::directory("test").with_attributes(0x12)
+    else {
+        unreachable!()
+    };
+
+    let attributes = directory.attributes();
+    assert!(attributes.contains(FileAttributes::DIRECTORY));
+    assert!(attributes.contains(FileAttributes::HIDDEN));
+    assert!(!attributes.contains(FileAttributes::READONLY));
+    assert_eq!(
+        attributes,
+        FileAttributes::DIRECTORY | FileAttributes::HIDDEN
+    );
+}
+
+#[test]
+fn entry_attribute_flags() {
+    let file = FileInfo {
+        file_name: "test.txt".to_string(),
+        file_attributes: FileAttributes::ARCHIVE.bits(),
+        ..Default::default()
+    }
+    .hidden(true)
+    .readonly(true)
+    .system(true);
+    assert_eq!(file.file_attributes, 0x27);
+
+    let file = file.readonly(false).system(false);
+    assert_eq!(
+        file.attributes(),
+        FileAttributes::ARCHIVE | FileAttributes::HIDDEN
+    );
+
+    let directory = DirectoryInfo {
+        directory_name: "test".to_string(),
+        directory_attributes: FileAttributes::DIRECTORY.bits(),
+        ..Default::default()
+    }
+    .hidden(true)
+    .hidden(true);
+    assert_eq!(directory.directory_attributes, 0x12);
+}
+
+#[test]
+fn entries_from_read_dir() -> anyhow::Result<()> {
+    let target_dir = TempDir::new("test_entries_from_read_dir")?;
+    let target_dir = target_dir.path();
+
+    fs::write(target_dir.join("file.txt"), "Hello World!")?;
+    fs::create_dir(target_dir.join("directory"))?;
+
+    let mut entries = DirectoryEntry::from_read_dir(target_dir, false)?;
+    entries.sort();
+
+    assert_eq!(entries.len(), 2);
+    assert!(
+        matches!(&entries[0], DirectoryEntry::Directory(dir) if dir.directory_name == "directory")
+    );
+    assert!(
+        matches!(&entries[1], DirectoryEntry::File(file) if file.file_name == "file.txt" && file.file_size == 12.into())
+    );
+    Ok(())
+}
+
+#[test]
+fn entries_from_metadata() -> anyhow::Result<()> {
+    let target_dir = TempDir::new("test_entries_from_metadata")?;
+    let target_dir = target_dir.path();
+
+    fs::write(target_dir.join("file.txt"), "Hello World!")?;
+    let metadata = fs::symlink_metadata(target_dir.join("file.txt"))?;
+    let file = FileInfo::from_metadata("file.txt", &metadata);
+    assert_eq!(file.file_name, "file.txt");
+    assert_eq!(file.file_size, 12.into());
+    assert!(file.attributes().contains(FileAttributes::ARCHIVE));
+    assert_eq!(file.modified(), Some(metadata.modified()?));
+
+    let metadata = fs::symlink_metadata(target_dir)?;
+    let directory = DirectoryInfo::from_metadata("directory", &metadata);
+    assert_eq!(directory.directory_name, "directory");
+    assert!(directory.attributes().contains(FileAttributes::DIRECTORY));
+    assert_eq!(directory.created(), Some(metadata.created()?));
+    Ok(())
+}
+
+#[test]
+fn entry_accessors() {
+    let file = DirectoryEntry::file("test.txt", 12).with_attributes(FileAttributes::HIDDEN.bits());
+    assert!(file.is_file());
+    assert!(!file.is_dir());
+    assert_eq!(file.size(), Some(12));
+    assert_eq!(file.attributes(), FileAttributes::HIDDEN);
+
+    let file = DirectoryEntry::file("unknown.txt", FileSize::Unknown);
+    assert_eq!(file.size(), None);
+
+    let directory =
+        DirectoryEntry::directory("test").with_attributes(FileAttributes::DIRECTORY.bits());
+    assert!(directory.is_dir());
+    assert!(!directory.is_file());
+    assert_eq!(directory.size(), None);
+    assert_eq!(directory.attributes(), FileAttributes::DIRECTORY);
+}
+
+#[test]
+fn entry_matches_ignoring_times() {
+    let file = DirectoryEntry::file("test.txt", 12).with_attributes(FileAttributes::HIDDEN.bits());
+    let file_with_times = file.clone().with_times(1, 2, 3);
+    assert_ne!(file, file_with_times);
+    assert!(file.matches_ignoring_times(&file_with_times));
+
+    assert!(!file.matches_ignoring_times(&DirectoryEntry::file("test.txt", 13)));
+    assert!(!file.matches_ignoring_times(&DirectoryEntry::file("other.txt", 12)));
+    assert!(!file.matches_ignoring_times(&file.clone().with_attributes(0)));
+
+    let directory = DirectoryEntry::directory("test.txt");
+    assert!(directory.matches_ignoring_times(&directory.clone().with_times(1, 2, 3)));
+    assert!(!directory.matches_ignoring_times(&DirectoryEntry::file("test.txt", 0)));
+    assert!(!DirectoryEntry::file("test.txt", 0).matches_ignoring_times(&directory));
+}
+
+#[test]
+fn entry_sort_key() {
+    let file = DirectoryEntry::file("test.txt", 12);
+    assert_eq!(file.sort_key(), None);
+
+    let file_with_key = file.clone().with_sort_key(7);
+    assert_eq!(file_with_key.sort_key(), Some(7));
+    assert!(!file.matches_ignoring_times(&file_with_key));
+
+    let DirectoryEntry::Directory(directory) = DirectoryEntry::directory("test").with_sort_key(3)
+    else {
+        unreachable!()
+    };
+    assert_eq!(directory.sort_key, Some(3));
+}
+
+#[test]
+fn entry_metadata_view() -> anyhow::Result<()> {
+    use std::os::windows::fs::MetadataExt;
+
+    let target_dir = TempDir::new("test_entry_metadata_view")?;
+    let target_dir = target_dir.path();
+
+    fs::write(target_dir.join("file.txt"), "Hello World!")?;
+    let metadata = fs::metadata(target_dir.join("file.txt"))?;
+    let view =
+        DirectoryEntry::from(FileInfo::from_metadata("file.txt", &metadata)).to_metadata_view();
+    assert!(view.is_file());
+    assert_eq!(view.len(), metadata.len());
+    assert_eq!(view.file_attributes(), metadata.file_attributes());
+    assert_eq!(view.created(), Some(metadata.created()?));
+    assert_eq!(view.accessed(), Some(metadata.accessed()?));
+    assert_eq!(view.modified(), Some(metadata.modified()?));
+
+    let metadata = fs::metadata(target_dir)?;
+    let view = DirectoryEntry::from(DirectoryInfo::from_metadata("directory", &metadata))
+        .to_metadata_view();
+    assert!(view.is_dir());
+    assert_eq!(view.len(), 0);
+    assert_eq!(view.file_attributes(), metadata.file_attributes());
+    assert_eq!(view.modified(), Some(metadata.modified()?));
+
+    let view = DirectoryEntry::directory("empty").to_metadata_view();
+    assert_eq!(view.file_attributes(), FileAttributes::DIRECTORY.bits());
+    assert_eq!(view.created(), None);
+
+    let view = DirectoryEntry::file("unknown.txt", FileSize::Unknown).to_metadata_view();
+    assert_eq!(view.len(), 0);
+    Ok(())
+}
+
+#[test]
+fn projected_file_absolute() {
+    let file = ProjectedFile {
+        path: PathBuf::from("dir\\file.txt"),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        file.absolute(Path::new("C:\\projection")),
+        PathBuf::from("C:\\projection\\dir\\file.txt")
+    );
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    io::{
+        self,
+        Read,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+    },
+    thread,
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows_projfs::{
+    resolve_path,
+    DirectoryEntry,
+    Filter,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+#[derive(Default, Clone)]
+struct CountingProjectionSource {
+    listings: Arc<AtomicUsize>,
+}
+
+impl ProjectedFileSystemSource for CountingProjectionSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        self.listings.fetch_add(1, Ordering::Relaxed);
+        vec![
+            DirectoryEntry::file("a.txt", 4),
+            DirectoryEntry::directory("b"),
+        ]
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Err(io::ErrorKind::NotFound.into())
+    }
+}
+
+#[test]
+fn entry_at_outside_of_request() {
+    let source = CountingProjectionSource::default();
+    assert!(source
+        .entry_at(Path::new("a.txt"))
+        .is_some_and(|entry| entry.is_file()));
+    assert!(source
+        .entry_at(Path::new("b"))
+        .is_some_and(|entry| entry.is_dir()));
+    assert!(source.entry_at(Path::new("c")).is_none());
+    assert!(source.entry_at(Path::new("")).is_none());
+
+    /* nothing is cached without a request */
+    assert_eq!(source.listings.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn entry_at_cached_within_request() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let source = CountingProjectionSource::default();
+    let listings = source.listings.clone();
+
+    /* the filter looks up the entry for the placeholder and again for the content id */
+    let source = Filter::new(source, |_: &DirectoryEntry| true);
+
+    let target_dir = TempDir::new("test_entry_at_cached_within_request")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    assert!(library.placeholder_info("a.txt").is_ok());
+    assert_eq!(listings.load(Ordering::Relaxed), 1);
+
+    /* every request lists the directory again */
+    assert!(library.placeholder_info("a.txt").is_ok());
+    assert_eq!(listings.load(Ordering::Relaxed), 2);
+    Ok(())
+}
+
+const DEEP_TREE_DEPTH: usize = 1000;
+
+#[derive(Default)]
+struct DeepNode {
+    entries: Vec<DirectoryEntry>,
+    children: BTreeMap<String, usize>,
+}
+
+/// A chain of nested directories stored within an arena.
+struct DeepProjectionSource {
+    nodes: Vec<DeepNode>,
+}
+
+impl DeepProjectionSource {
+    fn new(depth: usize) -> (Self, PathBuf) {
+        let mut nodes = Vec::with_capacity(depth + 1);
+        let mut path = PathBuf::new();
+        for index in 0..depth {
+            let name = format!("d{}", index);
+            nodes.push(DeepNode {
+                entries: vec![DirectoryEntry::directory(&name)],
+                children: BTreeMap::from([(name.clone(), index + 1)]),
+            });
+            path.push(name);
+        }
+
+        nodes.push(DeepNode {
+            entries: vec![DirectoryEntry::file("leaf.txt", 4)],
+            ..Default::default()
+        });
+        (Self { nodes }, path)
+    }
+}
+
+impl ProjectedFileSystemSource for DeepProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        resolve_path(&self.nodes[0], path, |node, name| {
+            node.children.get(name).map(|index| &self.nodes[*index])
+        })
+        .map(|node| node.entries.clone())
+        .unwrap_or_default()
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Err(io::ErrorKind::NotFound.into())
+    }
+}
+
+#[test]
+fn entry_at_deep_path() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let (source, deep_path) = DeepProjectionSource::new(DEEP_TREE_DEPTH);
+
+    /* the lookup must not grow the stack with the depth of the path */
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn_scoped(scope, || {
+                assert!(source
+                    .entry_at(&deep_path.join("leaf.txt"))
+                    .is_some_and(|entry| entry.is_file()));
+                assert!(source
+                    .get_directory_entry(&deep_path)
+                    .is_some_and(|entry| entry.is_dir()));
+                assert!(source.entry_at(&deep_path.join("missing.txt")).is_none());
+                assert!(source.list_directory(&deep_path.join("..")).is_empty());
+            })?
+            .join()
+            .expect("lookup thread should not panic");
+
+        io::Result::Ok(())
+    })?;
+
+    let target_dir = TempDir::new("test_entry_at_deep_path")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    let entries = library
+        .enumerate(&deep_path.to_string_lossy(), None, 100)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name, "leaf.txt");
+    Ok(())
+}
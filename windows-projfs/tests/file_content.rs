@@ -3,30 +3,39 @@ use std::{
         BTreeMap,
         VecDeque,
     },
+    ffi::OsString,
     fs,
     io::{
         self,
         Cursor,
         ErrorKind,
     },
-    path::PathBuf,
+    os::windows::ffi::OsStringExt,
+    path::{
+        Path,
+        PathBuf,
+    },
 };
 
 use tempdir::TempDir;
 use windows_projfs::{
-    DirectoryInfo,
-    FileInfo,
+    AsyncSource,
+    DirectoryEntry,
+    Error,
+    FileSize,
     ProjectedFileSystem,
     ProjectedFileSystemSource,
+    SourceFuture,
 };
 
 #[derive(Debug, Default)]
 struct TestProjectionSource {
     content: BTreeMap<PathBuf, Vec<u8>>,
+    unknown_sizes: bool,
 }
 
 impl ProjectedFileSystemSource for TestProjectionSource {
-    fn list_directory(&self, target: &std::path::Path) -> Vec<windows_projfs::DirectoryEntry> {
+    fn list_directory(&self, target: &std::path::Path) -> Vec<DirectoryEntry> {
         self.content
             .iter()
             .filter(|(path, _)| path.starts_with(target))
@@ -38,20 +47,15 @@ impl ProjectedFileSystemSource for TestProjectionSource {
                     .collect::<VecDeque<_>>();
 
                 if components.len() == 1 {
-                    FileInfo {
-                        file_name: components.pop_front().unwrap(),
-                        file_size: value.len() as u64,
+                    let file_size = if self.unknown_sizes {
+                        FileSize::Unknown
+                    } else {
+                        (value.len() as u64).into()
+                    };
 
-                        ..Default::default()
-                    }
-                    .into()
+                    DirectoryEntry::file(components.pop_front().unwrap(), file_size)
                 } else {
-                    DirectoryInfo {
-                        directory_name: components.pop_front().unwrap(),
-
-                        ..Default::default()
-                    }
-                    .into()
+                    DirectoryEntry::directory(components.pop_front().unwrap())
                 }
             })
             .collect()
@@ -73,6 +77,11 @@ impl ProjectedFileSystemSource for TestProjectionSource {
             }
         };
 
+        if length == usize::MAX {
+            /* the whole content has been requested */
+            return Ok(Box::new(Cursor::new(content[byte_offset..].to_owned())));
+        }
+
         if byte_offset + length > content.len() {
             return Err(io::Error::new(
                 io::ErrorKind::UnexpectedEof,
@@ -114,6 +123,121 @@ fn small_file_content() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn unknown_file_size() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_unknown_file_size")?;
+    let target_dir = target_dir.path();
+
+    let mut pfs_source = TestProjectionSource {
+        unknown_sizes: true,
+        ..Default::default()
+    };
+    pfs_source.content.insert(
+        PathBuf::from("generated.txt"),
+        "This content has been generated on the fly."
+            .as_bytes()
+            .to_vec(),
+    );
+    pfs_source.content.insert(
+        PathBuf::from("sub-dir/generated.txt"),
+        "Some more generated content".as_bytes().to_vec(),
+    );
+
+    let content = pfs_source.content.clone();
+    let _pfs = ProjectedFileSystem::new(target_dir, pfs_source)?;
+
+    for (path, expected_content) in content {
+        let path = target_dir.join(path);
+        assert_eq!(fs::metadata(&path)?.len(), expected_content.len() as u64);
+        assert_eq!(fs::read(&path)?, expected_content);
+    }
+    Ok(())
+}
+
+#[test]
+fn distinct_provider_ids() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_distinct_provider_ids")?;
+    let root_a = target_dir.path().join("provider-a");
+    let root_b = target_dir.path().join("provider-b");
+
+    let mut source_a = TestProjectionSource::default();
+    source_a.content.insert(
+        PathBuf::from("shared.txt"),
+        "Content of provider A".as_bytes().to_vec(),
+    );
+
+    let mut source_b = TestProjectionSource::default();
+    source_b.content.insert(
+        PathBuf::from("shared.txt"),
+        "Provider B has some other content".as_bytes().to_vec(),
+    );
+
+    let _pfs_a = ProjectedFileSystem::builder()
+        .provider_id([0xA; 16])
+        .build(&root_a, source_a)?;
+    let _pfs_b = ProjectedFileSystem::builder()
+        .provider_id([0xB; 16])
+        .build(&root_b, source_b)?;
+
+    assert_eq!(
+        fs::read_to_string(root_a.join("shared.txt"))?,
+        "Content of provider A"
+    );
+    assert_eq!(
+        fs::read_to_string(root_b.join("shared.txt"))?,
+        "Provider B has some other content"
+    );
+    Ok(())
+}
+
+#[test]
+fn hydrate_tree() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_hydrate_tree")?;
+    let target_dir = target_dir.path();
+
+    let mut pfs_source = TestProjectionSource::default();
+    pfs_source
+        .content
+        .insert(PathBuf::from("a.txt"), "Hello World!".as_bytes().to_vec());
+    pfs_source.content.insert(
+        PathBuf::from("sub-dir/b.txt"),
+        "Hello sub directory!".as_bytes().to_vec(),
+    );
+
+    let pfs = ProjectedFileSystem::new(target_dir, pfs_source)?;
+
+    let stats = pfs.hydrate(Path::new(""), false)?;
+    assert_eq!(stats.files, 1);
+    assert_eq!(stats.directories, 1);
+    assert_eq!(stats.bytes, 12);
+
+    let stats = pfs.hydrate(Path::new(""), true)?;
+    assert_eq!(stats.files, 2);
+    assert_eq!(stats.directories, 2);
+    assert_eq!(stats.bytes, 32);
+
+    /* paths must not leave the projection root */
+    for path in [
+        "..",
+        "sub-dir\\..\\..\\a.txt",
+        "C:\\Windows",
+        "\\Windows",
+        "C:a.txt",
+    ] {
+        assert!(matches!(
+            pfs.hydrate(Path::new(path), false),
+            Err(Error::InvalidRelativePath(_))
+        ));
+    }
+    Ok(())
+}
+
 #[test]
 fn file_not_found() -> anyhow::Result<()> {
     let _ = env_logger::try_init();
@@ -186,3 +310,139 @@ fn file_content_large() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+struct AsyncTestSource {
+    content: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl AsyncSource for AsyncTestSource {
+    fn read_file_content(
+        &self,
+        path: PathBuf,
+        byte_offset: usize,
+        length: usize,
+    ) -> SourceFuture<Vec<u8>> {
+        let content = self
+            .content
+            .get(&path)
+            .and_then(|content| content.get(byte_offset..byte_offset + length))
+            .map(<[u8]>::to_vec);
+
+        Box::pin(async move {
+            content.ok_or_else(|| io::Error::new(ErrorKind::NotFound, "target file not found"))
+        })
+    }
+}
+
+#[test]
+fn async_file_content() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_async_file_content")?;
+    let target_dir = target_dir.path();
+
+    let mut pfs_source = TestProjectionSource::default();
+    pfs_source.content.insert(
+        PathBuf::from("async.txt"),
+        "This content has been read asynchronously."
+            .as_bytes()
+            .to_vec(),
+    );
+    pfs_source.content.insert(
+        PathBuf::from("sub-dir/large.bin"),
+        vec![0x42; 3 * 1024 * 1024],
+    );
+
+    let async_source = AsyncTestSource {
+        content: pfs_source.content.clone(),
+    };
+
+    let content = pfs_source.content.clone();
+    let _pfs = ProjectedFileSystem::builder()
+        .async_source(async_source)
+        .build(target_dir, pfs_source)?;
+
+    for (path, expected_content) in content {
+        assert_eq!(fs::read(target_dir.join(path))?, expected_content);
+    }
+    Ok(())
+}
+
+#[test]
+fn unicode_file_names() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_unicode_file_names")?;
+    let target_dir = target_dir.path();
+
+    let mut pfs_source = TestProjectionSource::default();
+    pfs_source.content.insert(
+        PathBuf::from("Ünïcødé\\日本語.txt"),
+        "Non-ASCII names should round-trip".as_bytes().to_vec(),
+    );
+    pfs_source.content.insert(
+        PathBuf::from("Ünïcødé\\nested\\😀 emoji.txt"),
+        "Surrogate pairs too".as_bytes().to_vec(),
+    );
+
+    let content = pfs_source.content.clone();
+    let _pfs = ProjectedFileSystem::new(target_dir, pfs_source)?;
+
+    for (path, expected_content) in content {
+        let path = target_dir.join(path);
+        assert_eq!(fs::metadata(&path)?.len(), expected_content.len() as u64);
+        assert_eq!(fs::read(&path)?, expected_content);
+    }
+
+    let names = fs::read_dir(target_dir.join("Ünïcødé"))?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+        .collect::<io::Result<Vec<_>>>()?;
+    assert_eq!(names, vec!["nested", "日本語.txt"]);
+    Ok(())
+}
+
+#[test]
+fn unicode_root() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_unicode_root")?;
+
+    /* "root-😀-" followed by an unpaired surrogate which can not be represented as String */
+    let mut root_name = "root-😀-".encode_utf16().collect::<Vec<_>>();
+    root_name.push(0xD800);
+    let root = target_dir.path().join(OsString::from_wide(&root_name));
+
+    let mut pfs_source = TestProjectionSource::default();
+    pfs_source.content.insert(
+        PathBuf::from("😀 emoji.txt"),
+        "Hello from a surrogate pair".as_bytes().to_vec(),
+    );
+
+    let content = pfs_source.content.clone();
+    let _pfs = ProjectedFileSystem::new(&root, pfs_source)?;
+
+    for (path, expected_content) in content {
+        assert_eq!(fs::read(root.join(path))?, expected_content);
+    }
+    Ok(())
+}
+
+#[test]
+fn empty_file_content() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_empty_file_content")?;
+    let target_dir = target_dir.path();
+
+    let mut pfs_source = TestProjectionSource::default();
+    pfs_source
+        .content
+        .insert(PathBuf::from("empty.txt"), Vec::new());
+
+    let _pfs = ProjectedFileSystem::new(target_dir, pfs_source)?;
+
+    let path = target_dir.join("empty.txt");
+    assert_eq!(fs::metadata(&path)?.len(), 0);
+    assert!(fs::read(&path)?.is_empty());
+    Ok(())
+}
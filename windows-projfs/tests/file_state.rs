@@ -0,0 +1,71 @@
+use std::{
+    fs,
+    io::{
+        Cursor,
+        Read,
+    },
+    path::Path,
+};
+
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    OnDiskFileState,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+const FILE_CONTENT: &str = "Hello World!";
+
+struct TestProjectionSource;
+
+impl ProjectedFileSystemSource for TestProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            vec![
+                DirectoryEntry::file("placeholder.txt", FILE_CONTENT.len() as u64),
+                DirectoryEntry::file("hydrated.txt", FILE_CONTENT.len() as u64),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> std::io::Result<Box<dyn Read>> {
+        Ok(Box::new(Cursor::new(
+            FILE_CONTENT.as_bytes()[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+#[test]
+fn on_disk_state() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_on_disk_state")?;
+    let target_dir = target_dir.path();
+    let pfs = ProjectedFileSystem::new(target_dir, TestProjectionSource)?;
+
+    fs::metadata(target_dir.join("placeholder.txt"))?;
+    assert_eq!(
+        pfs.on_disk_state("placeholder.txt")?,
+        OnDiskFileState::PLACEHOLDER
+    );
+
+    assert_eq!(
+        fs::read_to_string(target_dir.join("hydrated.txt"))?,
+        FILE_CONTENT
+    );
+    assert!(pfs
+        .on_disk_state("hydrated.txt")?
+        .contains(OnDiskFileState::HYDRATED_PLACEHOLDER));
+
+    fs::write(target_dir.join("full.txt"), FILE_CONTENT)?;
+    assert_eq!(pfs.on_disk_state("full.txt")?, OnDiskFileState::FULL);
+    Ok(())
+}
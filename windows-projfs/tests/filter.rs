@@ -0,0 +1,70 @@
+mod common;
+
+use std::{
+    io::Cursor,
+    path::Path,
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    Filter,
+    FnSource,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+fn test_source() -> FnSource {
+    FnSource::new()
+        .on_list(|path| {
+            if path.as_os_str().is_empty() {
+                vec![
+                    DirectoryEntry::file("visible.txt", 5),
+                    DirectoryEntry::file(".secret", 5),
+                ]
+            } else {
+                vec![]
+            }
+        })
+        .on_stream(|_path, byte_offset, length| {
+            Ok(Box::new(Cursor::new(
+                b"12345"[byte_offset..byte_offset + length].to_vec(),
+            )))
+        })
+}
+
+#[test]
+fn filter_hidden_entries() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let source = Filter::new(test_source(), |entry: &DirectoryEntry| {
+        !entry.name().starts_with('.')
+    });
+    assert!(source.get_directory_entry(Path::new(".secret")).is_none());
+    assert!(source
+        .get_directory_entry(Path::new("visible.txt"))
+        .is_some());
+
+    let target_dir = TempDir::new("test_filter_hidden_entries")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    let names = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["visible.txt"]);
+
+    assert!(library.placeholder_info(".secret").is_err());
+    assert!(library.read_file(".secret", 0, 5).is_err());
+    assert_eq!(
+        library
+            .read_file("visible.txt", 0, 5)
+            .map_err(windows::core::Error::from)?,
+        b"12345"
+    );
+    Ok(())
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::{
+    io::Cursor,
+    path::Path,
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    FnSource,
+    ProjectedFileSystem,
+};
+
+#[test]
+fn fn_source() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_fn_source")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new()
+        .on_list(|path| {
+            if path.as_os_str().is_empty() {
+                vec![DirectoryEntry::file("test.txt", 12)]
+            } else {
+                vec![]
+            }
+        })
+        .on_stream(|path, byte_offset, length| {
+            assert_eq!(path, Path::new("test.txt"));
+            let content = &b"Hello World!"[byte_offset..byte_offset + length];
+            Ok(Box::new(Cursor::new(content.to_vec())))
+        });
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    let names = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["test.txt"]);
+
+    let content = library
+        .read_file("test.txt", 6, 6)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, b"World!");
+    Ok(())
+}
+
+#[test]
+fn fn_source_defaults() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_fn_source_defaults")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), FnSource::new(), library.clone())?;
+
+    let entries = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?;
+    assert!(entries.is_empty());
+    assert!(library.read_file("test.txt", 0, 1).is_err());
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use windows_projfs::{
+    is_projfs_available,
+    load_library,
+    Error,
+};
+
+#[test]
+fn projfs_availability() {
+    match load_library() {
+        Ok(_) => assert!(is_projfs_available()),
+        Err(Error::WindowsFeatureNotEnabled) => assert!(!is_projfs_available()),
+        Err(error) => panic!("unexpected error: {}", error),
+    }
+}
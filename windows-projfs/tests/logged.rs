@@ -0,0 +1,67 @@
+mod common;
+
+use std::{
+    io::Cursor,
+    ops::ControlFlow,
+    path::Path,
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows::Win32::Storage::ProjectedFileSystem::PRJ_NOTIFICATION_PRE_DELETE;
+use windows_projfs::{
+    DirectoryEntry,
+    FnSource,
+    Logged,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+fn test_source() -> FnSource {
+    FnSource::new()
+        .on_list(|path| {
+            if path.as_os_str().is_empty() {
+                vec![DirectoryEntry::file("a.txt", 5)]
+            } else {
+                vec![]
+            }
+        })
+        .on_stream(|_path, byte_offset, length| {
+            Ok(Box::new(Cursor::new(
+                b"12345"[byte_offset..byte_offset + length].to_vec(),
+            )))
+        })
+        .on_notification(|_| ControlFlow::Break(().into()))
+}
+
+#[test]
+fn logged_forwards_calls() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let source = Logged::new(test_source());
+    assert!(source.get_directory_entry(Path::new("a.txt")).is_some());
+    assert!(source.get_directory_entry(Path::new("b.txt")).is_none());
+
+    let target_dir = TempDir::new("test_logged_forwards_calls")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    let names = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["a.txt"]);
+
+    assert_eq!(
+        library
+            .read_file("a.txt", 1, 3)
+            .map_err(windows::core::Error::from)?,
+        b"234"
+    );
+    assert!(library
+        .notify("a.txt", false, PRJ_NOTIFICATION_PRE_DELETE)
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,2023 @@
+mod common;
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs,
+    io::{
+        self,
+        Cursor,
+        Read,
+    },
+    mem,
+    ops::{
+        ControlFlow,
+        Range,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        atomic,
+        Arc,
+        Mutex,
+    },
+    thread,
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows::Win32::{
+    Foundation::{
+        ERROR_DIRECTORY_NOT_SUPPORTED,
+        ERROR_FILE_NOT_FOUND,
+        ERROR_FILE_OFFLINE,
+        ERROR_FILE_SYSTEM_VIRTUALIZATION_INVALID_OPERATION,
+        ERROR_FILE_SYSTEM_VIRTUALIZATION_UNAVAILABLE,
+        ERROR_HANDLE_EOF,
+        ERROR_IO_PENDING,
+        ERROR_OPERATION_ABORTED,
+        ERROR_PATH_NOT_FOUND,
+        E_UNEXPECTED,
+        STATUS_CANNOT_DELETE,
+        STATUS_SHARING_VIOLATION,
+        S_OK,
+    },
+    Storage::ProjectedFileSystem::{
+        PRJ_FLAG_NONE,
+        PRJ_FLAG_USE_NEGATIVE_PATH_CACHE,
+        PRJ_NOTIFICATION_FILE_OVERWRITTEN,
+        PRJ_NOTIFICATION_FILE_RENAMED,
+        PRJ_NOTIFICATION_PRE_DELETE,
+        PRJ_NOTIFICATION_PRE_RENAME,
+        PRJ_PLACEHOLDER_INFO,
+    },
+};
+use windows_projfs::{
+    AsyncSource,
+    CancellationToken,
+    DirectoryEntry,
+    EnumerationStats,
+    Error,
+    FileAttributes,
+    FileChunks,
+    FileInfo,
+    FileSize,
+    FnSource,
+    Hydration,
+    Notification,
+    NotificationVeto,
+    OnDiskFileState,
+    ProjectedFileSystem,
+    ProjectedFileSystemBuilder,
+    ProjectedFileSystemSource,
+    RangeNotAvailable,
+    SortStrategy,
+    SourceError,
+    SourceFuture,
+};
+
+struct TestProjectionSource {
+    entries: Vec<DirectoryEntry>,
+}
+
+impl TestProjectionSource {
+    fn content(path: &Path) -> Vec<u8> {
+        let seed = path.as_os_str().len() as u8;
+        (0..3 * 1024 * 1024 + 17)
+            .map(|index: usize| seed.wrapping_add(index as u8))
+            .collect()
+    }
+}
+
+impl ProjectedFileSystemSource for TestProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            self.entries.clone()
+        } else {
+            vec![]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        let content = Self::content(path);
+        Ok(Box::new(Cursor::new(
+            content[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+fn test_source() -> TestProjectionSource {
+    TestProjectionSource {
+        entries: vec![
+            DirectoryEntry::file("b.txt", 2),
+            DirectoryEntry::directory("c"),
+            DirectoryEntry::file("A.txt", 1),
+            DirectoryEntry::file("large.bin", 3 * 1024 * 1024 + 17),
+            DirectoryEntry::file("empty.txt", 0),
+        ],
+    }
+}
+
+#[test]
+fn mock_enumeration() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_enumeration")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    /* a buffer capacity of one forces resuming the enumeration for every entry */
+    for buffer_capacity in [1, 2, 100] {
+        let entries = library
+            .enumerate("", None, buffer_capacity)
+            .map_err(windows::core::Error::from)?;
+
+        let names = entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["A.txt", "b.txt", "c", "empty.txt", "large.bin"]);
+        assert!(entries[2].is_directory);
+        assert_eq!(entries[1].file_size, 2);
+    }
+    Ok(())
+}
+
+#[test]
+fn mock_placeholder_info() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_placeholder_info")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    library.placeholder_info("b.txt").ok()?;
+    assert_eq!(
+        library.placeholder_info("missing.txt"),
+        ERROR_FILE_NOT_FOUND.to_hresult()
+    );
+
+    let placeholders = library.placeholders.lock().unwrap();
+    assert_eq!(placeholders.len(), 1);
+    assert_eq!(placeholders[0].name, "b.txt");
+    assert_eq!(placeholders[0].file_size, 2);
+    assert_eq!(
+        placeholders[0].placeholder_info_size as usize,
+        mem::size_of::<PRJ_PLACEHOLDER_INFO>()
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_placeholder_info_missing_parent() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_placeholder_info_missing_parent")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    /* the parent directory exists */
+    assert_eq!(
+        library.placeholder_info("c\\missing.txt"),
+        ERROR_FILE_NOT_FOUND.to_hresult()
+    );
+
+    /* the parent directory is missing or not a directory */
+    assert_eq!(
+        library.placeholder_info("missing\\c.txt"),
+        ERROR_PATH_NOT_FOUND.to_hresult()
+    );
+    assert_eq!(
+        library.placeholder_info("c\\missing\\c.txt"),
+        ERROR_PATH_NOT_FOUND.to_hresult()
+    );
+    assert_eq!(
+        library.placeholder_info("b.txt\\c.txt"),
+        ERROR_PATH_NOT_FOUND.to_hresult()
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_file_data() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_file_data")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    let expected = TestProjectionSource::content(Path::new("large.bin"));
+    let content = library
+        .read_file("large.bin", 0, expected.len() as u32)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, expected);
+
+    let content = library
+        .read_file("large.bin", 1024, 4096)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, expected[1024..1024 + 4096]);
+    Ok(())
+}
+
+#[test]
+fn mock_file_data_write_alignment() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_file_data_write_alignment")?;
+    let library = MockLibrary::new();
+    *library.write_alignment.lock().unwrap() = Some(3 * 4096);
+    let pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+    assert_eq!(pfs.write_alignment(), 3 * 4096);
+
+    let expected = TestProjectionSource::content(Path::new("large.bin"));
+    let content = library
+        .read_file("large.bin", 0, expected.len() as u32)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, expected);
+
+    let writes = library.file_data_writes.lock().unwrap();
+    let (last, chunks) = writes.split_last().unwrap();
+    assert!(!chunks.is_empty());
+    for (offset, length) in chunks {
+        assert_eq!(offset % (3 * 4096), 0);
+        assert_eq!(length % (3 * 4096), 0);
+    }
+    assert_eq!(last.0 % (3 * 4096), 0);
+    assert_eq!(last.0 + last.1 as u64, expected.len() as u64);
+    Ok(())
+}
+
+#[test]
+fn mock_file_data_placeholder_size() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_file_data_placeholder_size")?;
+    let library = MockLibrary::new();
+    let listings = Arc::new(atomic::AtomicUsize::new(0));
+    let source = FnSource::new()
+        .on_list({
+            let listings = listings.clone();
+            move |_| {
+                listings.fetch_add(1, atomic::Ordering::Relaxed);
+                vec![DirectoryEntry::file("file.txt", 4)]
+            }
+        })
+        .on_stream(|_path, byte_offset, length| {
+            Ok(Box::new(Cursor::new(
+                b"data"[byte_offset..byte_offset + length].to_vec(),
+            )) as Box<dyn Read>)
+        });
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    assert_eq!(library.placeholder_info("file.txt"), S_OK);
+    let placeholder_listings = listings.load(atomic::Ordering::Relaxed);
+
+    /* reads are clamped to the size of the placeholder without listing the directory again */
+    let content = library
+        .read_file("file.txt", 2, 4096)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, b"ta");
+    assert_eq!(
+        library.read_file("file.txt", 4, 1),
+        Err(ERROR_HANDLE_EOF.to_hresult())
+    );
+    assert_eq!(
+        listings.load(atomic::Ordering::Relaxed),
+        placeholder_listings
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_file_data_past_eof() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_file_data_past_eof")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    let expected = TestProjectionSource::content(Path::new("b.txt"));
+    let content = library
+        .read_file("b.txt", 1, 4096)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, expected[1..2]);
+
+    assert_eq!(
+        library.read_file("b.txt", 2, 1),
+        Err(ERROR_HANDLE_EOF.to_hresult())
+    );
+    assert_eq!(
+        library.read_file("b.txt", 4096, 4096),
+        Err(ERROR_HANDLE_EOF.to_hresult())
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_materialized_paths() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_materialized_paths")?;
+    let target_dir = target_dir.path();
+    let library = MockLibrary::new();
+    let pfs = ProjectedFileSystem::new_with_library(target_dir, test_source(), library.clone())?;
+
+    /* the full directory contains items unknown to the source */
+    fs::create_dir_all(target_dir.join("c").join("sub"))?;
+    fs::write(target_dir.join("c").join("new.txt"), "new")?;
+    fs::write(
+        target_dir.join("c").join("sub").join("nested.txt"),
+        "nested",
+    )?;
+
+    let states = [
+        ("b.txt", OnDiskFileState::PLACEHOLDER),
+        ("c", OnDiskFileState::FULL),
+        ("A.txt", OnDiskFileState::TOMBSTONE),
+        (
+            "large.bin",
+            OnDiskFileState::PLACEHOLDER | OnDiskFileState::HYDRATED_PLACEHOLDER,
+        ),
+    ];
+    for (path, state) in states {
+        library.on_disk_states.lock().unwrap().insert(
+            target_dir.join(path).to_string_lossy().to_string(),
+            state.bits(),
+        );
+    }
+
+    assert_eq!(
+        pfs.materialized_paths()?,
+        vec![
+            PathBuf::from("b.txt"),
+            PathBuf::from("c"),
+            Path::new("c").join("new.txt"),
+            Path::new("c").join("sub"),
+            Path::new("c").join("sub").join("nested.txt"),
+            PathBuf::from("large.bin"),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_enumeration_sorting() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_enumeration_sorting")?;
+    let library = MockLibrary::new();
+    let source = TestProjectionSource {
+        entries: vec![
+            DirectoryEntry::file("zeta.txt", 0),
+            DirectoryEntry::file("Beta.md", 0),
+            DirectoryEntry::directory("alpha"),
+            DirectoryEntry::file("GAMMA.txt", 0),
+            DirectoryEntry::file("delta", 0),
+            DirectoryEntry::file("beta.txt", 0),
+        ],
+    };
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    let names = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "alpha",
+            "Beta.md",
+            "beta.txt",
+            "delta",
+            "GAMMA.txt",
+            "zeta.txt"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_prewarm_names() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_prewarm_names")?;
+    let library = MockLibrary::new();
+    let pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    /* prewarmed names must behave exactly like names encoded on demand */
+    pfs.prewarm_names(["b.txt", "A.txt", "c", "unknown.txt"].map(String::from));
+
+    let names = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["A.txt", "b.txt", "c", "empty.txt", "large.bin"]);
+
+    let names = library
+        .enumerate("", Some("?.txt"), 100)
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["A.txt", "b.txt"]);
+
+    library.placeholder_info("b.txt").ok()?;
+    library.placeholder_info("empty.txt").ok()?;
+
+    let placeholders = library.placeholders.lock().unwrap();
+    assert_eq!(placeholders[0].name, "b.txt");
+    assert_eq!(placeholders[1].name, "empty.txt");
+    Ok(())
+}
+
+#[test]
+fn mock_enumeration_sort_strategy() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let entries = vec![
+        DirectoryEntry::file("zeta.txt", 0),
+        DirectoryEntry::file("Beta.md", 0),
+        DirectoryEntry::directory("alpha"),
+        DirectoryEntry::file("beta.txt", 0),
+    ];
+
+    let enumerate = |sort_strategy: SortStrategy,
+                     search_expression: Option<&str>|
+     -> anyhow::Result<Vec<String>> {
+        let target_dir = TempDir::new("test_mock_enumeration_sort_strategy")?;
+        let library = MockLibrary::new();
+        let _pfs = ProjectedFileSystemBuilder::new()
+            .library(library.clone())
+            .sort_strategy(sort_strategy)
+            .build(
+                target_dir.path(),
+                TestProjectionSource {
+                    entries: entries.clone(),
+                },
+            )?;
+
+        Ok(library
+            .enumerate("", search_expression, 100)
+            .map_err(windows::core::Error::from)?
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect())
+    };
+
+    assert_eq!(
+        enumerate(SortStrategy::PreSorted, None)?,
+        vec!["zeta.txt", "Beta.md", "alpha", "beta.txt"]
+    );
+    assert_eq!(
+        enumerate(SortStrategy::PreSorted, Some("*.txt"))?,
+        vec!["zeta.txt", "beta.txt"]
+    );
+    assert_eq!(
+        enumerate(
+            SortStrategy::Custom(Box::new(|a, b| a.name().cmp(b.name()))),
+            None
+        )?,
+        vec!["Beta.md", "alpha", "beta.txt", "zeta.txt"]
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_enumeration_sort_key() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let entries = vec![
+        DirectoryEntry::file("a.txt", 0).with_sort_key(3),
+        DirectoryEntry::file("b.txt", 0).with_sort_key(1),
+        DirectoryEntry::directory("c").with_sort_key(2),
+    ];
+
+    let enumerate = |sort_strategy: SortStrategy| -> anyhow::Result<Vec<String>> {
+        let target_dir = TempDir::new("test_mock_enumeration_sort_key")?;
+        let library = MockLibrary::new();
+        let _pfs = ProjectedFileSystemBuilder::new()
+            .library(library.clone())
+            .sort_strategy(sort_strategy)
+            .build(
+                target_dir.path(),
+                TestProjectionSource {
+                    entries: entries.clone(),
+                },
+            )?;
+
+        Ok(library
+            .enumerate("", None, 100)
+            .map_err(windows::core::Error::from)?
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect())
+    };
+
+    /* sort keys do not affect the ProjFS collation order */
+    assert_eq!(
+        enumerate(SortStrategy::ProjFsDefault)?,
+        vec!["a.txt", "b.txt", "c"]
+    );
+    assert_eq!(
+        enumerate(SortStrategy::Custom(Box::new(|a, b| a
+            .sort_key()
+            .cmp(&b.sort_key()))))?,
+        vec!["b.txt", "c", "a.txt"]
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_enumeration_single_entry_search_expression() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_enumeration_single_entry")?;
+    let library = MockLibrary::new();
+    let source = TestProjectionSource {
+        entries: vec![
+            DirectoryEntry::file("a.txt", 0),
+            DirectoryEntry::file("b.txt", 0),
+            DirectoryEntry::file("c.md", 0),
+        ],
+    };
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    /* the first two entries are filtered and must not end the callback without an entry */
+    let callbacks = library
+        .enumerate_single_entries("", Some("*.md"))
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entries| {
+            entries
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(callbacks, vec![vec!["c.md".to_string()]]);
+
+    let callbacks = library
+        .enumerate_single_entries("", None)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(callbacks.len(), 3);
+    assert!(callbacks.iter().all(|entries| entries.len() == 1));
+    Ok(())
+}
+
+#[test]
+fn mock_enumeration_invalid_names() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_enumeration_invalid_names")?;
+    let library = MockLibrary::new();
+    let source = TestProjectionSource {
+        entries: vec![
+            DirectoryEntry::file("a/b.txt", 0),
+            DirectoryEntry::file("a.txt", 0),
+            DirectoryEntry::file("c:d.txt", 0),
+            DirectoryEntry::directory("e\\f"),
+            DirectoryEntry::file("..", 0),
+        ],
+    };
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    /* invalid names are skipped instead of failing the enumeration */
+    let names = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["a.txt"]);
+    Ok(())
+}
+
+#[test]
+fn mock_enumeration_search_expression() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_enumeration_search_expression")?;
+    let library = MockLibrary::new();
+    let source = TestProjectionSource {
+        entries: vec![
+            DirectoryEntry::file("notes.txt", 0),
+            DirectoryEntry::file("image.png", 0),
+            DirectoryEntry::file("README.TXT", 0),
+            DirectoryEntry::file("a.txt.bak", 0),
+            DirectoryEntry::directory("docs.txt"),
+        ],
+    };
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    let enumerate = |search_expression: &str, buffer_capacity: usize| {
+        library
+            .enumerate("", Some(search_expression), buffer_capacity)
+            .map(|entries| {
+                entries
+                    .into_iter()
+                    .map(|entry| entry.name)
+                    .collect::<Vec<_>>()
+            })
+            .map_err(windows::core::Error::from)
+    };
+
+    /* filtered entries must not count against the buffer capacity */
+    for buffer_capacity in [1, 100] {
+        assert_eq!(
+            enumerate("*.txt", buffer_capacity)?,
+            vec!["docs.txt", "notes.txt", "README.TXT"]
+        );
+        assert_eq!(enumerate("?.txt*", buffer_capacity)?, vec!["a.txt.bak"]);
+        assert_eq!(enumerate("image.png", buffer_capacity)?, vec!["image.png"]);
+        assert!(enumerate("*.exe", buffer_capacity)?.is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn mock_case_sensitive() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_case_sensitive")?;
+    let library = MockLibrary::new();
+    let source = TestProjectionSource {
+        entries: vec![
+            DirectoryEntry::file("file.txt", 1),
+            DirectoryEntry::file("File.txt", 2),
+            DirectoryEntry::file("b.txt", 3),
+        ],
+    };
+    let result = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .case_sensitive(true)
+        .build(target_dir.path(), source);
+    let _pfs = match result {
+        Ok(pfs) => pfs,
+        Err(Error::EnableCaseSensitivity(_, error)) => {
+            eprintln!(
+                "Skipping test as case sensitivity is not supported: {}",
+                error
+            );
+            return Ok(());
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    let enumerate = |search_expression: Option<&str>| {
+        library
+            .enumerate("", search_expression, 100)
+            .map(|entries| {
+                entries
+                    .into_iter()
+                    .map(|entry| entry.name)
+                    .collect::<Vec<_>>()
+            })
+            .map_err(windows::core::Error::from)
+    };
+
+    /* ordinal order sorts upper case letters first */
+    assert_eq!(enumerate(None)?, vec!["File.txt", "b.txt", "file.txt"]);
+    assert_eq!(enumerate(Some("file.txt"))?, vec!["file.txt"]);
+    assert_eq!(enumerate(Some("F*"))?, vec!["File.txt"]);
+    assert_eq!(enumerate(Some("*.TXT"))?, Vec::<String>::new());
+
+    library.placeholder_info("file.txt").ok()?;
+    library.placeholder_info("File.txt").ok()?;
+    assert_eq!(
+        library.placeholder_info("FILE.txt"),
+        ERROR_FILE_NOT_FOUND.to_hresult()
+    );
+
+    let placeholders = library.placeholders.lock().unwrap();
+    assert_eq!(placeholders.len(), 2);
+    assert_eq!(placeholders[0].file_size, 1);
+    assert_eq!(placeholders[1].file_size, 2);
+    Ok(())
+}
+
+#[test]
+fn mock_empty_file_data() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_empty_file_data")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    let content = library
+        .read_file("empty.txt", 0, 0)
+        .map_err(windows::core::Error::from)?;
+    assert!(content.is_empty());
+    Ok(())
+}
+
+#[test]
+fn mock_file_overwritten_notification() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_file_overwritten")?;
+    let library = MockLibrary::new();
+    let pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+    let notifications = pfs.notification_channel();
+
+    let result = library.notify("b.txt", false, PRJ_NOTIFICATION_FILE_OVERWRITTEN);
+    assert!(result.is_ok());
+
+    let Notification::FileOverwritten(file) = notifications.try_recv()? else {
+        panic!("expected a file overwritten notification");
+    };
+    assert_eq!(file.path, Path::new("b.txt"));
+    assert!(!file.is_directory);
+    assert_eq!(
+        file.absolute(target_dir.path()),
+        target_dir.path().join("b.txt")
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_negative_path_cache() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_negative_path_cache")?;
+    let library = MockLibrary::new();
+    let pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .build(target_dir.path(), test_source())?;
+    assert_eq!(*library.start_flags.lock().unwrap(), Some(PRJ_FLAG_NONE));
+    drop(pfs);
+
+    let target_dir = TempDir::new("test_mock_negative_path_cache")?;
+    let library = MockLibrary::new();
+    let pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .use_negative_path_cache(true)
+        .build(target_dir.path(), test_source())?;
+    assert_eq!(
+        *library.start_flags.lock().unwrap(),
+        Some(PRJ_FLAG_USE_NEGATIVE_PATH_CACHE)
+    );
+
+    assert_eq!(pfs.clear_negative_path_cache()?, 0);
+    assert_eq!(
+        library
+            .negative_path_cache_clears
+            .load(atomic::Ordering::Relaxed),
+        1
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_enumeration_stats() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_enumeration_stats")?;
+    let library = MockLibrary::new();
+    let pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+    assert_eq!(pfs.enumeration_stats(), EnumerationStats::default());
+
+    library
+        .enumerate("", None, 2)
+        .map_err(windows::core::Error::from)?;
+    library
+        .enumerate("", Some("*.txt"), 2)
+        .map_err(windows::core::Error::from)?;
+    library
+        .enumerate("c", None, 2)
+        .map_err(windows::core::Error::from)?;
+
+    let stats = pfs.enumeration_stats();
+    assert_eq!(stats.enumerations, 3);
+    assert_eq!(stats.active_enumerations, 0);
+    assert_eq!(stats.max_concurrent_enumerations, 1);
+    assert_eq!(stats.max_depth, 1);
+    assert_eq!(
+        stats.entries_per_directory,
+        BTreeMap::from([(PathBuf::new(), 5), (PathBuf::from("c"), 0)])
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_file_data_range_not_available() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    const AVAILABLE: usize = 1024;
+
+    let target_dir = TempDir::new("test_mock_file_data_range_not_available")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new()
+        .on_list(|_| vec![DirectoryEntry::file("sparse.bin", 4096)])
+        .on_stream(|path, byte_offset, length| {
+            if path == Path::new("broken.bin") {
+                return Err(io::Error::other("backend failure"));
+            }
+
+            if byte_offset + length > AVAILABLE {
+                return Err(RangeNotAvailable {
+                    byte_offset,
+                    length,
+                }
+                .into());
+            }
+
+            Ok(Box::new(Cursor::new(vec![1u8; length])))
+        });
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    let content = library
+        .read_file("sparse.bin", 0, AVAILABLE as u32)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, vec![1u8; AVAILABLE]);
+
+    assert_eq!(
+        library.read_file("sparse.bin", AVAILABLE as u64, 1024),
+        Err(ERROR_FILE_OFFLINE.to_hresult())
+    );
+
+    let result = library.read_file("broken.bin", 0, 1024);
+    assert!(result.is_err());
+    assert_ne!(result, Err(ERROR_FILE_OFFLINE.to_hresult()));
+    Ok(())
+}
+
+#[test]
+fn mock_file_data_source_error() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_file_data_source_error")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new()
+        .on_list(|_| vec![DirectoryEntry::file("file.txt", 4)])
+        .on_stream(|path, _byte_offset, _length| {
+            let error = match path.to_str() {
+                Some("directory.txt") => SourceError::IsADirectory.into(),
+                Some("removed.txt") => SourceError::NotFound.into(),
+                Some("kind.txt") => io::Error::from(io::ErrorKind::IsADirectory),
+                _ => io::Error::other("backend failure"),
+            };
+            Err(error)
+        });
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let _pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .on_callback_error({
+            let errors = errors.clone();
+            move |error| errors.lock().unwrap().push(error.path)
+        })
+        .build(target_dir.path(), source)?;
+
+    assert_eq!(
+        library.read_file("directory.txt", 0, 4),
+        Err(ERROR_DIRECTORY_NOT_SUPPORTED.to_hresult())
+    );
+    assert_eq!(
+        library.read_file("kind.txt", 0, 4),
+        Err(ERROR_DIRECTORY_NOT_SUPPORTED.to_hresult())
+    );
+    assert_eq!(
+        library.read_file("removed.txt", 0, 4),
+        Err(ERROR_FILE_NOT_FOUND.to_hresult())
+    );
+    assert!(library.read_file("broken.txt", 0, 4).is_err());
+
+    /* only the unexpected failure is reported */
+    assert_eq!(
+        *errors.lock().unwrap(),
+        vec![Some(PathBuf::from("broken.txt"))]
+    );
+    Ok(())
+}
+
+/// Provides four bytes of content and records the threads polling the reads.
+#[derive(Default, Clone)]
+struct ThreadRecordingSource {
+    threads: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+impl AsyncSource for ThreadRecordingSource {
+    fn read_file_content(
+        &self,
+        _path: PathBuf,
+        _byte_offset: usize,
+        length: usize,
+    ) -> SourceFuture<Vec<u8>> {
+        let threads = self.threads.clone();
+        Box::pin(async move {
+            let name = thread::current().name().map(str::to_string);
+            threads.lock().unwrap().push(name);
+            Ok(vec![0x42; length])
+        })
+    }
+}
+
+#[test]
+fn mock_async_read_pool() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_async_read_pool")?;
+    let library = MockLibrary::new();
+    let async_source = ThreadRecordingSource::default();
+    let _pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .async_source(async_source.clone())
+        .async_read_threads(1)
+        .build(target_dir.path(), test_source())?;
+
+    /* empty files are not read */
+    for (path, size) in [("A.txt", 1), ("b.txt", 2), ("empty.txt", 0)] {
+        let content = library
+            .read_file(path, 0, size as u32)
+            .map_err(windows::core::Error::from)?;
+        assert_eq!(content, vec![0x42; size]);
+    }
+
+    /* every read has been driven by the single pool thread */
+    let threads = async_source.threads.lock().unwrap().clone();
+    assert_eq!(threads, vec![Some("projfs-read-0".to_string()); 2]);
+    Ok(())
+}
+
+#[test]
+fn mock_async_spawner() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_async_spawner")?;
+    let library = MockLibrary::new();
+    let async_source = ThreadRecordingSource::default();
+    let spawned = Arc::new(atomic::AtomicUsize::new(0));
+    let _pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .async_source(async_source.clone())
+        .async_spawner({
+            let spawned = spawned.clone();
+            move |task| {
+                spawned.fetch_add(1, atomic::Ordering::Relaxed);
+                thread::Builder::new()
+                    .name("test-runtime".to_string())
+                    .spawn(move || futures::executor::block_on(task))
+                    .expect("a runtime thread");
+            }
+        })
+        .build(target_dir.path(), test_source())?;
+
+    let content = library
+        .read_file("b.txt", 0, 2)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, vec![0x42; 2]);
+
+    assert_eq!(spawned.load(atomic::Ordering::Relaxed), 1);
+    assert_eq!(
+        *async_source.threads.lock().unwrap(),
+        vec![Some("test-runtime".to_string())]
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_async_read_after_drop() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_async_read_after_drop")?;
+    let library = MockLibrary::new();
+    let tasks = Arc::new(Mutex::new(Vec::new()));
+    let pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .async_source(ThreadRecordingSource::default())
+        .async_spawner({
+            let tasks = tasks.clone();
+            move |task| tasks.lock().unwrap().push(task)
+        })
+        .build(target_dir.path(), test_source())?;
+
+    assert_eq!(
+        library.request_file_data("b.txt", 0, 2),
+        ERROR_IO_PENDING.to_hresult()
+    );
+    drop(pfs);
+
+    /* the read resolves after the projection has been stopped and must be dropped */
+    let task = tasks.lock().unwrap().pop().expect("a spawned read");
+    futures::executor::block_on(task);
+
+    assert!(library.completions.lock().unwrap().is_empty());
+    assert!(library.file_data.lock().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn mock_source_panic() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_source_panic")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new()
+        .on_list(|path| {
+            if path.as_os_str().is_empty() {
+                vec![DirectoryEntry::file("file.txt", 4)]
+            } else {
+                panic!("listing {} failed", path.display())
+            }
+        })
+        .on_stream(|path, _byte_offset, _length| {
+            if path == Path::new("panic.txt") {
+                panic!("streaming failed");
+            }
+            Ok(Box::new(Cursor::new(b"data".to_vec())) as Box<dyn Read>)
+        });
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let _pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .on_callback_error({
+            let errors = errors.clone();
+            move |error| errors.lock().unwrap().push(error.result)
+        })
+        .build(target_dir.path(), source)?;
+
+    assert_eq!(library.enumerate("broken", None, 100), Err(E_UNEXPECTED));
+    assert_eq!(library.read_file("panic.txt", 0, 4), Err(E_UNEXPECTED));
+
+    /* the projection keeps serving requests after a panic */
+    let entries = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(entries.len(), 1);
+
+    let content = library
+        .read_file("file.txt", 0, 4)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, b"data");
+
+    assert_eq!(*errors.lock().unwrap(), vec![E_UNEXPECTED, E_UNEXPECTED]);
+    Ok(())
+}
+
+#[test]
+fn mock_rename_is_directory() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_rename_is_directory")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new().on_notification(|notification| match notification {
+        Notification::PreFileRename(info) if info.is_directory => ControlFlow::Break(().into()),
+        _ => ControlFlow::Continue(()),
+    });
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    assert!(library
+        .notify("c", true, PRJ_NOTIFICATION_PRE_RENAME)
+        .is_err());
+    assert!(library
+        .notify("b.txt", false, PRJ_NOTIFICATION_PRE_RENAME)
+        .is_ok());
+    Ok(())
+}
+
+#[test]
+fn mock_notification_veto_result() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_notification_veto_result")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new().on_notification(|notification| match notification {
+        Notification::PreFileDelete(file) if file.path.ends_with("b.txt") => ControlFlow::Break(
+            NotificationVeto::with_result(STATUS_SHARING_VIOLATION.to_hresult()),
+        ),
+        Notification::PreFileDelete(_) => ControlFlow::Break(().into()),
+        _ => ControlFlow::Continue(()),
+    });
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    assert_eq!(
+        library.notify("b.txt", false, PRJ_NOTIFICATION_PRE_DELETE),
+        STATUS_SHARING_VIOLATION.to_hresult()
+    );
+    assert_eq!(
+        library.notify("c", true, PRJ_NOTIFICATION_PRE_DELETE),
+        STATUS_CANNOT_DELETE.to_hresult()
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_notification_path_filter() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_notification_path_filter")?;
+    let library = MockLibrary::new();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let source = FnSource::new().on_notification({
+        let received = received.clone();
+        move |notification| {
+            received.lock().unwrap().push(notification.clone());
+            ControlFlow::Continue(())
+        }
+    });
+    let pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .notification_path_filter(|path| path.starts_with("watched"))
+        .build(target_dir.path(), source)?;
+    let notifications = pfs.notification_channel();
+
+    assert!(library
+        .notify("ignored\\b.txt", false, PRJ_NOTIFICATION_FILE_OVERWRITTEN)
+        .is_ok());
+    assert!(library
+        .notify("ignored\\b.txt", false, PRJ_NOTIFICATION_PRE_DELETE)
+        .is_ok());
+    assert!(received.lock().unwrap().is_empty());
+    assert!(notifications.try_recv().is_err());
+
+    assert!(library
+        .notify("watched\\b.txt", false, PRJ_NOTIFICATION_FILE_OVERWRITTEN)
+        .is_ok());
+    assert!(library
+        .notify_with_destination(
+            "ignored\\c.txt",
+            Some("watched\\c.txt"),
+            false,
+            PRJ_NOTIFICATION_FILE_RENAMED
+        )
+        .is_ok());
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+    assert!(matches!(received[0], Notification::FileOverwritten(_)));
+    assert!(matches!(received[1], Notification::FileRenamed(_)));
+    assert!(notifications.try_recv().is_ok());
+    Ok(())
+}
+
+#[test]
+fn mock_rename_paths() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_rename_paths")?;
+    let library = MockLibrary::new();
+    let pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+    let notifications = pfs.notification_channel();
+
+    let renames = [
+        ("c\\a.txt", "d\\b.txt"),
+        ("c\\a.txt", "\\d\\b.txt"),
+        ("c/a.txt", "d/b.txt"),
+        ("c\\a.txt", "b.txt"),
+        ("c\\a.txt", ""),
+        ("", "d\\b.txt"),
+    ];
+    for (source, destination) in renames {
+        let result = library.notify_with_destination(
+            source,
+            Some(destination),
+            false,
+            PRJ_NOTIFICATION_FILE_RENAMED,
+        );
+        assert!(result.is_ok());
+    }
+
+    let renames = notifications
+        .try_iter()
+        .map(|notification| match notification {
+            Notification::FileRenamed(info) => (info.source, info.destination),
+            notification => panic!("unexpected notification {:?}", notification),
+        })
+        .collect::<Vec<_>>();
+
+    let path = |path: &str| Some(PathBuf::from(path));
+    assert_eq!(
+        renames,
+        vec![
+            (path("c\\a.txt"), path("d\\b.txt")),
+            (path("c\\a.txt"), path("d\\b.txt")),
+            (path("c\\a.txt"), path("d\\b.txt")),
+            /* a single file name refers to the projection root */
+            (path("c\\a.txt"), path("b.txt")),
+            (path("c\\a.txt"), None),
+            (None, path("d\\b.txt")),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_thread_counts() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_thread_counts")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .pool_threads(8)
+        .concurrent_threads(4)
+        .build(target_dir.path(), test_source())?;
+    assert_eq!(*library.start_thread_counts.lock().unwrap(), Some((8, 4)));
+
+    let target_dir = TempDir::new("test_mock_thread_counts")?;
+    let result = ProjectedFileSystemBuilder::new()
+        .library(MockLibrary::new())
+        .pool_threads(2)
+        .concurrent_threads(4)
+        .build(target_dir.path(), test_source());
+    assert!(matches!(result, Err(Error::InvalidThreadCounts(2, 4))));
+    Ok(())
+}
+
+#[test]
+fn mock_instance_id() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_instance_id")?;
+    let library = MockLibrary::new();
+    let pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .instance_id(0x1234)
+        .build(target_dir.path(), test_source())?;
+    drop(pfs);
+
+    let _pfs = ProjectedFileSystemBuilder::new()
+        .library(library.clone())
+        .instance_id(0x1234)
+        .build(target_dir.path(), test_source())?;
+    assert_eq!(
+        *library.marked_instance_ids.lock().unwrap(),
+        vec![0x1234, 0x1234]
+    );
+
+    /* a new id is generated for every projection by default */
+    let target_dir = TempDir::new("test_mock_instance_id")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(
+        &target_dir.path().join("first"),
+        test_source(),
+        library.clone(),
+    )?;
+    let _pfs = ProjectedFileSystem::new_with_library(
+        &target_dir.path().join("second"),
+        test_source(),
+        library.clone(),
+    )?;
+
+    let instance_ids = library.marked_instance_ids.lock().unwrap();
+    assert_eq!(instance_ids.len(), 2);
+    assert_ne!(instance_ids[0], instance_ids[1]);
+    Ok(())
+}
+
+#[test]
+fn mock_is_running() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_is_running")?;
+    let root = target_dir.path().join("root");
+    let library = MockLibrary::new();
+    let pfs = ProjectedFileSystem::new_with_library(&root, test_source(), library.clone())?;
+    assert!(pfs.is_running());
+
+    *library.fail_instance_info.lock().unwrap() =
+        Some(ERROR_FILE_SYSTEM_VIRTUALIZATION_UNAVAILABLE.to_hresult());
+    assert!(!pfs.is_running());
+
+    *library.fail_instance_info.lock().unwrap() = None;
+    assert!(pfs.is_running());
+
+    fs::remove_dir(&root)?;
+    assert!(!pfs.is_running());
+    Ok(())
+}
+
+#[repr(C, align(4096))]
+struct AlignedContent([u8; 8192]);
+
+static SLICE_CONTENT: AlignedContent = AlignedContent([0x42; 8192]);
+
+struct SliceProjectionSource;
+
+impl ProjectedFileSystemSource for SliceProjectionSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        vec![DirectoryEntry::file(
+            "data.bin",
+            SLICE_CONTENT.0.len() as u64,
+        )]
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Err(io::Error::other("content should be served as slice"))
+    }
+
+    fn file_content_slice(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        _length: usize,
+    ) -> Option<io::Result<Cow<'_, [u8]>>> {
+        Some(Ok(Cow::Borrowed(&SLICE_CONTENT.0[byte_offset..])))
+    }
+}
+
+#[test]
+fn mock_file_content_slice() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_file_content_slice")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        SliceProjectionSource,
+        library.clone(),
+    )?;
+
+    /* aligned slices are written without copying */
+    let content = library
+        .read_file("data.bin", 0, 8192)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, SLICE_CONTENT.0);
+    assert_eq!(
+        library.file_data_buffers.lock().unwrap().last().copied(),
+        Some(SLICE_CONTENT.0.as_ptr() as usize)
+    );
+
+    /* unaligned slices are copied into an aligned buffer */
+    let content = library
+        .read_file("data.bin", 1, 100)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, &SLICE_CONTENT.0[1..101]);
+    assert_ne!(
+        library.file_data_buffers.lock().unwrap().last().copied(),
+        Some(SLICE_CONTENT.0.as_ptr() as usize + 1)
+    );
+
+    /* reads past the end of the file are clamped to the file size */
+    let content = library
+        .read_file("data.bin", 8000, 500)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, &SLICE_CONTENT.0[8000..]);
+    Ok(())
+}
+
+#[test]
+fn mock_file_data_short_stream() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    const DECLARED: usize = 3 * 1024 * 1024;
+    const AVAILABLE: usize = 1024 * 1024 + 17;
+
+    let target_dir = TempDir::new("test_mock_file_data_short_stream")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new()
+        .on_list(|_| vec![DirectoryEntry::file("short.bin", DECLARED as u64)])
+        .on_stream(|_path, byte_offset, _length| {
+            Ok(Box::new(Cursor::new(vec![
+                1u8;
+                AVAILABLE
+                    .saturating_sub(byte_offset)
+            ])))
+        });
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    assert_eq!(
+        library.read_file("short.bin", 0, DECLARED as u32),
+        Err(ERROR_HANDLE_EOF.to_hresult())
+    );
+
+    /* reads within the available content are not affected */
+    let content = library
+        .read_file("short.bin", 0, 1024)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, vec![1u8; 1024]);
+    Ok(())
+}
+
+#[test]
+fn mock_full_hydration() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    const SIZE: usize = 2 * 1024 * 1024 + 17;
+
+    let target_dir = TempDir::new("test_mock_full_hydration")?;
+    let library = MockLibrary::new();
+    let source = FnSource::new()
+        .on_list(|_| {
+            vec![
+                FileInfo {
+                    file_name: "full.bin".to_string(),
+                    file_size: (SIZE as u64).into(),
+                    ..Default::default()
+                }
+                .hydration(Hydration::Full)
+                .into(),
+                DirectoryEntry::file("on_demand.bin", SIZE as u64),
+            ]
+        })
+        .on_stream(|_path, byte_offset, length| {
+            Ok(Box::new(Cursor::new(
+                (byte_offset..byte_offset + length)
+                    .map(|index| index as u8)
+                    .collect::<Vec<_>>(),
+            )))
+        });
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+    let expected = (0..SIZE).map(|index| index as u8).collect::<Vec<_>>();
+
+    assert!(library.placeholder_info("full.bin").is_ok());
+    assert!(library.placeholder_info("on_demand.bin").is_ok());
+
+    /* the first read of a fully hydrated file writes the whole file */
+    let content = library
+        .read_file("full.bin", 4096, 4096)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, expected);
+
+    let content = library
+        .read_file("full.bin", 4096, 4096)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, &expected[4096..8192]);
+
+    let content = library
+        .read_file("on_demand.bin", 4096, 4096)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, &expected[4096..8192]);
+    Ok(())
+}
+
+#[test]
+fn mock_unknown_file_size() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    const SIZE: usize = 64 * 1024 + 17;
+
+    let target_dir = TempDir::new("test_mock_unknown_file_size")?;
+    let library = MockLibrary::new();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let source = FnSource::new()
+        .on_list(|_| vec![DirectoryEntry::file("generated.bin", FileSize::Unknown)])
+        .on_stream({
+            let requests = requests.clone();
+            move |_path, byte_offset, length| {
+                requests.lock().unwrap().push((byte_offset, length));
+                Ok(Box::new(Cursor::new(
+                    (byte_offset..SIZE)
+                        .map(|index| index as u8)
+                        .collect::<Vec<_>>(),
+                )))
+            }
+        });
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+    let expected = (0..SIZE).map(|index| index as u8).collect::<Vec<_>>();
+
+    /* listing the directory does not buffer the content */
+    let entries = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].file_size, 0);
+    assert!(requests.lock().unwrap().is_empty());
+
+    assert!(library.placeholder_info("generated.bin").is_ok());
+    assert_eq!(
+        library.placeholders.lock().unwrap()[0].file_size,
+        SIZE as i64
+    );
+    assert_eq!(*requests.lock().unwrap(), [(0, usize::MAX)]);
+
+    /* reading the tail first writes the whole file from the buffered content */
+    let content = library
+        .read_file("generated.bin", SIZE as u64 - 17, 17)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, expected);
+    assert_eq!(*requests.lock().unwrap(), [(0, usize::MAX)]);
+    Ok(())
+}
+
+struct SparseProjectionSource {
+    requests: Arc<Mutex<Vec<(usize, usize)>>>,
+}
+
+impl SparseProjectionSource {
+    const SIZE: usize = 16 * 4096 + 17;
+    const ZEROS: Range<usize> = 4000..12 * 4096 + 100;
+
+    fn content(byte_offset: usize, length: usize) -> Vec<u8> {
+        (byte_offset..byte_offset + length)
+            .map(|index| {
+                if Self::ZEROS.contains(&index) {
+                    0
+                } else {
+                    index as u8 | 1
+                }
+            })
+            .collect()
+    }
+}
+
+impl ProjectedFileSystemSource for SparseProjectionSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        vec![FileInfo {
+            file_name: "sparse.bin".to_string(),
+            file_size: (Self::SIZE as u64).into(),
+            file_attributes: FileAttributes::SPARSE_FILE.bits(),
+            ..Default::default()
+        }
+        .into()]
+    }
+
+    fn zero_ranges(&self, _path: &Path) -> Vec<Range<u64>> {
+        /* the range is not aligned and therefore shrunk */
+        vec![Range {
+            start: Self::ZEROS.start as u64,
+            end: Self::ZEROS.end as u64,
+        }]
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        self.requests.lock().unwrap().push((byte_offset, length));
+        Ok(Box::new(Cursor::new(Self::content(byte_offset, length))))
+    }
+}
+
+#[test]
+fn mock_sparse_file() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_sparse_file")?;
+    let library = MockLibrary::new();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let source = SparseProjectionSource {
+        requests: requests.clone(),
+    };
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    let size = SparseProjectionSource::SIZE;
+    let content = library
+        .read_file("sparse.bin", 0, size as u32)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, SparseProjectionSource::content(0, size));
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![(0, 4096), (12 * 4096, size - 12 * 4096)]
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_sparse_file_write_alignment() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_sparse_file_write_alignment")?;
+    let library = MockLibrary::new();
+    *library.write_alignment.lock().unwrap() = Some(2 * 4096);
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let source = SparseProjectionSource {
+        requests: requests.clone(),
+    };
+    let _pfs = ProjectedFileSystem::new_with_library(target_dir.path(), source, library.clone())?;
+
+    /* the zero range is shrunk to the alignment of the volume */
+    let size = SparseProjectionSource::SIZE;
+    let content = library
+        .read_file("sparse.bin", 0, size as u32)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, SparseProjectionSource::content(0, size));
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![(0, 2 * 4096), (12 * 4096, size - 12 * 4096)]
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_file_chunks() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    const SIZE: usize = 3 * 1024 * 1024 + 17;
+
+    struct ChunkedContentSource;
+    impl ProjectedFileSystemSource for ChunkedContentSource {
+        fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+            vec![DirectoryEntry::file("chunked.bin", SIZE as u64)]
+        }
+
+        fn stream_file_content(
+            &self,
+            _path: &Path,
+            _byte_offset: usize,
+            _length: usize,
+        ) -> io::Result<Box<dyn Read>> {
+            Err(io::Error::other("content should be served as chunks"))
+        }
+
+        fn stream_file_chunks(
+            &self,
+            _path: &Path,
+            byte_offset: usize,
+            length: usize,
+        ) -> Option<io::Result<FileChunks>> {
+            /* unaligned chunks followed by an empty one */
+            let end = byte_offset + length;
+            let chunks = (byte_offset..end).step_by(1000).map(move |start| {
+                Ok((start..(start + 1000).min(end))
+                    .map(|index| index as u8)
+                    .collect::<Vec<_>>())
+            });
+            Some(Ok(Box::new(chunks.chain([Ok(Vec::new())]))))
+        }
+    }
+
+    let target_dir = TempDir::new("test_mock_file_chunks")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        ChunkedContentSource,
+        library.clone(),
+    )?;
+
+    let content = library
+        .read_file("chunked.bin", 4096, (SIZE - 4096) as u32)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(
+        content,
+        (4096..SIZE).map(|index| index as u8).collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+struct SharedEntriesSource(Arc<Mutex<Vec<DirectoryEntry>>>);
+
+impl ProjectedFileSystemSource for SharedEntriesSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            self.0.lock().unwrap().clone()
+        } else {
+            vec![]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Err(io::ErrorKind::NotFound.into())
+    }
+}
+
+#[test]
+fn mock_refresh() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_refresh")?;
+    let library = MockLibrary::new();
+    let entries = Arc::new(Mutex::new(vec![DirectoryEntry::file("a.txt", 1)]));
+    let pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        SharedEntriesSource(entries.clone()),
+        library.clone(),
+    )?;
+
+    /* modified */
+    entries.lock().unwrap()[0] = DirectoryEntry::file("a.txt", 42);
+    pfs.refresh("a.txt")?;
+
+    /* created */
+    entries.lock().unwrap().push(DirectoryEntry::directory("b"));
+    pfs.refresh("b")?;
+
+    {
+        let updated_files = library.updated_files.lock().unwrap();
+        assert_eq!(updated_files.len(), 2);
+        assert_eq!(updated_files[0].name, "a.txt");
+        assert!(!updated_files[0].is_directory);
+        assert_eq!(updated_files[0].file_size, 42);
+        assert_eq!(
+            updated_files[0].placeholder_info_size,
+            mem::size_of::<PRJ_PLACEHOLDER_INFO>() as u32
+        );
+        assert_eq!(updated_files[1].name, "b");
+        assert!(updated_files[1].is_directory);
+    }
+    assert!(library.deleted_files.lock().unwrap().is_empty());
+
+    /* deleted */
+    entries
+        .lock()
+        .unwrap()
+        .retain(|entry| entry.name() != "a.txt");
+    pfs.refresh("a.txt")?;
+    assert_eq!(*library.deleted_files.lock().unwrap(), vec!["a.txt"]);
+    assert_eq!(library.updated_files.lock().unwrap().len(), 2);
+
+    /* items which do not exist on disk are left untouched */
+    *library.fail_update.lock().unwrap() = Some(ERROR_FILE_NOT_FOUND.to_hresult());
+    pfs.refresh("b")?;
+    pfs.refresh("a.txt")?;
+
+    /* local modifications prevent the update */
+    *library.fail_update.lock().unwrap() =
+        Some(ERROR_FILE_SYSTEM_VIRTUALIZATION_INVALID_OPERATION.to_hresult());
+    match pfs.refresh("b") {
+        Err(Error::GenericWindows(error)) => assert_eq!(
+            error.code(),
+            ERROR_FILE_SYSTEM_VIRTUALIZATION_INVALID_OPERATION.to_hresult()
+        ),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    Ok(())
+}
+
+/// Source serving every read with the number of previously served reads.
+struct CountingReadSource {
+    reads: u8,
+}
+
+impl ProjectedFileSystemSource for CountingReadSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            vec![DirectoryEntry::file("counter.bin", 16)]
+        } else {
+            vec![]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        unreachable!("reads are served by stream_file_content_mut")
+    }
+
+    fn stream_file_content_mut(
+        &mut self,
+        _path: &Path,
+        _byte_offset: usize,
+        length: usize,
+        _cancellation: &CancellationToken,
+    ) -> io::Result<Box<dyn Read>> {
+        let content = vec![self.reads; length];
+        self.reads += 1;
+        Ok(Box::new(Cursor::new(content)))
+    }
+}
+
+#[test]
+fn mock_file_data_mut() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_file_data_mut")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        CountingReadSource { reads: 0 },
+        library.clone(),
+    )?;
+
+    let content = library
+        .read_file("counter.bin", 0, 16)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, vec![0; 16]);
+
+    let content = library
+        .read_file("counter.bin", 8, 8)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(content, vec![1; 8]);
+    Ok(())
+}
+
+/// Source counting the calls to `list_directory` which looks up entries without listing.
+struct LookupSource {
+    listings: Arc<atomic::AtomicUsize>,
+}
+
+impl ProjectedFileSystemSource for LookupSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        self.listings.fetch_add(1, atomic::Ordering::Relaxed);
+        vec![
+            DirectoryEntry::directory("huge"),
+            DirectoryEntry::file("huge.txt", 4),
+        ]
+    }
+
+    fn get_directory_entry(&self, path: &Path) -> Option<DirectoryEntry> {
+        if path == Path::new("huge") {
+            Some(DirectoryEntry::directory("huge"))
+        } else if path.parent() == Some(Path::new("huge")) {
+            Some(DirectoryEntry::file(path.file_name()?.to_str()?, 4))
+        } else {
+            None
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(Cursor::new(
+            b"data"[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+#[test]
+fn mock_lookup_without_listing() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_lookup_without_listing")?;
+    let library = MockLibrary::new();
+    let listings = Arc::new(atomic::AtomicUsize::new(0));
+    let _pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        LookupSource {
+            listings: listings.clone(),
+        },
+        library.clone(),
+    )?;
+
+    /* opening a file walks its path component by component */
+    assert!(library.placeholder_info("huge").is_ok());
+    assert!(library.placeholder_info("huge\\file-42.txt").is_ok());
+    assert_eq!(
+        library
+            .read_file("huge\\file-42.txt", 0, 4)
+            .map_err(windows::core::Error::from)?,
+        b"data"
+    );
+    assert_eq!(
+        library.placeholder_info("missing.txt"),
+        ERROR_FILE_NOT_FOUND.to_hresult()
+    );
+    assert_eq!(listings.load(atomic::Ordering::Relaxed), 0);
+
+    let placeholders = library.placeholders.lock().unwrap();
+    assert_eq!(placeholders.len(), 2);
+    assert_eq!(placeholders[1].name, "huge\\file-42.txt");
+    assert_eq!(placeholders[1].file_size, 4);
+    Ok(())
+}
+
+#[test]
+fn mock_callback_success_codes() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_callback_success_codes")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    /* the library helpers additionally require every successful callback to return S_OK */
+    let enumeration = library
+        .start_enumeration("")
+        .map_err(windows::core::Error::from)?;
+    let entries = library
+        .next_entries(&enumeration, 100)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(entries.len(), 5);
+
+    /* the end of an enumeration is signaled by S_OK without any entries */
+    assert_eq!(library.next_entries(&enumeration, 100), Ok(vec![]));
+    library.end_enumeration(&enumeration);
+    assert_eq!(library.next_entries(&enumeration, 100), Ok(vec![]));
+
+    assert_eq!(library.placeholder_info("b.txt"), S_OK);
+    assert_eq!(
+        library.read_file("b.txt", 0, 2),
+        Ok(TestProjectionSource::content(Path::new("b.txt"))[..2].to_vec())
+    );
+    assert_eq!(
+        library.notify("b.txt", false, PRJ_NOTIFICATION_FILE_OVERWRITTEN),
+        S_OK
+    );
+    Ok(())
+}
+
+#[test]
+fn mock_cancel_enumerations() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_cancel_enumerations")?;
+    let library = MockLibrary::new();
+    let pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_source(), library.clone())?;
+
+    let enumeration = library
+        .start_enumeration("")
+        .map_err(windows::core::Error::from)?;
+    let entries = library
+        .next_entries(&enumeration, 1)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(pfs.enumeration_stats().active_enumerations, 1);
+
+    pfs.cancel_enumerations();
+    assert_eq!(
+        library.next_entries(&enumeration, 1),
+        Err(ERROR_OPERATION_ABORTED.to_hresult())
+    );
+    /* the enumeration stays canceled */
+    assert_eq!(
+        library.next_entries(&enumeration, 1),
+        Err(ERROR_OPERATION_ABORTED.to_hresult())
+    );
+    library.end_enumeration(&enumeration);
+    assert_eq!(pfs.enumeration_stats().active_enumerations, 0);
+
+    /* enumerations started afterwards are not affected */
+    let entries = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?;
+    assert_eq!(entries.len(), 5);
+    Ok(())
+}
+
+/// Source answering existence checks from an index.
+struct IndexedSource {
+    lookups: Arc<atomic::AtomicUsize>,
+}
+
+impl ProjectedFileSystemSource for IndexedSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        vec![DirectoryEntry::file("known.txt", 4)]
+    }
+
+    fn get_directory_entry(&self, path: &Path) -> Option<DirectoryEntry> {
+        self.lookups.fetch_add(1, atomic::Ordering::Relaxed);
+        self.entry_at(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path == Path::new("known.txt")
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Err(io::ErrorKind::NotFound.into())
+    }
+}
+
+#[test]
+fn mock_exists_short_circuit() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_mock_exists_short_circuit")?;
+    let library = MockLibrary::new();
+    let lookups = Arc::new(atomic::AtomicUsize::new(0));
+    let _pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        IndexedSource {
+            lookups: lookups.clone(),
+        },
+        library.clone(),
+    )?;
+
+    assert_eq!(
+        library.placeholder_info("desktop.ini"),
+        ERROR_FILE_NOT_FOUND.to_hresult()
+    );
+    assert_eq!(lookups.load(atomic::Ordering::Relaxed), 0);
+
+    assert!(library.placeholder_info("known.txt").is_ok());
+    assert_eq!(lookups.load(atomic::Ordering::Relaxed), 1);
+    assert_eq!(library.placeholders.lock().unwrap()[0].file_size, 4);
+    Ok(())
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use std::path::Path;
+
+use common::MockLibrary;
+use futures::{
+    executor::block_on,
+    StreamExt,
+};
+use tempdir::TempDir;
+use windows::Win32::Storage::ProjectedFileSystem::{
+    PRJ_NOTIFICATION_FILE_OPENED,
+    PRJ_NOTIFICATION_NEW_FILE_CREATED,
+    PRJ_NOTIFICATION_PRE_DELETE,
+};
+use windows_projfs::{
+    FnSource,
+    Notification,
+    ProjectedFileSystem,
+};
+
+#[test]
+fn notification_stream() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_notification_stream")?;
+    let library = MockLibrary::new();
+    let pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), FnSource::new(), library.clone())?;
+    let notifications = pfs.notification_stream();
+
+    assert!(library
+        .notify("a.txt", false, PRJ_NOTIFICATION_FILE_OPENED)
+        .is_ok());
+    assert!(library
+        .notify("a.txt", false, PRJ_NOTIFICATION_PRE_DELETE)
+        .is_ok());
+    assert!(library
+        .notify("b.txt", false, PRJ_NOTIFICATION_NEW_FILE_CREATED)
+        .is_ok());
+
+    /* the stream ends once the projection has been dropped */
+    drop(pfs);
+    let notifications = block_on(notifications.collect::<Vec<_>>());
+
+    assert_eq!(notifications.len(), 2);
+    assert!(matches!(
+        &notifications[0],
+        Notification::FileOpened(file) if file.path == Path::new("a.txt")
+    ));
+    assert!(matches!(
+        &notifications[1],
+        Notification::FileCreated(file) if file.path == Path::new("b.txt")
+    ));
+    Ok(())
+}
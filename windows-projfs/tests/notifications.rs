@@ -0,0 +1,155 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{
+        Cursor,
+        Read,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::Duration,
+};
+
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    FileCloseAction,
+    FileRenameInfo,
+    Notification,
+    NotificationKind,
+    ProjectedFile,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+const FILE_CONTENT: &str = "Hello World!";
+
+struct TestProjectionSource;
+
+impl ProjectedFileSystemSource for TestProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            vec![DirectoryEntry::file("test.txt", FILE_CONTENT.len() as u64)]
+        } else {
+            vec![]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> std::io::Result<Box<dyn Read>> {
+        Ok(Box::new(Cursor::new(
+            FILE_CONTENT.as_bytes()[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+#[test]
+fn notification_channel() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_notification_channel")?;
+    let target_dir = target_dir.path();
+
+    let pfs = ProjectedFileSystem::new(target_dir, TestProjectionSource)?;
+    let notifications = pfs.notification_channel();
+
+    assert_eq!(
+        fs::read_to_string(target_dir.join("test.txt"))?,
+        FILE_CONTENT
+    );
+
+    let mut file_opened = false;
+    while let Ok(notification) = notifications.recv_timeout(Duration::from_secs(1)) {
+        assert!(!notification.is_cancelable());
+        if let Notification::FileOpened(file) = notification {
+            file_opened |= file.path == Path::new("test.txt");
+        }
+    }
+
+    assert!(file_opened);
+    Ok(())
+}
+
+#[test]
+fn rename_info_is_move() {
+    let rename = |source: Option<&str>, destination: Option<&str>| FileRenameInfo {
+        source: source.map(PathBuf::from),
+        destination: destination.map(PathBuf::from),
+        is_directory: false,
+    };
+
+    let info = rename(Some("dir\\a.txt"), Some("dir\\b.txt"));
+    assert!(!info.is_move());
+    assert_eq!(info.new_name(), Some(OsStr::new("b.txt")));
+
+    let info = rename(Some("a.txt"), Some("b.txt"));
+    assert!(!info.is_move());
+
+    let info = rename(Some("dir\\a.txt"), Some("other\\a.txt"));
+    assert!(info.is_move());
+    assert_eq!(info.new_name(), Some(OsStr::new("a.txt")));
+
+    let info = rename(Some("dir\\a.txt"), Some("a.txt"));
+    assert!(info.is_move());
+
+    let info = rename(None, Some("a.txt"));
+    assert!(info.is_move());
+    assert_eq!(info.new_name(), Some(OsStr::new("a.txt")));
+
+    let info = rename(Some("a.txt"), None);
+    assert!(info.is_move());
+    assert_eq!(info.new_name(), None);
+}
+
+#[test]
+fn notification_kind() {
+    let file = ProjectedFile {
+        file_id: 1,
+        is_directory: false,
+        path: PathBuf::from("test.txt"),
+    };
+    let rename = FileRenameInfo {
+        source: Some(PathBuf::from("a.txt")),
+        destination: Some(PathBuf::from("b.txt")),
+        is_directory: false,
+    };
+
+    let notifications = [
+        Notification::FileCreated(file.clone()),
+        Notification::FileOpened(file.clone()),
+        Notification::FileClosed(file.clone(), FileCloseAction::Modified),
+        Notification::FileOverwritten(file.clone()),
+        Notification::PreFileRename(rename.clone()),
+        Notification::FileRenamed(rename),
+        Notification::PreSetHardlink(file.clone()),
+        Notification::HardlinkCreated(file.clone()),
+        Notification::PreFileDelete(file.clone()),
+        Notification::FilePreConvertToFull(file),
+    ];
+
+    let kinds = notifications
+        .iter()
+        .map(Notification::kind)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, NotificationKind::ALL);
+
+    for notification in &notifications {
+        assert_eq!(
+            notification.is_cancelable(),
+            notification.kind().is_cancelable()
+        );
+    }
+    assert_eq!(
+        NotificationKind::ALL
+            .iter()
+            .filter(|kind| kind.is_cancelable())
+            .count(),
+        4
+    );
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use std::{
+    io::Cursor,
+    path::Path,
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    FnSource,
+    Overlay,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+fn content_source(content: &'static [u8]) -> FnSource {
+    FnSource::new().on_stream(move |_path, byte_offset, length| {
+        Ok(Box::new(Cursor::new(
+            content[byte_offset..byte_offset + length].to_vec(),
+        )))
+    })
+}
+
+fn test_overlay() -> Overlay<FnSource, FnSource> {
+    let upper = content_source(b"upper").on_list(|path| {
+        if path.as_os_str().is_empty() {
+            vec![
+                DirectoryEntry::file("shared.txt", 5),
+                DirectoryEntry::directory("conflict"),
+                DirectoryEntry::file("upper.txt", 5),
+            ]
+        } else {
+            vec![]
+        }
+    });
+    let lower = content_source(b"lower").on_list(|path| {
+        if path.as_os_str().is_empty() {
+            vec![
+                DirectoryEntry::file("SHARED.txt", 5),
+                DirectoryEntry::file("conflict", 5),
+                DirectoryEntry::file("lower.txt", 5),
+            ]
+        } else {
+            vec![]
+        }
+    });
+
+    Overlay::new(upper, lower)
+}
+
+#[test]
+fn overlay_entries() {
+    let overlay = test_overlay();
+
+    let mut names = overlay
+        .list_directory(Path::new(""))
+        .iter()
+        .map(|entry| entry.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(
+        names,
+        vec!["conflict", "lower.txt", "shared.txt", "upper.txt"]
+    );
+
+    /* the directory of the upper source hides the file of the lower source */
+    assert!(overlay
+        .get_directory_entry(Path::new("conflict"))
+        .is_some_and(|entry| entry.is_dir()));
+    assert!(overlay
+        .get_directory_entry(Path::new("lower.txt"))
+        .is_some_and(|entry| entry.is_file()));
+    assert!(overlay
+        .get_directory_entry(Path::new("missing.txt"))
+        .is_none());
+}
+
+#[test]
+fn overlay_name_collation() {
+    let list = |names: &'static [&'static str]| {
+        FnSource::new().on_list(move |_path| {
+            names
+                .iter()
+                .map(|name| DirectoryEntry::file(*name, 0))
+                .collect()
+        })
+    };
+
+    /* names only collide if ProjFS considers them equal (the Kelvin sign is not an uppercase k) */
+    let overlay = Overlay::new(
+        list(&["k.txt", "\u{00E4}.txt"]),
+        list(&["\u{212A}.txt", "\u{00C4}.TXT"]),
+    );
+    let mut names = overlay
+        .list_directory(Path::new(""))
+        .iter()
+        .map(|entry| entry.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["k.txt", "\u{00E4}.txt", "\u{212A}.txt"]);
+}
+
+#[test]
+fn overlay_file_content() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_overlay_file_content")?;
+    let library = MockLibrary::new();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), test_overlay(), library.clone())?;
+
+    let read = |path: &str| {
+        library
+            .read_file(path, 0, 5)
+            .map_err(windows::core::Error::from)
+    };
+    assert_eq!(read("shared.txt")?, b"upper");
+    assert_eq!(read("upper.txt")?, b"upper");
+    assert_eq!(read("lower.txt")?, b"lower");
+    Ok(())
+}
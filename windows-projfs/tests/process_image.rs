@@ -0,0 +1,34 @@
+use std::{
+    ffi::OsStr,
+    path::Path,
+};
+
+use windows_projfs::ProcessImage;
+
+#[test]
+fn process_image_names() {
+    let image = ProcessImage::new("\\Device\\HarddiskVolume3\\Windows\\explorer.exe");
+    assert_eq!(
+        image.nt_path(),
+        Path::new("\\Device\\HarddiskVolume3\\Windows\\explorer.exe")
+    );
+    assert_eq!(image.executable_name(), Some(OsStr::new("explorer.exe")));
+}
+
+#[test]
+fn process_image_win32_path() {
+    let image = ProcessImage::new("C:\\Windows\\explorer.exe");
+    assert_eq!(
+        image.win32_path().as_deref(),
+        Some(Path::new("C:\\Windows\\explorer.exe"))
+    );
+
+    let image = ProcessImage::new("\\??\\C:\\Windows\\explorer.exe");
+    assert_eq!(
+        image.win32_path().as_deref(),
+        Some(Path::new("C:\\Windows\\explorer.exe"))
+    );
+
+    let image = ProcessImage::new("\\Device\\DoesNotExist\\explorer.exe");
+    assert_eq!(image.win32_path(), None);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::{
+    io::{
+        self,
+        Cursor,
+        Read,
+    },
+    path::Path,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows_projfs::{
+    CallbackKind,
+    DirectoryEntry,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+    RawCallbackInfo,
+};
+
+#[derive(Default, Clone)]
+struct RecordingProjectionSource {
+    callbacks: Arc<Mutex<Vec<RawCallbackInfo>>>,
+}
+
+impl ProjectedFileSystemSource for RecordingProjectionSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        vec![
+            DirectoryEntry::file("a.txt", 4),
+            DirectoryEntry::file("b.txt", 4),
+        ]
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(Cursor::new(
+            b"test"[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+
+    fn on_raw_callback(&self, info: &RawCallbackInfo) {
+        self.callbacks.lock().unwrap().push(info.clone());
+    }
+}
+
+#[test]
+fn raw_callback_info() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_raw_callback_info")?;
+    let library = MockLibrary::new();
+    let source = RecordingProjectionSource::default();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), source.clone(), library.clone())?;
+
+    library
+        .enumerate_single_entries("", None)
+        .map_err(windows::core::Error::from)?;
+    {
+        let callbacks = source.callbacks.lock().unwrap();
+        let kinds = callbacks.iter().map(|info| info.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds.first(),
+            Some(&CallbackKind::StartDirectoryEnumeration)
+        );
+        assert_eq!(kinds.last(), Some(&CallbackKind::EndDirectoryEnumeration));
+
+        let enumerations = callbacks
+            .iter()
+            .filter(|info| info.kind == CallbackKind::GetDirectoryEnumeration)
+            .collect::<Vec<_>>();
+        assert!(!enumerations.is_empty());
+        assert!(enumerations
+            .iter()
+            .all(|info| info.has_flag(RawCallbackInfo::FLAG_ENUM_RETURN_SINGLE_ENTRY)));
+        assert!(enumerations
+            .iter()
+            .all(|info| !info.has_flag(RawCallbackInfo::FLAG_ENUM_RESTART_SCAN)));
+    }
+
+    source.callbacks.lock().unwrap().clear();
+    library
+        .read_file("a.txt", 0, 4)
+        .map_err(windows::core::Error::from)?;
+    {
+        let callbacks = source.callbacks.lock().unwrap();
+        assert_eq!(callbacks.len(), 1);
+        assert_eq!(callbacks[0].kind, CallbackKind::GetFileData);
+        assert_eq!(callbacks[0].flags, 0);
+        assert_eq!(callbacks[0].triggering_process_id, std::process::id());
+        assert_ne!(callbacks[0].data_stream_id, 0);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::{
+    fs,
+    io::{
+        Cursor,
+        Read,
+    },
+    path::Path,
+};
+
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+    ProjectionMode,
+};
+
+const FILE_CONTENT: &str = "Hello World!";
+
+struct TestProjectionSource;
+
+impl ProjectedFileSystemSource for TestProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            vec![
+                DirectoryEntry::file("test.txt", FILE_CONTENT.len() as u64),
+                DirectoryEntry::directory("sub-dir"),
+            ]
+        } else {
+            vec![]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> std::io::Result<Box<dyn Read>> {
+        Ok(Box::new(Cursor::new(
+            FILE_CONTENT.as_bytes()[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+#[test]
+fn read_only_rejects_delete() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_read_only_rejects_delete")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::builder()
+        .mode(ProjectionMode::ReadOnly)
+        .build(target_dir, TestProjectionSource)?;
+
+    assert!(fs::remove_file(target_dir.join("test.txt")).is_err());
+    assert!(fs::remove_dir(target_dir.join("sub-dir")).is_err());
+
+    assert_eq!(
+        fs::read_to_string(target_dir.join("test.txt"))?,
+        FILE_CONTENT
+    );
+    Ok(())
+}
+
+#[test]
+fn read_only_rejects_rename() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_read_only_rejects_rename")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::builder()
+        .mode(ProjectionMode::ReadOnly)
+        .build(target_dir, TestProjectionSource)?;
+
+    assert!(fs::rename(target_dir.join("test.txt"), target_dir.join("renamed.txt")).is_err());
+    assert!(target_dir.join("test.txt").exists());
+    assert!(!target_dir.join("renamed.txt").exists());
+    Ok(())
+}
+
+#[test]
+fn read_only_attributes() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_read_only_attributes")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::builder()
+        .mode(ProjectionMode::ReadOnly)
+        .read_only_attributes(true)
+        .build(target_dir, TestProjectionSource)?;
+
+    let metadata = fs::metadata(target_dir.join("test.txt"))?;
+    assert!(metadata.permissions().readonly());
+    Ok(())
+}
+
+#[test]
+fn read_write_allows_delete() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_read_write_allows_delete")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::builder()
+        .mode(ProjectionMode::ReadWrite)
+        .build(target_dir, TestProjectionSource)?;
+
+    fs::remove_file(target_dir.join("test.txt"))?;
+    assert!(!target_dir.join("test.txt").exists());
+    Ok(())
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::{
+    io::{
+        self,
+        Cursor,
+        Read,
+    },
+    path::Path,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+    RequestContext,
+};
+
+#[derive(Default, Clone)]
+struct RecordingProjectionSource {
+    contexts: Arc<Mutex<Vec<Option<RequestContext>>>>,
+}
+
+impl ProjectedFileSystemSource for RecordingProjectionSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        self.contexts
+            .lock()
+            .unwrap()
+            .push(RequestContext::current());
+        vec![DirectoryEntry::file("a.txt", 4)]
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        self.contexts
+            .lock()
+            .unwrap()
+            .push(RequestContext::current());
+        Ok(Box::new(Cursor::new(
+            b"test"[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+#[test]
+fn request_context_command_id() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_request_context")?;
+    let library = MockLibrary::new();
+    let source = RecordingProjectionSource::default();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), source.clone(), library.clone())?;
+
+    assert!(RequestContext::current().is_none());
+    library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?;
+    library
+        .read_file("a.txt", 0, 4)
+        .map_err(windows::core::Error::from)?;
+    assert!(RequestContext::current().is_none());
+
+    let contexts = source.contexts.lock().unwrap().clone();
+    assert_eq!(contexts.len(), 2);
+
+    let contexts = contexts
+        .into_iter()
+        .map(|context| context.expect("a request context to be set"))
+        .collect::<Vec<_>>();
+    for context in &contexts {
+        assert_eq!(context.triggering_process_id, std::process::id());
+    }
+    assert_ne!(contexts[0].command_id, contexts[1].command_id);
+    Ok(())
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use std::{
+    io::{
+        self,
+        Cursor,
+        Read,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows::Win32::{
+    Foundation::ERROR_INVALID_NAME,
+    Storage::ProjectedFileSystem::{
+        PRJ_NOTIFICATION_FILE_OPENED,
+        PRJ_NOTIFICATION_FILE_RENAMED,
+    },
+};
+use windows_projfs::{
+    DirectoryEntry,
+    Notification,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+#[derive(Default, Clone)]
+struct RecordingProjectionSource {
+    paths: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+impl ProjectedFileSystemSource for RecordingProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        self.paths.lock().unwrap().push(path.to_path_buf());
+        vec![
+            DirectoryEntry::directory("dir"),
+            DirectoryEntry::file("file.txt", 4),
+        ]
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        self.paths.lock().unwrap().push(path.to_path_buf());
+        Ok(Box::new(Cursor::new(
+            b"test"[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+#[test]
+fn request_path_traversal() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_request_path_traversal")?;
+    let library = MockLibrary::new();
+    let source = RecordingProjectionSource::default();
+    let _pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), source.clone(), library.clone())?;
+
+    let invalid_name = ERROR_INVALID_NAME.to_hresult();
+    for path in [
+        "..",
+        "..\\file.txt",
+        "dir\\..\\..\\file.txt",
+        "dir/../file.txt",
+        "dir\\..",
+    ] {
+        assert_eq!(library.placeholder_info(path), invalid_name, "{}", path);
+        assert_eq!(library.read_file(path, 0, 4), Err(invalid_name), "{}", path);
+        assert_eq!(
+            library.enumerate(path, None, 100),
+            Err(invalid_name),
+            "{}",
+            path
+        );
+        assert_eq!(
+            library.notify(path, false, PRJ_NOTIFICATION_FILE_OPENED),
+            invalid_name,
+            "{}",
+            path
+        );
+    }
+
+    /* the source must never see any of the paths */
+    assert!(source.paths.lock().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn request_path_normalization() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_request_path_normalization")?;
+    let library = MockLibrary::new();
+    let source = RecordingProjectionSource::default();
+    let pfs =
+        ProjectedFileSystem::new_with_library(target_dir.path(), source.clone(), library.clone())?;
+    let notifications = pfs.notification_channel();
+
+    assert!(library.placeholder_info("dir/./file.txt").is_ok());
+    assert!(library.read_file(".\\dir//file.txt", 0, 4).is_ok());
+    assert!(library.enumerate("dir\\", None, 100).is_ok());
+    assert_eq!(
+        *source.paths.lock().unwrap(),
+        vec![
+            PathBuf::from("dir"),
+            PathBuf::from("dir\\file.txt"),
+            PathBuf::from("dir"),
+        ]
+    );
+
+    /* renames to a path outside of the root are reported without a destination */
+    assert!(library
+        .notify_with_destination(
+            "file.txt",
+            Some("..\\file.txt"),
+            false,
+            PRJ_NOTIFICATION_FILE_RENAMED
+        )
+        .is_ok());
+    match notifications.try_recv()? {
+        Notification::FileRenamed(info) => {
+            assert_eq!(info.source, Some(PathBuf::from("file.txt")));
+            assert_eq!(info.destination, None);
+        }
+        notification => panic!("unexpected notification {:?}", notification),
+    }
+    Ok(())
+}
@@ -0,0 +1,208 @@
+mod common;
+
+use std::{
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+use tempdir::TempDir;
+use windows::Win32::Foundation::E_FAIL;
+use windows_projfs::{
+    DirectoryEntry,
+    Error,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+struct EmptyProjectionSource;
+
+impl ProjectedFileSystemSource for EmptyProjectionSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        vec![]
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> std::io::Result<Box<dyn std::io::prelude::Read>> {
+        panic!("file contents should not be requested")
+    }
+}
+
+#[test]
+fn root_missing() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let temp_dir = TempDir::new("test_root_missing")?;
+    let target_dir = temp_dir.path().join("root");
+
+    let _pfs = ProjectedFileSystem::new(&target_dir, EmptyProjectionSource)?;
+    assert!(target_dir.is_dir());
+    Ok(())
+}
+
+#[test]
+fn root_missing_not_created() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let temp_dir = TempDir::new("test_root_missing_not_created")?;
+    let target_dir = temp_dir.path().join("root");
+
+    let result = ProjectedFileSystem::builder()
+        .create_root(false)
+        .build(&target_dir, EmptyProjectionSource);
+
+    assert!(matches!(result, Err(Error::RootNotFound(_))));
+    assert!(!target_dir.exists());
+    Ok(())
+}
+
+#[test]
+fn root_empty() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_root_empty")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::new(target_dir, EmptyProjectionSource)?;
+    Ok(())
+}
+
+#[test]
+fn root_not_empty() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_root_not_empty")?;
+    let target_dir = target_dir.path();
+    fs::write(target_dir.join("existing.txt"), "Hello World")?;
+
+    let result = ProjectedFileSystem::new(target_dir, EmptyProjectionSource);
+    assert!(matches!(result, Err(Error::RootNotEmpty(_))));
+    Ok(())
+}
+
+#[test]
+fn root_not_empty_instance_id() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_root_not_empty_instance_id")?;
+    let target_dir = target_dir.path();
+    fs::write(target_dir.join("existing.txt"), "Hello World")?;
+
+    /* only a previous virtualization root may be reused while not being empty */
+    let result = ProjectedFileSystem::builder()
+        .instance_id(0x1234)
+        .build(target_dir, EmptyProjectionSource);
+    assert!(matches!(result, Err(Error::RootNotEmpty(_))));
+    Ok(())
+}
+
+#[test]
+fn root_removed_on_drop() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let temp_dir = TempDir::new("test_root_removed_on_drop")?;
+    let target_dir = temp_dir.path().join("root");
+
+    let pfs = ProjectedFileSystem::builder()
+        .remove_root_on_drop(true)
+        .build(&target_dir, EmptyProjectionSource)?;
+    assert!(target_dir.is_dir());
+
+    drop(pfs);
+    assert!(!target_dir.exists());
+    Ok(())
+}
+
+#[test]
+fn root_start_failure_unmarks_root() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_root_start_failure_unmarks_root")?;
+    let target_dir = target_dir.path();
+
+    let mut library = common::FaultInjectingLibrary::new()?;
+    library.fail_start = Some(E_FAIL);
+
+    let result = ProjectedFileSystem::builder()
+        .library(Arc::new(library))
+        .build(target_dir, EmptyProjectionSource);
+    assert!(matches!(result, Err(Error::StartProjection(_))));
+
+    /* the root must be usable for another attempt */
+    let _pfs = ProjectedFileSystem::new(target_dir, EmptyProjectionSource)?;
+    Ok(())
+}
+
+#[test]
+fn root_invalid_path() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let temp_dir = TempDir::new("test_root_invalid_path")?;
+    for name in ["NUL", "con.txt", "stream:data", "pipe|name", "trailing."] {
+        let target_dir = temp_dir.path().join(name);
+        let result = ProjectedFileSystem::new(&target_dir, EmptyProjectionSource);
+        assert!(
+            matches!(result, Err(Error::InvalidRootPath(_))),
+            "{} should be rejected",
+            name
+        );
+    }
+
+    assert!(fs::read_dir(temp_dir.path())?.next().is_none());
+
+    let target_file = temp_dir.path().join("file.txt");
+    fs::write(&target_file, "Not a directory")?;
+    let result = ProjectedFileSystem::new(&target_file, EmptyProjectionSource);
+    assert!(matches!(result, Err(Error::InvalidRootPath(_))));
+    Ok(())
+}
+
+#[test]
+fn root_custom_thread_counts() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_root_custom_thread_counts")?;
+    let target_dir = target_dir.path();
+
+    let _pfs = ProjectedFileSystem::builder()
+        .pool_threads(4)
+        .concurrent_threads(2)
+        .build(target_dir, EmptyProjectionSource)?;
+    assert_eq!(fs::read_dir(target_dir)?.count(), 0);
+    Ok(())
+}
+
+#[test]
+fn root_deeply_nested() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_root_deeply_nested")?;
+    let root = (0..12).fold(target_dir.path().to_path_buf(), |path, index| {
+        path.join(format!("nested directory {}", index))
+    });
+    assert!(root.as_os_str().len() > 260);
+
+    let pfs = ProjectedFileSystem::new(&root, EmptyProjectionSource)?;
+    assert_eq!(pfs.root(), root);
+    assert_eq!(fs::read_dir(&root)?.count(), 0);
+    Ok(())
+}
+
+#[test]
+fn root_unnormalized_path() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_root_unnormalized_path")?;
+    let root = target_dir.path().join("root");
+    let unnormalized_root = target_dir.path().join("other").join("..").join("root");
+
+    let pfs = ProjectedFileSystem::new(&unnormalized_root, EmptyProjectionSource)?;
+    assert!(pfs.root().is_absolute());
+    assert_eq!(pfs.root(), root);
+    assert!(root.is_dir());
+    Ok(())
+}
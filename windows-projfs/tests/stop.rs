@@ -0,0 +1,158 @@
+use std::{
+    fs,
+    io::{
+        self,
+        Cursor,
+        Read,
+    },
+    path::Path,
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        mpsc::{
+            self,
+            Receiver,
+            Sender,
+        },
+        Arc,
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    Error,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+/// Source which blocks reading file contents until it gets released.
+struct BlockingProjectionSource {
+    reading: Mutex<Sender<()>>,
+    release: Mutex<Receiver<()>>,
+}
+
+impl ProjectedFileSystemSource for BlockingProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            vec![DirectoryEntry::file("test.txt", 4)]
+        } else {
+            vec![]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        let _ = self.reading.lock().unwrap().send(());
+        let _ = self.release.lock().unwrap().recv();
+        Ok(Box::new(Cursor::new(
+            b"test"[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+fn blocking_source() -> (BlockingProjectionSource, Receiver<()>, Sender<()>) {
+    let (reading_tx, reading_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel();
+    let source = BlockingProjectionSource {
+        reading: Mutex::new(reading_tx),
+        release: Mutex::new(release_rx),
+    };
+    (source, reading_rx, release_tx)
+}
+
+#[test]
+fn stop_with_timeout() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_stop_with_timeout")?;
+    let (source, _reading, _release) = blocking_source();
+    let pfs = ProjectedFileSystem::new(target_dir.path(), source)?;
+
+    pfs.stop_with_timeout(Duration::from_secs(5))?;
+    Ok(())
+}
+
+#[test]
+fn stop_with_timeout_stuck_source() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_stop_with_timeout_stuck_source")?;
+    let (source, reading, release) = blocking_source();
+    let pfs = ProjectedFileSystem::new(target_dir.path(), source)?;
+
+    let file = target_dir.path().join("test.txt");
+    let reader = thread::spawn(move || fs::read(file));
+    reading.recv_timeout(Duration::from_secs(5))?;
+
+    let result = pfs.stop_with_timeout(Duration::from_millis(100));
+    assert!(matches!(result, Err(Error::StopTimeout)));
+
+    /* the projection finishes stopping once the source has been released */
+    release.send(())?;
+    let _ = reader.join();
+    Ok(())
+}
+
+#[derive(Default, Clone)]
+struct StopTrackingSource {
+    stops: Arc<AtomicUsize>,
+    stop_threads: Arc<Mutex<Vec<thread::ThreadId>>>,
+}
+
+impl ProjectedFileSystemSource for StopTrackingSource {
+    fn list_directory(&self, _path: &Path) -> Vec<DirectoryEntry> {
+        vec![]
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        _byte_offset: usize,
+        _length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Err(io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    fn on_stop(&self) {
+        self.stops.fetch_add(1, Ordering::Relaxed);
+        self.stop_threads
+            .lock()
+            .unwrap()
+            .push(thread::current().id());
+    }
+}
+
+#[test]
+fn stop_notifies_source() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_stop_notifies_source")?;
+    let source = StopTrackingSource::default();
+
+    let pfs = ProjectedFileSystem::new(target_dir.path(), source.clone())?;
+    assert_eq!(source.stops.load(Ordering::Relaxed), 0);
+    drop(pfs);
+    assert_eq!(source.stops.load(Ordering::Relaxed), 1);
+
+    let target_dir = TempDir::new("test_stop_notifies_source")?;
+    let pfs = ProjectedFileSystem::new(target_dir.path(), source.clone())?;
+    pfs.stop_with_timeout(Duration::from_secs(5))?;
+    assert_eq!(source.stops.load(Ordering::Relaxed), 2);
+
+    /* the source is never released by the helper thread stopping the projection */
+    assert_eq!(
+        *source.stop_threads.lock().unwrap(),
+        vec![thread::current().id(); 2]
+    );
+    Ok(())
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use std::{
+    io::{
+        self,
+        Cursor,
+        Read,
+    },
+    path::Path,
+};
+
+use common::MockLibrary;
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+const NESTED_DIRECTORY: &str = "nested directory with a long name";
+
+struct UnicodeProjectionSource;
+
+impl ProjectedFileSystemSource for UnicodeProjectionSource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            vec![
+                DirectoryEntry::file("日本語のファイル.txt", 4),
+                DirectoryEntry::file("e\u{301}te\u{301}.txt", 4),
+                DirectoryEntry::file("with spaces .txt", 4),
+                DirectoryEntry::directory("目录"),
+                DirectoryEntry::directory(NESTED_DIRECTORY),
+            ]
+        } else {
+            vec![
+                DirectoryEntry::file("文件.txt", 4),
+                DirectoryEntry::directory(NESTED_DIRECTORY),
+            ]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        if self.get_directory_entry(path).is_none() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        Ok(Box::new(Cursor::new(
+            b"test"[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+}
+
+#[test]
+fn unicode_names() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_unicode_names")?;
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::new_with_library(
+        target_dir.path(),
+        UnicodeProjectionSource,
+        library.clone(),
+    )?;
+
+    let mut names = library
+        .enumerate("", None, 100)
+        .map_err(windows::core::Error::from)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    names.sort();
+
+    let mut expected = UnicodeProjectionSource
+        .list_directory(Path::new(""))
+        .iter()
+        .map(|entry| entry.name().to_string())
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(names, expected);
+
+    for name in &expected {
+        assert!(library.placeholder_info(name).is_ok(), "{}", name);
+    }
+    assert!(library.placeholder_info("目录\\文件.txt").is_ok());
+    assert_eq!(
+        library
+            .placeholders
+            .lock()
+            .unwrap()
+            .last()
+            .map(|placeholder| placeholder.name.clone()),
+        Some("目录\\文件.txt".to_string())
+    );
+
+    assert_eq!(
+        library
+            .read_file("e\u{301}te\u{301}.txt", 0, 4)
+            .map_err(windows::core::Error::from)?,
+        b"test"
+    );
+    Ok(())
+}
+
+#[test]
+fn long_paths() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_long_paths")?;
+    let root = (0..8).fold(target_dir.path().to_path_buf(), |path, _| {
+        path.join(NESTED_DIRECTORY)
+    });
+    assert!(root.as_os_str().len() > 260);
+
+    let library = MockLibrary::new();
+    let _pfs = ProjectedFileSystem::builder()
+        .library(library.clone())
+        .create_root(true)
+        .build(&root, UnicodeProjectionSource)?;
+
+    let virtualization_root = library
+        .virtualization_root
+        .lock()
+        .unwrap()
+        .clone()
+        .expect("the root to be set");
+    assert!(virtualization_root.starts_with("\\\\?\\"));
+    assert!(virtualization_root.ends_with(NESTED_DIRECTORY));
+
+    /* paths within the projection are relative to the root and may exceed MAX_PATH as well */
+    let nested = [NESTED_DIRECTORY; 8].join("\\");
+    let file = format!("{}\\文件.txt", nested);
+    assert!(file.len() > 260);
+    assert!(library.placeholder_info(&file).is_ok());
+    assert_eq!(
+        library
+            .read_file(&file, 0, 4)
+            .map_err(windows::core::Error::from)?,
+        b"test"
+    );
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::{
+    fs,
+    io::{
+        self,
+        Cursor,
+        Read,
+    },
+    ops::ControlFlow,
+    path::Path,
+};
+
+use tempdir::TempDir;
+use windows_projfs::{
+    DirectoryEntry,
+    Error,
+    Notification,
+    NotificationVeto,
+    ProjectedFileSystem,
+    ProjectedFileSystemSource,
+};
+
+const FILE_CONTENT: &str = "Hello World!";
+
+/// Source rejecting every modification of its entries.
+struct ReadOnlySource;
+
+impl ProjectedFileSystemSource for ReadOnlySource {
+    fn list_directory(&self, path: &Path) -> Vec<DirectoryEntry> {
+        if path.as_os_str().is_empty() {
+            vec![DirectoryEntry::file("test.txt", FILE_CONTENT.len() as u64)]
+        } else {
+            vec![]
+        }
+    }
+
+    fn stream_file_content(
+        &self,
+        _path: &Path,
+        byte_offset: usize,
+        length: usize,
+    ) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(Cursor::new(
+            FILE_CONTENT.as_bytes()[byte_offset..byte_offset + length].to_vec(),
+        )))
+    }
+
+    fn handle_notification(&self, notification: &Notification) -> ControlFlow<NotificationVeto> {
+        if notification.is_cancelable() {
+            ControlFlow::Break(().into())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+/// Start the projection or return `None` if ProjFS is not available on this system.
+fn start_projection(root: &Path) -> anyhow::Result<Option<ProjectedFileSystem>> {
+    match ProjectedFileSystem::new(root, ReadOnlySource) {
+        Ok(pfs) => Ok(Some(pfs)),
+        Err(Error::WindowsFeatureNotEnabled) => {
+            eprintln!("Skipping test as ProjFS is not available");
+            Ok(None)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[test]
+fn veto_delete() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_veto_delete")?;
+    let target_dir = target_dir.path();
+    let Some(_pfs) = start_projection(target_dir)? else {
+        return Ok(());
+    };
+
+    let file = target_dir.join("test.txt");
+    assert_eq!(fs::read_to_string(&file)?, FILE_CONTENT);
+
+    let error = fs::remove_file(&file).expect_err("delete to be rejected");
+    assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+
+    assert_eq!(fs::read_to_string(&file)?, FILE_CONTENT);
+    Ok(())
+}
+
+#[test]
+fn veto_rename() -> anyhow::Result<()> {
+    let _ = env_logger::try_init();
+
+    let target_dir = TempDir::new("test_veto_rename")?;
+    let target_dir = target_dir.path();
+    let Some(_pfs) = start_projection(target_dir)? else {
+        return Ok(());
+    };
+
+    let file = target_dir.join("test.txt");
+    assert_eq!(fs::read_to_string(&file)?, FILE_CONTENT);
+
+    let error =
+        fs::rename(&file, target_dir.join("renamed.txt")).expect_err("rename to be rejected");
+    assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+
+    assert_eq!(fs::read_to_string(&file)?, FILE_CONTENT);
+    assert!(!target_dir.join("renamed.txt").exists());
+    Ok(())
+}